@@ -0,0 +1,77 @@
+-- CreateEnum
+CREATE TYPE "public"."EscrowStatus" AS ENUM ('ACTIVE', 'WITHDRAWN', 'CANCELLED');
+
+-- AlterTable
+ALTER TABLE "public"."order_fills" ALTER COLUMN "filledBy" SET DATA TYPE VARCHAR(255);
+
+-- CreateTable
+CREATE TABLE "public"."stellar_escrows" (
+    "orderHash" VARCHAR(66) NOT NULL,
+    "contractId" VARCHAR(56) NOT NULL,
+    "maker" VARCHAR(56) NOT NULL,
+    "taker" VARCHAR(56) NOT NULL,
+    "receiver" VARCHAR(56) NOT NULL,
+    "token" VARCHAR(56) NOT NULL,
+    "amount" DECIMAL(78,18) NOT NULL,
+    "releasedAmount" DECIMAL(78,18) NOT NULL DEFAULT 0,
+    "refundedAmount" DECIMAL(78,18) NOT NULL DEFAULT 0,
+    "hashlock" VARCHAR(66) NOT NULL,
+    "hashAlgorithm" VARCHAR(16) NOT NULL,
+    "isSrc" BOOLEAN NOT NULL,
+    "parts" INTEGER NOT NULL,
+    "status" "public"."EscrowStatus" NOT NULL DEFAULT 'ACTIVE',
+    "safetyDepositClaimedBy" VARCHAR(56),
+    "createdLedger" INTEGER NOT NULL,
+    "updatedLedger" INTEGER NOT NULL,
+    "createdAt" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" TIMESTAMPTZ NOT NULL,
+
+    CONSTRAINT "stellar_escrows_pkey" PRIMARY KEY ("orderHash")
+);
+
+-- CreateTable
+CREATE TABLE "public"."stellar_escrow_fills" (
+    "eventId" VARCHAR(255) NOT NULL,
+    "orderHash" VARCHAR(66) NOT NULL,
+    "recipient" VARCHAR(56) NOT NULL,
+    "amount" DECIMAL(78,18) NOT NULL,
+    "preimage" VARCHAR(66) NOT NULL,
+    "txHash" VARCHAR(255) NOT NULL,
+    "ledger" INTEGER NOT NULL,
+    "createdAt" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "stellar_escrow_fills_pkey" PRIMARY KEY ("eventId")
+);
+
+-- CreateTable
+CREATE TABLE "public"."stellar_secrets" (
+    "eventId" VARCHAR(255) NOT NULL,
+    "orderHash" VARCHAR(66) NOT NULL,
+    "partIdx" INTEGER NOT NULL,
+    "txHash" VARCHAR(255) NOT NULL,
+    "ledger" INTEGER NOT NULL,
+    "createdAt" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "stellar_secrets_pkey" PRIMARY KEY ("eventId")
+);
+
+-- CreateTable
+CREATE TABLE "public"."indexer_cursors" (
+    "name" VARCHAR(255) NOT NULL,
+    "cursor" VARCHAR(255) NOT NULL,
+    "updatedAt" TIMESTAMPTZ NOT NULL,
+
+    CONSTRAINT "indexer_cursors_pkey" PRIMARY KEY ("name")
+);
+
+-- CreateIndex
+CREATE INDEX "stellar_escrows_status_idx" ON "public"."stellar_escrows"("status");
+
+-- CreateIndex
+CREATE INDEX "stellar_escrows_maker_idx" ON "public"."stellar_escrows"("maker");
+
+-- CreateIndex
+CREATE INDEX "stellar_escrow_fills_orderHash_idx" ON "public"."stellar_escrow_fills"("orderHash");
+
+-- CreateIndex
+CREATE INDEX "stellar_secrets_orderHash_idx" ON "public"."stellar_secrets"("orderHash");
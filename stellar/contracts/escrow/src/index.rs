@@ -0,0 +1,139 @@
+use soroban_sdk::{contracttype, Address, BytesN, Env, Vec};
+
+use crate::settlement;
+use crate::{DataKey, Escrow};
+
+/// Largest page returned by the enumeration views.
+pub(crate) const MAX_PAGE_SIZE: u32 = 50;
+
+/// Secondary indexes over open escrows.
+///
+/// Each index is stored as a dense list (`IndexItem`) with a reverse lookup
+/// (`IndexPos`) so finalized escrows can be swap-removed without scanning.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EscrowIndex {
+    Maker(Address),
+    Taker(Address),
+    Active,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowPage {
+    pub order_hashes: Vec<BytesN<32>>,
+    pub next_cursor: Option<u32>,
+    pub total: u32,
+}
+
+fn read_len(env: &Env, index: &EscrowIndex) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::IndexLen(index.clone()))
+        .unwrap_or(0)
+}
+
+fn write_len(env: &Env, index: &EscrowIndex, len: u32) {
+    let key = DataKey::IndexLen(index.clone());
+    if len == 0 {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, &len);
+    }
+}
+
+fn insert(env: &Env, index: EscrowIndex, order_hash: &BytesN<32>) {
+    let len = read_len(env, &index);
+    let storage = env.storage().persistent();
+    storage.set(&DataKey::IndexItem(index.clone(), len), order_hash);
+    storage.set(&DataKey::IndexPos(index.clone(), order_hash.clone()), &len);
+    write_len(env, &index, len + 1);
+}
+
+fn remove(env: &Env, index: EscrowIndex, order_hash: &BytesN<32>) {
+    let storage = env.storage().persistent();
+    let pos_key = DataKey::IndexPos(index.clone(), order_hash.clone());
+    let Some(pos) = storage.get::<_, u32>(&pos_key) else {
+        return;
+    };
+    storage.remove(&pos_key);
+
+    // Move the last entry into the freed slot
+    let last = read_len(env, &index) - 1;
+    let last_key = DataKey::IndexItem(index.clone(), last);
+    if pos != last {
+        let moved: BytesN<32> = storage.get(&last_key).unwrap();
+        let item_key = DataKey::IndexItem(index.clone(), pos);
+        let moved_pos_key = DataKey::IndexPos(index.clone(), moved.clone());
+        storage.set(&item_key, &moved);
+        storage.set(&moved_pos_key, &pos);
+
+        // The freed slot was sized for the removed escrow
+        let moved_escrow = settlement::load_escrow(env, &moved).unwrap();
+        let ledgers = settlement::ttl_ledgers(env, &moved_escrow);
+        storage.extend_ttl(&item_key, ledgers, ledgers);
+        storage.extend_ttl(&moved_pos_key, ledgers, ledgers);
+    }
+    storage.remove(&last_key);
+    write_len(env, &index, last);
+}
+
+/// Record a newly created escrow in every index.
+pub fn add_escrow(env: &Env, order_hash: &BytesN<32>, escrow: &Escrow) {
+    insert(env, EscrowIndex::Maker(escrow.maker.clone()), order_hash);
+    insert(env, EscrowIndex::Taker(escrow.taker.clone()), order_hash);
+    insert(env, EscrowIndex::Active, order_hash);
+}
+
+fn extend(env: &Env, index: EscrowIndex, order_hash: &BytesN<32>, ledgers: u32) {
+    let storage = env.storage().persistent();
+    let pos_key = DataKey::IndexPos(index.clone(), order_hash.clone());
+    let Some(pos) = storage.get::<_, u32>(&pos_key) else {
+        return;
+    };
+    storage.extend_ttl(&pos_key, ledgers, ledgers);
+    storage.extend_ttl(&DataKey::IndexItem(index.clone(), pos), ledgers, ledgers);
+    storage.extend_ttl(&DataKey::IndexLen(index), ledgers, ledgers);
+}
+
+/// Keep the index entries of an open escrow alive for `ledgers`.
+///
+/// `IndexLen` is shared by the whole index and is never shortened, so it
+/// outlives every escrow still listed in it.
+pub fn extend_escrow(env: &Env, order_hash: &BytesN<32>, escrow: &Escrow, ledgers: u32) {
+    extend(env, EscrowIndex::Maker(escrow.maker.clone()), order_hash, ledgers);
+    extend(env, EscrowIndex::Taker(escrow.taker.clone()), order_hash, ledgers);
+    extend(env, EscrowIndex::Active, order_hash, ledgers);
+}
+
+/// Drop a finalized escrow from every index.
+pub fn remove_escrow(env: &Env, order_hash: &BytesN<32>, escrow: &Escrow) {
+    remove(env, EscrowIndex::Maker(escrow.maker.clone()), order_hash);
+    remove(env, EscrowIndex::Taker(escrow.taker.clone()), order_hash);
+    remove(env, EscrowIndex::Active, order_hash);
+}
+
+/// Read up to `limit` order hashes starting at position `cursor`.
+///
+/// Removals swap the last entry into the freed slot, so an escrow finalized
+/// between two page reads can shift one later entry to an earlier position.
+pub fn page(env: &Env, index: EscrowIndex, cursor: u32, limit: u32) -> EscrowPage {
+    let total = read_len(env, &index);
+    let end = cursor.saturating_add(limit.min(MAX_PAGE_SIZE)).min(total);
+
+    let mut order_hashes = Vec::new(env);
+    for pos in cursor..end {
+        let order_hash: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::IndexItem(index.clone(), pos))
+            .unwrap();
+        order_hashes.push_back(order_hash);
+    }
+
+    EscrowPage {
+        order_hashes,
+        next_cursor: if end < total { Some(end) } else { None },
+        total,
+    }
+}
@@ -1,13 +1,30 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Bytes, BytesN, Env, Vec,
-    symbol_short,
+    contract, contracterror, contractimpl, contracttype, panic_with_error, token, Address,
+    Bytes, BytesN, Env, Vec, symbol_short,
 };
 use soroban_sdk::xdr::ToXdr;
 
-// Import crypto utilities
-use stellar_contract_utils::crypto::keccak::Keccak256;
-use stellar_contract_utils::crypto::merkle::Verifier;
+mod index;
+mod resolvers;
+mod schedule;
+mod settlement;
+mod upgrade;
+mod test;
+
+pub use index::{EscrowIndex, EscrowPage};
+pub use resolvers::AllowedResolvers;
+pub use schedule::{Schedule, Stage, StageWindow};
+
+use settlement::{Payouts, Settlement};
+
+use upgrade::{
+    migrate, read_admin, read_pending_upgrade, read_storage_version, remove_pending_upgrade,
+    write_admin, write_pending_upgrade, write_storage_version, MAX_RESCUE_DELAY,
+    STORAGE_VERSION, UPGRADE_DELAY,
+};
+pub use upgrade::PendingUpgrade;
 
 // Stage constants
 const STAGE_FINALITY: u32 = 0;
@@ -16,16 +33,73 @@ const STAGE_SRC_PUBLIC_WITHDRAWAL: u32 = 2;
 const STAGE_SRC_CANCELLATION: u32 = 3;
 const STAGE_SRC_PUBLIC_CANCELLATION: u32 = 4;
 
+/// Most parts an escrow can be filled in. It bounds the `used_parts` flags
+/// kept per escrow, and its `MAX_PARTS + 1` secrets fit a tree of depth 8.
+pub const MAX_PARTS: u32 = 255;
+
+/// Error codes
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum EscrowError {
+    EscrowNotFound = 1,
+    AlreadyFinalized = 2,
+    InvalidPreimage = 3,
+    InvalidFillIndex = 4,
+    PartAlreadyUsed = 5,
+    InvalidMerkleProof = 6,
+    StageNotReached = 7,
+    StageExpired = 8,
+    Unauthorized = 9,
+    InvalidTimelocks = 10,
+    UnsafeTimelocks = 11,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct OrderHash {
     pub value: BytesN<32>,
 }
 
+/// Hash function the secret is checked with
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HashAlgorithm {
+    /// Matches the EVM escrows
+    Keccak256,
+    /// Matches Lightning-style HTLCs
+    Sha256,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct HashLock {
     pub value: BytesN<32>,
+    pub algorithm: HashAlgorithm,
+}
+
+/// Lifecycle of an escrow; terminal entries are kept in storage as tombstones
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscrowStatus {
+    Active,
+    PartiallyFilled,
+    Withdrawn,
+    Cancelled,
+    PublicCancelled,
+}
+
+impl EscrowStatus {
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, EscrowStatus::Active | EscrowStatus::PartiallyFilled)
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenAmount {
+    pub token: Address,
+    pub amount: i128,
 }
 
 #[contracttype]
@@ -33,23 +107,37 @@ pub struct HashLock {
 pub struct Escrow {
     pub maker: Address,
     pub taker: Address,
+    /// Receives the funds released from a destination escrow
+    pub receiver: Address,
+    /// Deposited the funds, gets the safety deposit back
+    pub funder: Address,
     pub token: Address,
     pub amount: i128,
+    /// Extra legs locked under the same hashlock, released with `token`
+    pub bundle: Vec<TokenAmount>,
     pub safety_deposit: i128,
     pub hashlock: HashLock,
     pub timelocks: Vec<u64>,
-    pub claimed: bool,
-    pub cancelled: bool,
+    pub status: EscrowStatus,
     pub is_src: bool,
     pub merkle_root: BytesN<32>,
     pub parts: u32,
+    /// Fill indexes revealed so far, one per secret
     pub used_parts: Vec<bool>,
+    /// Cancellation time of the paired source escrow, destination escrows only
+    pub src_cancellation: Option<u64>,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataKey {
     Escrow(BytesN<32>), 
+    Admin,
+    PendingUpgrade,
+    StorageVersion,
+    IndexLen(EscrowIndex),
+    IndexItem(EscrowIndex, u32),
+    IndexPos(EscrowIndex, BytesN<32>),
 }
 
 #[contracttype]
@@ -58,24 +146,31 @@ pub struct EscrowCreatedEvent {
     pub order_hash: BytesN<32>,
     pub maker: Address,
     pub taker: Address,
+    pub receiver: Address,
+    pub funder: Address,
     pub token: Address,
     pub amount: i128,
     pub hashlock: BytesN<32>,
+    pub hash_algorithm: HashAlgorithm,
     pub timelocks: Vec<u64>,
     pub is_src: bool,
     pub merkle_root: BytesN<32>,
     pub parts: u32,
+    pub legs: Vec<TokenAmount>,
+    /// Source cancellation time a destination escrow was checked against
+    pub src_cancellation: Option<u64>,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct FundsClaimedEvent {
     pub order_hash: BytesN<32>,
-    pub preimage: Bytes,
+    pub preimage: BytesN<32>,
     pub recipient: Address,
     pub amount: i128,
     pub merkle_root: BytesN<32>,
     pub parts: u32,
+    pub legs: Vec<TokenAmount>,
 }
 
 #[contracttype]
@@ -86,6 +181,9 @@ pub struct FundsRefundedEvent {
     pub amount: i128,
     pub merkle_root: BytesN<32>,
     pub parts: u32,
+    pub legs: Vec<TokenAmount>,
+    /// `Cancelled` by the taker or `PublicCancelled` by an executor
+    pub status: EscrowStatus,
 }
 
 #[contracttype]
@@ -96,482 +194,394 @@ pub struct SafetyDepositClaimedEvent {
     pub amount: i128,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimelocksExtendedEvent {
+    pub order_hash: BytesN<32>,
+    pub old_timelocks: Vec<u64>,
+    pub new_timelocks: Vec<u64>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradeScheduledEvent {
+    pub wasm_hash: BytesN<32>,
+    pub eta: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradeCancelledEvent {
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpgradeAppliedEvent {
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StorageMigratedEvent {
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminChangedEvent {
+    pub old_admin: Address,
+    pub new_admin: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreateEscrowArgs {
+    pub maker: Address,
+    pub taker: Address,
+    /// Destination escrow payee, defaults to the maker
+    pub receiver: Option<Address>,
+    pub token: Address,
+    pub amount: i128,
+    /// Extra legs locked with `token`, not allowed with partial fills
+    pub bundle: Vec<TokenAmount>,
+    pub safety_deposit: i128,
+    pub hashlock: BytesN<32>,
+    pub hash_algorithm: HashAlgorithm,
+    pub rescue_delay: u32,
+    pub is_src: bool,
+    /// Root of the tree over the hash of each fill level's secret, checked
+    /// instead of `hashlock` when `parts` is more than one. It must then equal
+    /// `hashlock`, which the order hash commits to
+    pub merkle_root: BytesN<32>,
+    /// Number of parts the order can be filled in, with `parts + 1` secrets
+    /// when more than one as on EVM
+    pub parts: u32,
+    /// Lets any listed resolver become the taker of a source escrow
+    pub resolvers: AllowedResolvers,
+    /// Proof of `taker` for `AllowedResolvers::MerkleRoot`
+    pub resolver_proof: Vec<BytesN<32>>,
+    /// Cancellation time of the paired source escrow, required for
+    /// destination escrows, which must become cancellable early enough
+    pub src_cancellation: Option<u64>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawArgs {
+    pub order_hash: BytesN<32>,
+    pub preimage: BytesN<32>,
+    pub merkle_proof: Vec<BytesN<32>>,
+    pub fill_index: u32,
+}
+
+/// Outcome of one item in a batch call, failures carry the `EscrowError` code
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BatchResult {
+    Success,
+    Failed(u32),
+}
+
+impl BatchResult {
+    fn record(payouts: &mut Payouts, settled: Result<Settlement, EscrowError>) -> Self {
+        match settled {
+            Ok(settlement) => {
+                payouts.add(settlement);
+                BatchResult::Success
+            }
+            Err(e) => BatchResult::Failed(e as u32),
+        }
+    }
+}
+
 #[contract]
 pub struct EscrowContract;
 
 #[contractimpl]
 impl EscrowContract {
-    /// Create new escrow
-    pub fn create_escrow(
-        env: Env,
-        maker: Address,
-        taker: Address,
-        token: Address,
-        amount: i128,
-        safety_deposit: i128,
-        hashlock: BytesN<32>,
-        rescue_delay: u32,
-        is_src: bool,
-        merkle_root: BytesN<32>,
-        parts: u32,
-    ) -> BytesN<32> {
-        // Validate inputs
-        if amount <= 0 {
-            panic!("Invalid amount");
-        }
-        if safety_deposit <= 0 {
-            panic!("Invalid safety deposit");
-        }
-
-        // Validate init_time
-        let init_time = env.ledger().timestamp();
+    pub fn __constructor(env: Env, admin: Address) {
+        write_admin(&env, &admin);
+        write_storage_version(&env, STORAGE_VERSION);
+    }
 
-        maker.require_auth();
+    /// Announce a new contract WASM; it can be applied after `UPGRADE_DELAY`
+    pub fn schedule_upgrade(env: Env, new_wasm_hash: BytesN<32>) -> u64 {
+        let admin = read_admin(&env);
+        admin.require_auth();
 
-        // Compute timelocks using get_timelock_deadline
-        let mut timelocks = Vec::new(&env);
-        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_FINALITY, rescue_delay));
-        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_WITHDRAWAL, rescue_delay));
-        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_PUBLIC_WITHDRAWAL, rescue_delay));
-        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_CANCELLATION, rescue_delay));
-        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_PUBLIC_CANCELLATION, rescue_delay));
+        if read_pending_upgrade(&env).is_some() {
+            panic!("Upgrade already scheduled");
+        }
 
-        // Compute order hash for deterministic identification
-        let order_hash = Self::compute_order_hash(
+        let eta = env.ledger().timestamp() + UPGRADE_DELAY;
+        write_pending_upgrade(
             &env,
-            maker.clone(),
-            taker.clone(),
-            token.clone(),
-            amount,
-            hashlock.clone(),
-            timelocks.clone(),
-            is_src,
+            &PendingUpgrade {
+                wasm_hash: new_wasm_hash.clone(),
+                eta,
+            },
         );
 
-        // Check if escrow already exists
-        let escrow_key = DataKey::Escrow(order_hash.clone());
-        if env.storage().persistent().has(&escrow_key) {
-            panic!("Escrow already exists");
-        }
-
-        // Initialize used_parts bitmap
-        let mut used_parts = Vec::new(&env);
-        for _ in 0..parts {
-            used_parts.push_back(false);
-        }
+        env.events().publish(
+            (symbol_short!("upgrade"), symbol_short!("scheduled")),
+            UpgradeScheduledEvent {
+                wasm_hash: new_wasm_hash,
+                eta,
+            },
+        );
 
-        // Transfer tokens to contract
-        let contract_address = env.current_contract_address();
-        let total_amount = amount + safety_deposit;
-        
-        let token_client = token::Client::new(&env, &token);
-        token_client.transfer(&maker, &contract_address, &total_amount);
+        eta
+    }
 
-        // Create escrow
-        let escrow = Escrow {
-            maker: maker.clone(),
-            taker: taker.clone(),
-            token: token.clone(),
-            amount,
-            safety_deposit,
-            hashlock: HashLock { value: hashlock.clone() },
-            timelocks: timelocks.clone(),
-            claimed: false,
-            cancelled: false,
-            is_src,
-            merkle_root: merkle_root.clone(),
-            parts,
-            used_parts,
-        };
+    /// Withdraw a scheduled upgrade
+    pub fn cancel_upgrade(env: Env) {
+        let admin = read_admin(&env);
+        admin.require_auth();
 
-        // Store escrow
-        env.storage().persistent().set(&escrow_key, &escrow);
+        let pending = read_pending_upgrade(&env).unwrap_or_else(|| panic!("No upgrade scheduled"));
+        remove_pending_upgrade(&env);
 
-        // Emit event
         env.events().publish(
-            (symbol_short!("created"),),
-            EscrowCreatedEvent {
-                order_hash: order_hash.clone(),
-                maker,
-                taker,
-                token,
-                amount,
-                hashlock,
-                timelocks,
-                is_src,
-                merkle_root,
-                parts,
+            (symbol_short!("upgrade"), symbol_short!("cancelled")),
+            UpgradeCancelledEvent {
+                wasm_hash: pending.wasm_hash,
             },
         );
-
-        order_hash
     }
 
-    /// Withdraw funds with preimage (private)
-    pub fn withdraw(
-        env: Env,
-        order_hash: BytesN<32>,
-        preimage: Bytes,
-        merkle_proof: Vec<BytesN<32>>,
-        fill_index: u32,
-    ) {
-        let escrow_key = DataKey::Escrow(order_hash.clone());
-        
-        if !env.storage().persistent().has(&escrow_key) {
-            panic!("Escrow does not exist");
-        }
+    /// Replace the contract WASM once the scheduled upgrade delay has passed
+    pub fn apply_upgrade(env: Env) {
+        let admin = read_admin(&env);
+        admin.require_auth();
 
-        let mut escrow: Escrow = env.storage().persistent().get(&escrow_key).unwrap();
-        
-        if escrow.claimed || escrow.cancelled {
-            panic!("Escrow already finalized");
+        let pending = read_pending_upgrade(&env).unwrap_or_else(|| panic!("No upgrade scheduled"));
+        if env.ledger().timestamp() < pending.eta {
+            panic!("Upgrade delay not elapsed");
         }
-
-        // Verify preimage - using keccak256 as per original contract
-        let computed_hash = env.crypto().keccak256(&preimage).to_bytes();
-        if computed_hash != escrow.hashlock.value {
-            panic!("Invalid preimage");
+        // Migrations go one WASM at a time, so the current one must be done
+        if read_storage_version(&env) != STORAGE_VERSION {
+            panic!("Storage migration pending");
         }
+        remove_pending_upgrade(&env);
 
-        // Verify partial fill if using Merkle tree
-        if escrow.parts > 1 {
-            if fill_index >= escrow.parts {
-                panic!("Invalid fill index");
-            }
-            
-            let used = escrow.used_parts.get(fill_index).unwrap_or(false);
-            if used {
-                panic!("Part already used");
-            }
-
-            // Verify Merkle proof using the proper crypto library
-            // Note: Using Keccak256 for Merkle tree as it's more standard for blockchain
-            let leaf = env.crypto().keccak256(&preimage).to_bytes();
-            if !Verifier::<Keccak256>::verify_with_index(
-                &env, 
-                merkle_proof, 
-                escrow.merkle_root.clone(), 
-                leaf, 
-                fill_index
-            ) {
-                panic!("Invalid Merkle proof");
-            }
+        env.deployer()
+            .update_current_contract_wasm(pending.wasm_hash.clone());
 
-            // Mark part as used
-            escrow.used_parts.set(fill_index, true);
-        }
+        env.events().publish(
+            (symbol_short!("upgrade"), symbol_short!("applied")),
+            UpgradeAppliedEvent {
+                wasm_hash: pending.wasm_hash,
+            },
+        );
+    }
 
-        // Check authorization and timing
-        let current_time = env.ledger().timestamp();
-        let withdrawal_time = escrow.timelocks.get(STAGE_SRC_WITHDRAWAL).unwrap();
-        let cancellation_time = escrow.timelocks.get(STAGE_SRC_CANCELLATION).unwrap();
+    /// Migration hook, called by the admin with the new WASM in place.
+    ///
+    /// `from_version` must be the stored version, so a migration cannot run
+    /// twice or against storage it was not written for.
+    pub fn migrate(env: Env, from_version: u32) -> u32 {
+        let admin = read_admin(&env);
+        admin.require_auth();
 
-        if current_time < withdrawal_time {
-            panic!("Stage not reached");
-        }
-        if current_time >= cancellation_time {
-            panic!("Stage expired");
+        if read_storage_version(&env) != from_version {
+            panic!("Storage version mismatch");
         }
+        migrate(&env, from_version);
 
-        // Determine recipient and verify authorization
-        let (recipient, caller) = if escrow.is_src {
-            // EscrowSrc: only taker can withdraw, tokens go to taker
-            (escrow.taker.clone(), escrow.taker.clone())
-        } else {
-            // EscrowDst: only taker can withdraw, tokens go to maker
-            (escrow.maker.clone(), escrow.taker.clone())
-        };
+        env.events().publish(
+            (symbol_short!("upgrade"), symbol_short!("migrated")),
+            StorageMigratedEvent {
+                from_version,
+                to_version: STORAGE_VERSION,
+            },
+        );
+        STORAGE_VERSION
+    }
 
-        caller.require_auth();
+    pub fn get_pending_upgrade(env: Env) -> Option<PendingUpgrade> {
+        read_pending_upgrade(&env)
+    }
 
-        // Mark as claimed
-        escrow.claimed = true;
-        env.storage().persistent().set(&escrow_key, &escrow);
+    pub fn get_storage_version(env: Env) -> u32 {
+        read_storage_version(&env)
+    }
 
-        // Transfer funds
-        let contract_address = env.current_contract_address();
-        let token_client = token::Client::new(&env, &escrow.token);
-        
-        // Transfer amount to recipient
-        token_client.transfer(&contract_address, &recipient, &escrow.amount);
-
-        // Return safety deposit to maker (resolver)
-        if escrow.safety_deposit > 0 {
-            token_client.transfer(&contract_address, &escrow.maker, &escrow.safety_deposit);
-
-            env.events().publish(
-                (symbol_short!("safety"),),
-                SafetyDepositClaimedEvent {
-                    order_hash: order_hash.clone(),
-                    executor: escrow.maker.clone(),
-                    amount: escrow.safety_deposit,
-                },
-            );
-        }
+    pub fn set_admin(env: Env, new_admin: Address) {
+        let admin = read_admin(&env);
+        admin.require_auth();
+
+        write_admin(&env, &new_admin);
 
-        // Emit event
         env.events().publish(
-            (symbol_short!("claimed"),),
-            FundsClaimedEvent {
-                order_hash,
-                preimage,
-                recipient,
-                amount: escrow.amount,
-                merkle_root: escrow.merkle_root,
-                parts: escrow.parts,
+            (symbol_short!("admin"),),
+            AdminChangedEvent {
+                old_admin: admin,
+                new_admin,
             },
         );
     }
 
-    /// Cancel escrow (private)
-    pub fn cancel(env: Env, order_hash: BytesN<32>) {
-        let escrow_key = DataKey::Escrow(order_hash.clone());
-        
-        if !env.storage().persistent().has(&escrow_key) {
-            panic!("Escrow does not exist");
-        }
-
-        let mut escrow: Escrow = env.storage().persistent().get(&escrow_key).unwrap();
-        
-        if escrow.claimed || escrow.cancelled {
-            panic!("Escrow already finalized");
+    /// Create new escrow funded by the maker.
+    ///
+    /// With `resolvers` set the taker is whichever allowed resolver submits
+    /// the maker's signed order first, see `resolvers::maker_auth_args`.
+    pub fn create_escrow(env: Env, args: CreateEscrowArgs) -> BytesN<32> {
+        match &args.resolvers {
+            AllowedResolvers::Taker => args.maker.require_auth(),
+            allowed => {
+                args.maker
+                    .require_auth_for_args(resolvers::maker_auth_args(&env, &args));
+                args.taker.require_auth();
+                if !resolvers::is_allowed(&env, allowed, &args.taker, &args.resolver_proof) {
+                    panic_with_error!(&env, EscrowError::Unauthorized);
+                }
+            }
         }
 
-        // Check timing
-        let current_time = env.ledger().timestamp();
-        let cancellation_time = escrow.timelocks.get(STAGE_SRC_CANCELLATION).unwrap();
+        let funder = args.maker.clone();
+        Self::open_escrow(&env, args, funder)
+    }
 
-        if current_time < cancellation_time {
-            panic!("Stage not reached");
+    /// Create a destination escrow funded by the taker (resolver).
+    ///
+    /// The maker is only named as beneficiary and does not sign, like
+    /// `createDstEscrow` on the EVM side.
+    pub fn create_dst_escrow(env: Env, args: CreateEscrowArgs) -> BytesN<32> {
+        if args.is_src {
+            panic!("Pre-funded escrows must be destination escrows");
         }
+        args.taker.require_auth();
 
-        // Determine refund recipient and verify authorization
-        let (refund_recipient, caller) = if escrow.is_src {
-            // EscrowSrc: only taker can cancel, tokens go back to maker
-            (escrow.maker.clone(), escrow.taker.clone())
-        } else {
-            // EscrowDst: only taker can cancel, tokens go back to taker
-            (escrow.taker.clone(), escrow.taker.clone())
-        };
-
-        caller.require_auth();
-
-        // Mark as cancelled
-        escrow.cancelled = true;
-        env.storage().persistent().set(&escrow_key, &escrow);
+        let funder = args.taker.clone();
+        Self::open_escrow(&env, args, funder)
+    }
 
-        // Transfer funds
-        let contract_address = env.current_contract_address();
-        let token_client = token::Client::new(&env, &escrow.token);
-        
-        // Refund amount
-        token_client.transfer(&contract_address, &refund_recipient, &escrow.amount);
+    /// Push the escrow's deadlines later, signed by both maker and taker.
+    ///
+    /// The escrow keeps its order hash, which no longer matches
+    /// `compute_order_hash` over the new timelocks.
+    pub fn extend_timelocks(env: Env, order_hash: BytesN<32>, new_timelocks: Vec<u64>) {
+        let mut authorized = Vec::new(&env);
+        settlement::extend_timelocks(&env, &mut authorized, order_hash, new_timelocks)
+            .unwrap_or_else(|e| panic_with_error!(&env, e));
+    }
 
-        // Return safety deposit to maker (resolver)
-        if escrow.safety_deposit > 0 {
-            token_client.transfer(&contract_address, &escrow.maker, &escrow.safety_deposit);
-        }
+    /// Withdraw funds with preimage (private)
+    pub fn withdraw(
+        env: Env,
+        order_hash: BytesN<32>,
+        preimage: BytesN<32>,
+        merkle_proof: Vec<BytesN<32>>,
+        fill_index: u32,
+    ) {
+        let mut authorized = Vec::new(&env);
+        settlement::withdraw(&env, &mut authorized, order_hash, preimage, merkle_proof, fill_index)
+            .unwrap_or_else(|e| panic_with_error!(&env, e))
+            .pay(&env);
+    }
 
-        // Emit event
-        env.events().publish(
-            (symbol_short!("refunded"),),
-            FundsRefundedEvent {
-                order_hash,
-                sender: refund_recipient,
-                amount: escrow.amount,
-                merkle_root: BytesN::from_array(&env, &[0u8; 32]),
-                parts: 0,
-            },
-        );
+    /// Cancel escrow (private)
+    pub fn cancel(env: Env, order_hash: BytesN<32>) {
+        let mut authorized = Vec::new(&env);
+        settlement::cancel(&env, &mut authorized, order_hash)
+            .unwrap_or_else(|e| panic_with_error!(&env, e))
+            .pay(&env);
     }
 
     /// Public withdraw with preimage (for relayer)
     pub fn public_withdraw(
         env: Env,
         order_hash: BytesN<32>,
-        preimage: Bytes,
+        preimage: BytesN<32>,
         merkle_proof: Vec<BytesN<32>>,
         fill_index: u32,
         executor: Address,
     ) {
         executor.require_auth();
 
-        let escrow_key = DataKey::Escrow(order_hash.clone());
-        
-        if !env.storage().persistent().has(&escrow_key) {
-            panic!("Escrow does not exist");
-        }
-
-        let mut escrow: Escrow = env.storage().persistent().get(&escrow_key).unwrap();
-        
-        if escrow.claimed || escrow.cancelled {
-            panic!("Escrow already finalized");
-        }
-
-        // Verify preimage
-        let computed_hash = env.crypto().keccak256(&preimage).to_bytes();
-        if computed_hash != escrow.hashlock.value {
-            panic!("Invalid preimage");
-        }
-
-        // Verify partial fill if using Merkle tree
-        if escrow.parts > 1 {
-            if fill_index >= escrow.parts {
-                panic!("Invalid fill index");
-            }
-            
-            let used = escrow.used_parts.get(fill_index).unwrap_or(false);
-            if used {
-                panic!("Part already used");
-            }
-
-            // Verify Merkle proof using the proper crypto library
-            let leaf = env.crypto().keccak256(&preimage).to_bytes();
-            if !Verifier::<Keccak256>::verify_with_index(
-                &env, 
-                merkle_proof, 
-                escrow.merkle_root.clone(), 
-                leaf, 
-                fill_index
-            ) {
-                panic!("Invalid Merkle proof");
-            }
-
-            // Mark part as used
-            escrow.used_parts.set(fill_index, true);
-        }
-
-        // Check timing for public withdrawal
-        let current_time = env.ledger().timestamp();
-        let public_withdrawal_time = escrow.timelocks.get(STAGE_SRC_PUBLIC_WITHDRAWAL).unwrap();
-        let public_cancellation_time = escrow.timelocks.get(STAGE_SRC_PUBLIC_CANCELLATION).unwrap();
-
-        if current_time < public_withdrawal_time {
-            panic!("Stage not reached");
-        }
-        if current_time >= public_cancellation_time {
-            panic!("Stage expired");
-        }
-
-        // Determine recipient
-        let recipient = if escrow.is_src { 
-            escrow.taker.clone() 
-        } else { 
-            escrow.maker.clone() 
-        };
-
-        // Mark as claimed
-        escrow.claimed = true;
-        env.storage().persistent().set(&escrow_key, &escrow);
-
-        // Transfer funds
-        let contract_address = env.current_contract_address();
-        let token_client = token::Client::new(&env, &escrow.token);
-        
-        // Transfer amount to recipient
-        token_client.transfer(&contract_address, &recipient, &escrow.amount);
-
-        // Award safety deposit to executor as incentive
-        if escrow.safety_deposit > 0 {
-            token_client.transfer(&contract_address, &executor, &escrow.safety_deposit);
-
-            env.events().publish(
-                (symbol_short!("safety"),),
-                SafetyDepositClaimedEvent {
-                    order_hash: order_hash.clone(),
-                    executor: executor.clone(),
-                    amount: escrow.safety_deposit,
-                },
-            );
-        }
-
-        // Emit event
-        env.events().publish(
-            (symbol_short!("claimed"),),
-            FundsClaimedEvent {
-                order_hash,
-                preimage,
-                recipient,
-                amount: escrow.amount,
-                merkle_root: BytesN::from_array(&env, &[0u8; 32]),
-                parts: 0,
-            },
-        );
+        settlement::public_withdraw(&env, order_hash, preimage, merkle_proof, fill_index, &executor)
+            .unwrap_or_else(|e| panic_with_error!(&env, e))
+            .pay(&env);
     }
 
     /// Public cancel (for relayer) - only available on SRC chain
     pub fn public_cancel(env: Env, order_hash: BytesN<32>, executor: Address) {
         executor.require_auth();
 
-        let escrow_key = DataKey::Escrow(order_hash.clone());
-        
-        if !env.storage().persistent().has(&escrow_key) {
-            panic!("Escrow does not exist");
-        }
+        settlement::public_cancel(&env, order_hash, &executor)
+            .unwrap_or_else(|e| panic_with_error!(&env, e))
+            .pay(&env);
+    }
 
-        let escrow: Escrow = env.storage().persistent().get(&escrow_key).unwrap();
-        
-        if escrow.claimed || escrow.cancelled {
-            panic!("Escrow already finalized");
+    /// Withdraw several escrows, paying each (token, recipient) pair once
+    pub fn batch_withdraw(env: Env, items: Vec<WithdrawArgs>) -> Vec<BatchResult> {
+        let mut authorized = Vec::new(&env);
+        let mut payouts = Payouts::new(&env);
+        let mut results = Vec::new(&env);
+
+        for item in items.iter() {
+            let settled = settlement::withdraw(
+                &env,
+                &mut authorized,
+                item.order_hash,
+                item.preimage,
+                item.merkle_proof,
+                item.fill_index,
+            );
+            results.push_back(BatchResult::record(&mut payouts, settled));
         }
 
-        // Only available on SRC chain
-        if !escrow.is_src {
-            panic!("Unauthorized");
-        }
+        payouts.pay(&env);
+        results
+    }
 
-        // Check timing for public cancellation
-        let current_time = env.ledger().timestamp();
-        let public_cancellation_time = escrow.timelocks.get(STAGE_SRC_PUBLIC_CANCELLATION).unwrap();
+    /// Cancel several escrows, paying each (token, recipient) pair once
+    pub fn batch_cancel(env: Env, order_hashes: Vec<BytesN<32>>) -> Vec<BatchResult> {
+        let mut authorized = Vec::new(&env);
+        let mut payouts = Payouts::new(&env);
+        let mut results = Vec::new(&env);
 
-        if current_time < public_cancellation_time {
-            panic!("Stage not reached");
+        for order_hash in order_hashes.iter() {
+            let settled = settlement::cancel(&env, &mut authorized, order_hash);
+            results.push_back(BatchResult::record(&mut payouts, settled));
         }
 
-        // Remove escrow from storage
-        env.storage().persistent().remove(&escrow_key);
+        payouts.pay(&env);
+        results
+    }
+
+    /// Publicly cancel several escrows, safety deposits go to `executor`
+    pub fn batch_public_cancel(
+        env: Env,
+        order_hashes: Vec<BytesN<32>>,
+        executor: Address,
+    ) -> Vec<BatchResult> {
+        executor.require_auth();
 
-        // On SRC chain: refund to maker, safety deposit to executor
-        let refund_recipient = escrow.maker.clone();
+        let mut payouts = Payouts::new(&env);
+        let mut results = Vec::new(&env);
 
-        // Transfer funds
-        let contract_address = env.current_contract_address();
-        let token_client = token::Client::new(&env, &escrow.token);
-        
-        // Refund amount to maker
-        token_client.transfer(&contract_address, &refund_recipient, &escrow.amount);
-
-        // Award safety deposit to executor
-        if escrow.safety_deposit > 0 {
-            token_client.transfer(&contract_address, &executor, &escrow.safety_deposit);
-
-            env.events().publish(
-                (symbol_short!("safety"),),
-                SafetyDepositClaimedEvent {
-                    order_hash: order_hash.clone(),
-                    executor: executor.clone(),
-                    amount: escrow.safety_deposit,
-                },
-            );
+        for order_hash in order_hashes.iter() {
+            let settled = settlement::public_cancel(&env, order_hash, &executor);
+            results.push_back(BatchResult::record(&mut payouts, settled));
         }
 
-        // Emit event
-        env.events().publish(
-            (symbol_short!("refunded"),),
-            FundsRefundedEvent {
-                order_hash,
-                sender: refund_recipient,
-                amount: escrow.amount,
-                merkle_root: escrow.merkle_root,
-                parts: escrow.parts,
-            },
-        );
+        payouts.pay(&env);
+        results
     }
 
     /// Get escrow information
     pub fn get_escrow_info(env: Env, order_hash: BytesN<32>) -> Option<Escrow> {
-        let escrow_key = DataKey::Escrow(order_hash);
-        env.storage().persistent().get(&escrow_key)
+        settlement::load_escrow(&env, &order_hash).ok()
+    }
+
+    /// Get escrow status, `None` if the escrow never existed
+    pub fn get_status(env: Env, order_hash: BytesN<32>) -> Option<EscrowStatus> {
+        settlement::load_escrow(&env, &order_hash)
+            .ok()
+            .map(|escrow| escrow.status)
     }
 
     /// Check if escrow exists
@@ -580,25 +590,46 @@ impl EscrowContract {
         env.storage().persistent().has(&escrow_key)
     }
 
+    /// Open escrows where `maker` is the maker, `limit` is capped at 50
+    pub fn escrows_by_maker(env: Env, maker: Address, cursor: u32, limit: u32) -> EscrowPage {
+        index::page(&env, EscrowIndex::Maker(maker), cursor, limit)
+    }
+
+    /// Open escrows where `taker` is the taker, `limit` is capped at 50
+    pub fn escrows_by_taker(env: Env, taker: Address, cursor: u32, limit: u32) -> EscrowPage {
+        index::page(&env, EscrowIndex::Taker(taker), cursor, limit)
+    }
+
+    /// All open escrows, `limit` is capped at 50
+    pub fn active_escrows(env: Env, cursor: u32, limit: u32) -> EscrowPage {
+        index::page(&env, EscrowIndex::Active, cursor, limit)
+    }
+
     /// Compute order hash from parameters
     pub fn compute_order_hash(
         env: &Env,
         maker: Address,
         taker: Address,
+        receiver: Address,
         token: Address,
         amount: i128,
-        hashlock: BytesN<32>,
+        bundle: Vec<TokenAmount>,
+        hashlock: HashLock,
         timelocks: Vec<u64>,
         is_src: bool,
+        src_cancellation: Option<u64>,
     ) -> BytesN<32> {
         let mut data = Bytes::new(env);
         data.append(&maker.to_xdr(env));
         data.append(&taker.to_xdr(env));
+        data.append(&receiver.to_xdr(env));
         data.append(&token.to_xdr(env));
         data.append(&amount.to_xdr(env));
+        data.append(&bundle.to_xdr(env));
         data.append(&hashlock.to_xdr(env));
         data.append(&timelocks.to_xdr(env));
         data.append(&is_src.to_xdr(env));
+        data.append(&src_cancellation.to_xdr(env));
     
         env.crypto().keccak256(&data).to_bytes()
     }
@@ -615,34 +646,193 @@ impl EscrowContract {
         }
     }
 
-    pub fn get_current_stage(env: Env, order_hash: BytesN<32>) -> u32 {
-        let escrow_key = DataKey::Escrow(order_hash);
-        
-        if !env.storage().persistent().has(&escrow_key) {
-            panic!("Escrow does not exist");
+    /// Get the stage the escrow is in at the current ledger time
+    pub fn get_current_stage(env: Env, order_hash: BytesN<32>) -> Stage {
+        let escrow = settlement::load_escrow(&env, &order_hash)
+            .unwrap_or_else(|e| panic_with_error!(&env, e));
+        schedule::current_stage(&escrow.timelocks, env.ledger().timestamp())
+    }
+
+    /// Get the number of secrets that open the escrow, `parts + 1` when it
+    /// can be filled in parts
+    pub fn get_secret_count(env: Env, order_hash: BytesN<32>) -> u32 {
+        let escrow = settlement::load_escrow(&env, &order_hash)
+            .unwrap_or_else(|e| panic_with_error!(&env, e));
+        settlement::secret_count(&escrow)
+    }
+
+    /// Get every stage window and the time left until the next stage
+    pub fn get_schedule(env: Env, order_hash: BytesN<32>) -> Schedule {
+        let escrow = settlement::load_escrow(&env, &order_hash)
+            .unwrap_or_else(|e| panic_with_error!(&env, e));
+        schedule::schedule(&env, &escrow.timelocks, env.ledger().timestamp())
+    }
+}
+
+impl EscrowContract {
+    fn open_escrow(env: &Env, args: CreateEscrowArgs, funder: Address) -> BytesN<32> {
+        let CreateEscrowArgs {
+            maker,
+            taker,
+            receiver,
+            token,
+            amount,
+            bundle,
+            safety_deposit,
+            hashlock,
+            hash_algorithm,
+            rescue_delay,
+            is_src,
+            merkle_root,
+            parts,
+            resolvers,
+            resolver_proof: _,
+            src_cancellation,
+        } = args;
+        let hash_lock = HashLock {
+            value: hashlock.clone(),
+            algorithm: hash_algorithm,
+        };
+        let receiver = receiver.unwrap_or_else(|| maker.clone());
+
+        // Validate inputs
+        if amount <= 0 {
+            panic!("Invalid amount");
+        }
+        if safety_deposit <= 0 {
+            panic!("Invalid safety deposit");
+        }
+        if rescue_delay > MAX_RESCUE_DELAY {
+            panic!("Invalid rescue delay");
+        }
+        for leg in bundle.iter() {
+            if leg.amount <= 0 {
+                panic!("Invalid amount");
+            }
+        }
+        if parts == 0 || parts > MAX_PARTS {
+            panic!("Invalid parts");
+        }
+        if !bundle.is_empty() && parts > 1 {
+            panic!("Bundles cannot be partially filled");
         }
-     
-        let escrow: Escrow = env.storage().persistent().get(&escrow_key).unwrap();
-        let current_time = env.ledger().timestamp();
-     
-        let finality_time = escrow.timelocks.get(STAGE_FINALITY).unwrap();
-        let withdrawal_time = escrow.timelocks.get(STAGE_SRC_WITHDRAWAL).unwrap();
-        let public_withdrawal_time = escrow.timelocks.get(STAGE_SRC_PUBLIC_WITHDRAWAL).unwrap();
-        let cancellation_time = escrow.timelocks.get(STAGE_SRC_CANCELLATION).unwrap();
-        let public_cancellation_time = escrow.timelocks.get(STAGE_SRC_PUBLIC_CANCELLATION).unwrap();
-     
-        if current_time < finality_time {
-            STAGE_FINALITY
-        } else if current_time < withdrawal_time {
-            STAGE_FINALITY
-        } else if current_time < public_withdrawal_time {
-            STAGE_SRC_WITHDRAWAL
-        } else if current_time < cancellation_time {
-            STAGE_SRC_PUBLIC_WITHDRAWAL
-        } else if current_time < public_cancellation_time {
-            STAGE_SRC_CANCELLATION
+        if parts > 1 && hashlock != merkle_root {
+            panic!("Hashlock must be the merkle root of a partially fillable escrow");
+        }
+        if resolvers != AllowedResolvers::Taker && !is_src {
+            panic!("Resolver whitelists only apply to source escrows");
+        }
+
+        // Validate init_time
+        let init_time = env.ledger().timestamp();
+
+        // Compute timelocks using get_timelock_deadline
+        let mut timelocks = Vec::new(env);
+        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_FINALITY, rescue_delay));
+        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_WITHDRAWAL, rescue_delay));
+        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_PUBLIC_WITHDRAWAL, rescue_delay));
+        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_CANCELLATION, rescue_delay));
+        timelocks.push_back(Self::get_timelock_deadline(init_time, STAGE_SRC_PUBLIC_CANCELLATION, rescue_delay));
+
+        let src_cancellation = if is_src {
+            None
         } else {
-            STAGE_SRC_PUBLIC_CANCELLATION
+            let Some(src_cancellation) = src_cancellation else {
+                panic!("Missing source cancellation time");
+            };
+            schedule::check_src_margin(src_cancellation, &timelocks)
+                .unwrap_or_else(|e| panic_with_error!(env, e));
+            Some(src_cancellation)
+        };
+
+        // Compute order hash for deterministic identification
+        let order_hash = Self::compute_order_hash(
+            env,
+            maker.clone(),
+            taker.clone(),
+            receiver.clone(),
+            token.clone(),
+            amount,
+            bundle.clone(),
+            hash_lock.clone(),
+            timelocks.clone(),
+            is_src,
+            src_cancellation,
+        );
+
+        // Check if escrow already exists
+        let escrow_key = DataKey::Escrow(order_hash.clone());
+        if env.storage().persistent().has(&escrow_key) {
+            panic!("Escrow already exists");
+        }
+
+        // Transfer tokens to contract
+        let contract_address = env.current_contract_address();
+        let total_amount = amount + safety_deposit;
+        
+        let token_client = token::Client::new(env, &token);
+        token_client.transfer(&funder, &contract_address, &total_amount);
+        for leg in bundle.iter() {
+            token::Client::new(env, &leg.token).transfer(&funder, &contract_address, &leg.amount);
+        }
+
+        // Create escrow
+        let mut escrow = Escrow {
+            maker: maker.clone(),
+            taker: taker.clone(),
+            receiver: receiver.clone(),
+            funder: funder.clone(),
+            token: token.clone(),
+            amount,
+            bundle: bundle.clone(),
+            safety_deposit,
+            hashlock: hash_lock,
+            timelocks: timelocks.clone(),
+            status: EscrowStatus::Active,
+            is_src,
+            merkle_root: merkle_root.clone(),
+            parts,
+            used_parts: Vec::new(env),
+            src_cancellation,
+        };
+        // One flag per secret, set once its fill level is reached
+        for _ in 0..settlement::secret_count(&escrow) {
+            escrow.used_parts.push_back(false);
         }
-     }
-}
\ No newline at end of file
+
+        // Store escrow
+        env.storage().persistent().set(&escrow_key, &escrow);
+        index::add_escrow(env, &order_hash, &escrow);
+        settlement::extend_escrow_ttl(env, &order_hash, &escrow);
+
+        // Emit event
+        let mut legs = Vec::new(env);
+        legs.push_back(TokenAmount {
+            token: token.clone(),
+            amount,
+        });
+        legs.append(&bundle);
+        env.events().publish(
+            (symbol_short!("created"),),
+            EscrowCreatedEvent {
+                order_hash: order_hash.clone(),
+                maker,
+                taker,
+                receiver,
+                funder,
+                token,
+                amount,
+                hashlock,
+                hash_algorithm,
+                timelocks,
+                is_src,
+                merkle_root,
+                parts,
+                legs,
+                src_cancellation,
+            },
+        );
+
+        order_hash
+    }
+}
@@ -0,0 +1,55 @@
+use soroban_sdk::{contracttype, xdr::ToXdr, Address, BytesN, Env, IntoVal, Val, Vec};
+use stellar_contract_utils::crypto::keccak::Keccak256;
+use stellar_contract_utils::crypto::merkle::Verifier;
+
+use crate::CreateEscrowArgs;
+
+/// Resolvers allowed to fill a source order
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AllowedResolvers {
+    /// Only the taker named by the maker
+    Taker,
+    List(Vec<Address>),
+    /// Root of a sorted-pair Keccak256 tree over `resolver_leaf` values
+    MerkleRoot(BytesN<32>),
+}
+
+/// Leaf committing to `resolver` in an `AllowedResolvers::MerkleRoot` tree.
+pub fn resolver_leaf(env: &Env, resolver: &Address) -> BytesN<32> {
+    env.crypto()
+        .keccak256(&resolver.clone().to_xdr(env))
+        .to_bytes()
+}
+
+pub fn is_allowed(
+    env: &Env,
+    resolvers: &AllowedResolvers,
+    resolver: &Address,
+    proof: &Vec<BytesN<32>>,
+) -> bool {
+    match resolvers {
+        AllowedResolvers::Taker => false,
+        AllowedResolvers::List(list) => list.contains(resolver),
+        AllowedResolvers::MerkleRoot(root) => Verifier::<Keccak256>::verify(
+            env,
+            proof.clone(),
+            root.clone(),
+            resolver_leaf(env, resolver),
+        ),
+    }
+}
+
+/// Arguments the maker signs for a whitelisted order.
+///
+/// The filling resolver is not known when the maker signs, so the maker signs
+/// the `create_escrow` args with `taker` set to the maker and an empty
+/// `resolver_proof`. The signature's nonce lets only the first resolver use it.
+pub fn maker_auth_args(env: &Env, args: &CreateEscrowArgs) -> Vec<Val> {
+    let order = CreateEscrowArgs {
+        taker: args.maker.clone(),
+        resolver_proof: Vec::new(env),
+        ..args.clone()
+    };
+    (order,).into_val(env)
+}
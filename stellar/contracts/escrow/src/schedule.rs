@@ -0,0 +1,107 @@
+use soroban_sdk::{contracttype, Env, Vec};
+use timelock_safety::{check_cancellation_margin, FinalityAssumptions};
+
+use crate::{
+    EscrowError, STAGE_FINALITY, STAGE_SRC_CANCELLATION, STAGE_SRC_PUBLIC_CANCELLATION,
+    STAGE_SRC_PUBLIC_WITHDRAWAL, STAGE_SRC_WITHDRAWAL,
+};
+
+/// Escrow stage at a point in time
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stage {
+    /// The finality lock has not started yet
+    BeforeFinality,
+    /// Waiting for finality, nothing can be executed
+    Finality,
+    /// The taker can withdraw with the secret
+    Withdrawal,
+    /// Anyone can withdraw with the secret
+    PublicWithdrawal,
+    /// The taker can cancel
+    Cancellation,
+    /// Anyone can cancel (source escrows only)
+    PublicCancellation,
+}
+
+/// Window of a single stage, `end` is `None` for the last stage
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StageWindow {
+    pub stage: Stage,
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+    pub current: Stage,
+    pub stages: Vec<StageWindow>,
+    /// Seconds until the next stage begins, `None` once in the last stage
+    pub next_stage_in: Option<u64>,
+}
+
+/// Stages in the order they occur, paired with the timelock that starts them
+const STAGES: [(Stage, u32); 5] = [
+    (Stage::Finality, STAGE_FINALITY),
+    (Stage::Withdrawal, STAGE_SRC_WITHDRAWAL),
+    (Stage::PublicWithdrawal, STAGE_SRC_PUBLIC_WITHDRAWAL),
+    (Stage::Cancellation, STAGE_SRC_CANCELLATION),
+    (Stage::PublicCancellation, STAGE_SRC_PUBLIC_CANCELLATION),
+];
+
+/// Finality assumed for a destination escrow on Stellar paired with a source
+/// escrow on Ethereum, which finalizes in about two epochs.
+const CHAIN_FINALITY: FinalityAssumptions = FinalityAssumptions {
+    src_finality: 15 * 60,
+    dst_finality: 60,
+    clock_drift: 5 * 60,
+};
+
+/// Check that destination `timelocks` become cancellable early enough before
+/// the paired source escrow does.
+pub fn check_src_margin(src_cancellation: u64, timelocks: &Vec<u64>) -> Result<(), EscrowError> {
+    check_cancellation_margin(
+        src_cancellation,
+        timelocks.get(STAGE_SRC_CANCELLATION).unwrap(),
+        &CHAIN_FINALITY,
+    )
+    .map_err(|_| EscrowError::UnsafeTimelocks)
+}
+
+pub fn current_stage(timelocks: &Vec<u64>, now: u64) -> Stage {
+    let mut current = Stage::BeforeFinality;
+    for (stage, timelock) in STAGES {
+        if now >= timelocks.get(timelock).unwrap() {
+            current = stage;
+        }
+    }
+    current
+}
+
+pub fn schedule(env: &Env, timelocks: &Vec<u64>, now: u64) -> Schedule {
+    let mut stages = Vec::new(env);
+    let mut next_stage_in = None;
+
+    for (i, (stage, timelock)) in STAGES.iter().enumerate() {
+        let start = timelocks.get(*timelock).unwrap();
+        let end = STAGES
+            .get(i + 1)
+            .map(|(_, next)| timelocks.get(*next).unwrap());
+        if next_stage_in.is_none() && start > now {
+            next_stage_in = Some(start - now);
+        }
+        stages.push_back(StageWindow {
+            stage: *stage,
+            start,
+            end,
+        });
+    }
+
+    Schedule {
+        current: current_stage(timelocks, now),
+        stages,
+        next_stage_in,
+    }
+}
@@ -0,0 +1,578 @@
+use soroban_sdk::{symbol_short, token, Address, Bytes, BytesN, Env, Map, Vec};
+
+// Import crypto utilities
+use stellar_contract_utils::crypto::keccak::Keccak256;
+use stellar_contract_utils::crypto::merkle::Verifier;
+
+use crate::index;
+use crate::schedule;
+use crate::upgrade::{self, MAX_ESCROW_LIFETIME};
+use crate::{
+    DataKey, Escrow, EscrowError, EscrowStatus, FundsClaimedEvent, FundsRefundedEvent,
+    HashAlgorithm, SafetyDepositClaimedEvent, TimelocksExtendedEvent, TokenAmount, STAGE_FINALITY,
+    STAGE_SRC_CANCELLATION, STAGE_SRC_PUBLIC_CANCELLATION, STAGE_SRC_PUBLIC_WITHDRAWAL,
+    STAGE_SRC_WITHDRAWAL,
+};
+
+/// Ledgers are assumed to close no faster than this when sizing entry TTLs.
+const LEDGER_SECONDS: u64 = 5;
+
+/// Lifetime kept past public cancellation so the entry is still there to
+/// cancel, about one day of ledgers.
+const TTL_MARGIN_LEDGERS: u32 = 17_280;
+
+/// Token transfers owed by the contract after an escrow is settled.
+///
+/// Settling only validates and updates storage; the caller decides whether
+/// to pay out right away or fold the transfers into a batch.
+pub struct Settlement {
+    /// (token, recipient, amount)
+    transfers: Vec<(Address, Address, i128)>,
+}
+
+impl Settlement {
+    fn new(env: &Env) -> Self {
+        Settlement {
+            transfers: Vec::new(env),
+        }
+    }
+
+    fn add(&mut self, token: &Address, recipient: &Address, amount: i128) {
+        if amount > 0 {
+            self.transfers
+                .push_back((token.clone(), recipient.clone(), amount));
+        }
+    }
+
+    /// Add every leg in `legs` for `recipient`.
+    fn add_legs(&mut self, legs: &Vec<TokenAmount>, recipient: &Address) {
+        for leg in legs.iter() {
+            self.add(&leg.token, recipient, leg.amount);
+        }
+    }
+
+    pub fn pay(&self, env: &Env) {
+        let contract_address = env.current_contract_address();
+        for (token, recipient, amount) in self.transfers.iter() {
+            token::Client::new(env, &token).transfer(&contract_address, &recipient, &amount);
+        }
+    }
+}
+
+/// Transfers accumulated per (token, recipient) over a batch.
+pub struct Payouts {
+    totals: Map<(Address, Address), i128>,
+}
+
+impl Payouts {
+    pub fn new(env: &Env) -> Self {
+        Payouts {
+            totals: Map::new(env),
+        }
+    }
+
+    pub fn add(&mut self, settlement: Settlement) {
+        for (token, recipient, amount) in settlement.transfers.iter() {
+            let key = (token, recipient);
+            let total = self.totals.get(key.clone()).unwrap_or(0);
+            self.totals.set(key, total + amount);
+        }
+    }
+
+    pub fn pay(&self, env: &Env) {
+        let contract_address = env.current_contract_address();
+        for ((token, recipient), amount) in self.totals.iter() {
+            token::Client::new(env, &token).transfer(&contract_address, &recipient, &amount);
+        }
+    }
+}
+
+/// Require auth from `addr` unless it was already required in this invocation.
+///
+/// The host rejects a second `require_auth` for the same address in one frame,
+/// so batches track the addresses they have already checked.
+pub fn require_auth_once(authorized: &mut Vec<Address>, addr: &Address) {
+    if !authorized.contains(addr) {
+        addr.require_auth();
+        authorized.push_back(addr.clone());
+    }
+}
+
+pub fn load_escrow(env: &Env, order_hash: &BytesN<32>) -> Result<Escrow, EscrowError> {
+    let stored = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Escrow(order_hash.clone()))
+        .ok_or(EscrowError::EscrowNotFound)?;
+    Ok(upgrade::read_escrow(env, stored))
+}
+
+fn load_open_escrow(env: &Env, order_hash: &BytesN<32>) -> Result<Escrow, EscrowError> {
+    let escrow = load_escrow(env, order_hash)?;
+    if escrow.status.is_terminal() {
+        return Err(EscrowError::AlreadyFinalized);
+    }
+    Ok(escrow)
+}
+
+/// Persist `escrow`; terminal entries stay as tombstones but leave the indexes.
+fn save_escrow(env: &Env, order_hash: &BytesN<32>, escrow: &Escrow) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::Escrow(order_hash.clone()), escrow);
+    if escrow.status.is_terminal() {
+        index::remove_escrow(env, order_hash, escrow);
+    }
+}
+
+/// Ledgers until a margin past the public cancellation of `escrow`.
+pub fn ttl_ledgers(env: &Env, escrow: &Escrow) -> u32 {
+    let public_cancellation = escrow.timelocks.get(STAGE_SRC_PUBLIC_CANCELLATION).unwrap();
+    let remaining = public_cancellation.saturating_sub(env.ledger().timestamp());
+    (remaining / LEDGER_SECONDS) as u32 + TTL_MARGIN_LEDGERS
+}
+
+/// Keep the escrow entry and its index entries alive until a margin past
+/// its public cancellation.
+pub fn extend_escrow_ttl(env: &Env, order_hash: &BytesN<32>, escrow: &Escrow) {
+    let ledgers = ttl_ledgers(env, escrow);
+    env.storage()
+        .persistent()
+        .extend_ttl(&DataKey::Escrow(order_hash.clone()), ledgers, ledgers);
+    index::extend_escrow(env, order_hash, escrow, ledgers);
+}
+
+/// Number of secrets that open the escrow.
+///
+/// Like on EVM, an order filled in `parts` parts has a secret for every fill
+/// level `0..=parts`; escrows that are not split have the hashlock's only.
+pub fn secret_count(escrow: &Escrow) -> u32 {
+    if escrow.parts <= 1 {
+        1
+    } else {
+        escrow.parts + 1
+    }
+}
+
+/// Total amount released once the secret at `fill_index` is revealed.
+///
+/// Index `i` covers fills up to `(i + 1) / parts` of the amount, so the last
+/// two indexes both complete the escrow, the last one being what a fill up
+/// to 100% uses on EVM.
+fn fill_level(escrow: &Escrow, fill_index: u32) -> i128 {
+    if fill_index + 1 >= escrow.parts {
+        escrow.amount
+    } else {
+        escrow.amount * (fill_index + 1) as i128 / escrow.parts as i128
+    }
+}
+
+/// Highest fill index revealed so far.
+fn last_fill_index(escrow: &Escrow) -> Option<u32> {
+    (0..escrow.used_parts.len())
+        .rev()
+        .find(|index| escrow.used_parts.get_unchecked(*index))
+}
+
+/// Amount already released by partial fills.
+fn released_amount(escrow: &Escrow) -> i128 {
+    last_fill_index(escrow).map_or(0, |index| fill_level(escrow, index))
+}
+
+/// Amount still locked in the escrow.
+fn remaining_amount(escrow: &Escrow) -> i128 {
+    if escrow.parts <= 1 {
+        escrow.amount
+    } else {
+        escrow.amount - released_amount(escrow)
+    }
+}
+
+/// Every leg of the escrow, the primary token carrying `amount`.
+fn legs(env: &Env, escrow: &Escrow, amount: i128) -> Vec<TokenAmount> {
+    let mut legs = Vec::new(env);
+    legs.push_back(TokenAmount {
+        token: escrow.token.clone(),
+        amount,
+    });
+    legs.append(&escrow.bundle);
+    legs
+}
+
+/// Advance the status for a withdrawal and return the amount it unlocks.
+///
+/// Fills are cumulative: a multi-part escrow releases what is missing up to
+/// the level of `fill_index` and is withdrawn once that level is the full
+/// amount.
+fn release(escrow: &mut Escrow, fill_index: u32) -> i128 {
+    if escrow.parts <= 1 {
+        escrow.status = EscrowStatus::Withdrawn;
+        return escrow.amount;
+    }
+
+    let released = released_amount(escrow);
+    let level = fill_level(escrow, fill_index);
+    escrow.used_parts.set(fill_index, true);
+    escrow.status = if level == escrow.amount {
+        EscrowStatus::Withdrawn
+    } else {
+        EscrowStatus::PartiallyFilled
+    };
+    level - released
+}
+
+/// Hash a 32-byte secret. Keccak256 over exactly these 32 bytes is what the EVM
+/// escrows check in `BaseEscrow.onlyValidSecret`.
+pub fn hash_secret(env: &Env, algorithm: HashAlgorithm, preimage: &BytesN<32>) -> BytesN<32> {
+    let preimage = Bytes::from(preimage);
+    match algorithm {
+        HashAlgorithm::Keccak256 => env.crypto().keccak256(&preimage).to_bytes(),
+        HashAlgorithm::Sha256 => env.crypto().sha256(&preimage).to_bytes(),
+    }
+}
+
+/// Check the preimage against the hashlock or, for partial fills, the Merkle
+/// proof for `fill_index`.
+///
+/// Each fill level has its own secret, so those are only checked against the
+/// Merkle root. Levels only go up, so `fill_index` must be above every index
+/// revealed before.
+fn verify_secret(
+    env: &Env,
+    escrow: &Escrow,
+    preimage: &BytesN<32>,
+    merkle_proof: Vec<BytesN<32>>,
+    fill_index: u32,
+) -> Result<(), EscrowError> {
+    let computed_hash = hash_secret(env, escrow.hashlock.algorithm, preimage);
+    if escrow.parts <= 1 && computed_hash != escrow.hashlock.value {
+        return Err(EscrowError::InvalidPreimage);
+    }
+
+    // Verify partial fill if using Merkle tree
+    if escrow.parts > 1 {
+        if fill_index >= secret_count(escrow) {
+            return Err(EscrowError::InvalidFillIndex);
+        }
+        if last_fill_index(escrow).is_some_and(|last| fill_index <= last) {
+            return Err(EscrowError::PartAlreadyUsed);
+        }
+
+        // Leaves use the escrow's hash algorithm, the tree itself is always
+        // built with Keccak256
+        let leaf = computed_hash.clone();
+        if !Verifier::<Keccak256>::verify_with_index(
+            env,
+            merkle_proof,
+            escrow.merkle_root.clone(),
+            leaf,
+            fill_index,
+        ) {
+            return Err(EscrowError::InvalidMerkleProof);
+        }
+    }
+
+    Ok(())
+}
+
+fn check_window(
+    env: &Env,
+    escrow: &Escrow,
+    start: u32,
+    end: Option<u32>,
+) -> Result<(), EscrowError> {
+    let current_time = env.ledger().timestamp();
+    if current_time < escrow.timelocks.get(start).unwrap() {
+        return Err(EscrowError::StageNotReached);
+    }
+    if let Some(end) = end {
+        if current_time >= escrow.timelocks.get(end).unwrap() {
+            return Err(EscrowError::StageExpired);
+        }
+    }
+    Ok(())
+}
+
+pub fn withdraw(
+    env: &Env,
+    authorized: &mut Vec<Address>,
+    order_hash: BytesN<32>,
+    preimage: BytesN<32>,
+    merkle_proof: Vec<BytesN<32>>,
+    fill_index: u32,
+) -> Result<Settlement, EscrowError> {
+    let mut escrow = load_open_escrow(env, &order_hash)?;
+    verify_secret(env, &escrow, &preimage, merkle_proof, fill_index)?;
+
+    // Check authorization and timing
+    check_window(
+        env,
+        &escrow,
+        STAGE_SRC_WITHDRAWAL,
+        Some(STAGE_SRC_CANCELLATION),
+    )?;
+
+    // Determine recipient and verify authorization
+    let (recipient, caller) = if escrow.is_src {
+        // EscrowSrc: only taker can withdraw, tokens go to taker
+        (escrow.taker.clone(), escrow.taker.clone())
+    } else {
+        // EscrowDst: only taker can withdraw, tokens go to the maker's receiver
+        (escrow.receiver.clone(), escrow.taker.clone())
+    };
+
+    require_auth_once(authorized, &caller);
+
+    let amount = release(&mut escrow, fill_index);
+    let released = legs(env, &escrow, amount);
+    save_escrow(env, &order_hash, &escrow);
+
+    // Return safety deposit to whoever funded the escrow with the last part
+    let safety_deposit = if escrow.status == EscrowStatus::Withdrawn {
+        escrow.safety_deposit
+    } else {
+        0
+    };
+    if safety_deposit > 0 {
+        env.events().publish(
+            (symbol_short!("safety"),),
+            SafetyDepositClaimedEvent {
+                order_hash: order_hash.clone(),
+                executor: escrow.funder.clone(),
+                amount: escrow.safety_deposit,
+            },
+        );
+    }
+
+    env.events().publish(
+        (symbol_short!("claimed"),),
+        FundsClaimedEvent {
+            order_hash,
+            preimage,
+            recipient: recipient.clone(),
+            amount,
+            merkle_root: escrow.merkle_root.clone(),
+            parts: escrow.parts,
+            legs: released.clone(),
+        },
+    );
+
+    let mut settlement = Settlement::new(env);
+    settlement.add_legs(&released, &recipient);
+    settlement.add(&escrow.token, &escrow.funder, safety_deposit);
+    Ok(settlement)
+}
+
+/// Replace the timelocks of an open escrow with later ones.
+///
+/// Every deadline must stay at or after its current value and in stage order.
+/// The finality start is the creation time and cannot move, and public
+/// cancellation must stay within `MAX_ESCROW_LIFETIME` of it so scheduled
+/// upgrades still wait out every escrow. Destination escrows must also keep
+/// their margin before the source cancellation.
+pub fn extend_timelocks(
+    env: &Env,
+    authorized: &mut Vec<Address>,
+    order_hash: BytesN<32>,
+    new_timelocks: Vec<u64>,
+) -> Result<(), EscrowError> {
+    let mut escrow = load_open_escrow(env, &order_hash)?;
+    require_auth_once(authorized, &escrow.maker);
+    require_auth_once(authorized, &escrow.taker);
+
+    let old_timelocks = escrow.timelocks.clone();
+    if new_timelocks.len() != old_timelocks.len() {
+        return Err(EscrowError::InvalidTimelocks);
+    }
+    let created = old_timelocks.get(STAGE_FINALITY).unwrap();
+    if new_timelocks.get(STAGE_FINALITY).unwrap() != created {
+        return Err(EscrowError::InvalidTimelocks);
+    }
+    let mut previous = created;
+    for (old, new) in old_timelocks.iter().zip(new_timelocks.iter()) {
+        if new < old || new < previous {
+            return Err(EscrowError::InvalidTimelocks);
+        }
+        previous = new;
+    }
+    if previous > created + MAX_ESCROW_LIFETIME {
+        return Err(EscrowError::InvalidTimelocks);
+    }
+    if let Some(src_cancellation) = escrow.src_cancellation {
+        schedule::check_src_margin(src_cancellation, &new_timelocks)?;
+    }
+
+    escrow.timelocks = new_timelocks.clone();
+    save_escrow(env, &order_hash, &escrow);
+    extend_escrow_ttl(env, &order_hash, &escrow);
+
+    env.events().publish(
+        (symbol_short!("extended"),),
+        TimelocksExtendedEvent {
+            order_hash,
+            old_timelocks,
+            new_timelocks,
+        },
+    );
+    Ok(())
+}
+
+pub fn cancel(
+    env: &Env,
+    authorized: &mut Vec<Address>,
+    order_hash: BytesN<32>,
+) -> Result<Settlement, EscrowError> {
+    let mut escrow = load_open_escrow(env, &order_hash)?;
+
+    // Check timing
+    check_window(env, &escrow, STAGE_SRC_CANCELLATION, None)?;
+
+    // Only the taker can cancel, tokens go back to whoever funded the
+    // escrow: the maker of a source escrow, the maker or the resolver of
+    // a destination escrow
+    let refund_recipient = escrow.funder.clone();
+    require_auth_once(authorized, &escrow.taker);
+
+    let amount = remaining_amount(&escrow);
+    let refunded = legs(env, &escrow, amount);
+    escrow.status = EscrowStatus::Cancelled;
+    save_escrow(env, &order_hash, &escrow);
+
+    env.events().publish(
+        (symbol_short!("refunded"),),
+        FundsRefundedEvent {
+            order_hash,
+            sender: refund_recipient.clone(),
+            amount,
+            merkle_root: BytesN::from_array(env, &[0u8; 32]),
+            parts: 0,
+            legs: refunded.clone(),
+            status: escrow.status,
+        },
+    );
+
+    // Refund amount, return safety deposit to the funder
+    let mut settlement = Settlement::new(env);
+    settlement.add_legs(&refunded, &refund_recipient);
+    settlement.add(&escrow.token, &escrow.funder, escrow.safety_deposit);
+    Ok(settlement)
+}
+
+pub fn public_withdraw(
+    env: &Env,
+    order_hash: BytesN<32>,
+    preimage: BytesN<32>,
+    merkle_proof: Vec<BytesN<32>>,
+    fill_index: u32,
+    executor: &Address,
+) -> Result<Settlement, EscrowError> {
+    let mut escrow = load_open_escrow(env, &order_hash)?;
+    verify_secret(env, &escrow, &preimage, merkle_proof, fill_index)?;
+
+    // Check timing for public withdrawal
+    check_window(
+        env,
+        &escrow,
+        STAGE_SRC_PUBLIC_WITHDRAWAL,
+        Some(STAGE_SRC_PUBLIC_CANCELLATION),
+    )?;
+
+    // Determine recipient
+    let recipient = if escrow.is_src {
+        escrow.taker.clone()
+    } else {
+        escrow.receiver.clone()
+    };
+
+    let amount = release(&mut escrow, fill_index);
+    let released = legs(env, &escrow, amount);
+    save_escrow(env, &order_hash, &escrow);
+
+    // Award safety deposit to executor as incentive with the last part
+    let safety_deposit = if escrow.status == EscrowStatus::Withdrawn {
+        escrow.safety_deposit
+    } else {
+        0
+    };
+    if safety_deposit > 0 {
+        env.events().publish(
+            (symbol_short!("safety"),),
+            SafetyDepositClaimedEvent {
+                order_hash: order_hash.clone(),
+                executor: executor.clone(),
+                amount: escrow.safety_deposit,
+            },
+        );
+    }
+
+    env.events().publish(
+        (symbol_short!("claimed"),),
+        FundsClaimedEvent {
+            order_hash,
+            preimage,
+            recipient: recipient.clone(),
+            amount,
+            merkle_root: BytesN::from_array(env, &[0u8; 32]),
+            parts: 0,
+            legs: released.clone(),
+        },
+    );
+
+    let mut settlement = Settlement::new(env);
+    settlement.add_legs(&released, &recipient);
+    settlement.add(&escrow.token, executor, safety_deposit);
+    Ok(settlement)
+}
+
+pub fn public_cancel(
+    env: &Env,
+    order_hash: BytesN<32>,
+    executor: &Address,
+) -> Result<Settlement, EscrowError> {
+    let mut escrow = load_open_escrow(env, &order_hash)?;
+
+    // Only available on SRC chain
+    if !escrow.is_src {
+        return Err(EscrowError::Unauthorized);
+    }
+
+    // Check timing for public cancellation
+    check_window(env, &escrow, STAGE_SRC_PUBLIC_CANCELLATION, None)?;
+
+    let amount = remaining_amount(&escrow);
+    let refunded = legs(env, &escrow, amount);
+    escrow.status = EscrowStatus::PublicCancelled;
+    save_escrow(env, &order_hash, &escrow);
+
+    // On SRC chain: refund to maker, safety deposit to executor
+    let refund_recipient = escrow.maker.clone();
+
+    if escrow.safety_deposit > 0 {
+        env.events().publish(
+            (symbol_short!("safety"),),
+            SafetyDepositClaimedEvent {
+                order_hash: order_hash.clone(),
+                executor: executor.clone(),
+                amount: escrow.safety_deposit,
+            },
+        );
+    }
+
+    env.events().publish(
+        (symbol_short!("refunded"),),
+        FundsRefundedEvent {
+            order_hash,
+            sender: refund_recipient.clone(),
+            amount,
+            merkle_root: escrow.merkle_root.clone(),
+            parts: escrow.parts,
+            legs: refunded.clone(),
+            status: escrow.status,
+        },
+    );
+
+    let mut settlement = Settlement::new(env);
+    settlement.add_legs(&refunded, &refund_recipient);
+    settlement.add(&escrow.token, executor, escrow.safety_deposit);
+    Ok(settlement)
+}
@@ -1,21 +1,1491 @@
 #![cfg(test)]
+extern crate std;
 
 use super::*;
-use soroban_sdk::{vec, Env, String};
+use proptest::prelude::*;
+use soroban_sdk::{
+    testutils::{storage::Persistent as _, AuthorizedFunction, EnvTestConfig},
+    testutils::{Address as _, Events, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    vec, Address, Bytes, BytesN, Env, IntoVal,
+};
+
+const RESCUE_DELAY: u32 = 3600;
+
+struct Setup<'a> {
+    env: Env,
+    admin: Address,
+    maker: Address,
+    taker: Address,
+    token: TokenClient<'a>,
+    escrow: EscrowContractClient<'a>,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    setup_in(Env::default())
+}
+
+/// Environment for property tests, which would otherwise write a snapshot
+/// for every generated case.
+fn unsnapshotted_env() -> Env {
+    Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    })
+}
+
+fn setup_in<'a>(env: Env) -> Setup<'a> {
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000_000);
+
+    let admin = Address::generate(&env);
+    let maker = Address::generate(&env);
+    let taker = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env
+        .register_stellar_asset_contract_v2(token_admin)
+        .address();
+    StellarAssetClient::new(&env, &token_id).mint(&maker, &1_000_000);
+    let token = TokenClient::new(&env, &token_id);
+
+    let escrow_id = env.register(EscrowContract, (&admin,));
+    let escrow = EscrowContractClient::new(&env, &escrow_id);
+
+    Setup {
+        env,
+        admin,
+        maker,
+        taker,
+        token,
+        escrow,
+    }
+}
+
+fn secret(env: &Env) -> (BytesN<32>, BytesN<32>) {
+    let preimage = BytesN::from_array(env, &[7u8; 32]);
+    let hashlock = env.crypto().keccak256(&preimage.clone().into()).to_bytes();
+    (preimage, hashlock)
+}
+
+/// Tree over the secret hashes of a two-part escrow, padded to four leaves,
+/// with the proof for every fill index.
+fn fill_tree(env: &Env, hashes: &[BytesN<32>; 3]) -> (BytesN<32>, [Vec<BytesN<32>>; 3]) {
+    let hash_pair = |left: &BytesN<32>, right: &BytesN<32>| {
+        let mut pair = Bytes::from(left);
+        pair.append(&right.clone().into());
+        env.crypto().keccak256(&pair).to_bytes()
+    };
+    let padding = BytesN::from_array(env, &[0u8; 32]);
+    let left = hash_pair(&hashes[0], &hashes[1]);
+    let right = hash_pair(&hashes[2], &padding);
+    (
+        hash_pair(&left, &right),
+        [
+            vec![env, hashes[1].clone(), right.clone()],
+            vec![env, hashes[0].clone(), right],
+            vec![env, padding, left],
+        ],
+    )
+}
+
+/// Reference for `BaseEscrow._keccakBytes32`: keccak256 over the 32 secret
+/// bytes, computed off-chain so it does not share code with the contract.
+fn evm_hashlock(secret: &[u8; 32]) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+    Keccak256::digest(secret).into()
+}
+
+fn create_escrow(s: &Setup, is_src: bool) -> BytesN<32> {
+    create_escrow_with_amount(s, is_src, 1_000)
+}
+
+fn escrow_args(s: &Setup, is_src: bool, amount: i128) -> CreateEscrowArgs {
+    let (_, hashlock) = secret(&s.env);
+    CreateEscrowArgs {
+        maker: s.maker.clone(),
+        taker: s.taker.clone(),
+        receiver: None,
+        token: s.token.address.clone(),
+        amount,
+        bundle: Vec::new(&s.env),
+        safety_deposit: 100,
+        hashlock,
+        hash_algorithm: HashAlgorithm::Keccak256,
+        rescue_delay: RESCUE_DELAY,
+        is_src,
+        merkle_root: BytesN::from_array(&s.env, &[0u8; 32]),
+        parts: 1,
+        resolvers: AllowedResolvers::Taker,
+        resolver_proof: Vec::new(&s.env),
+        src_cancellation: if is_src {
+            None
+        } else {
+            Some(s.env.ledger().timestamp() + 4 * RESCUE_DELAY as u64)
+        },
+    }
+}
+
+fn create_escrow_with_amount(s: &Setup, is_src: bool, amount: i128) -> BytesN<32> {
+    s.escrow.create_escrow(&escrow_args(s, is_src, amount))
+}
+
+fn advance(env: &Env, seconds: u64) {
+    let now = env.ledger().timestamp();
+    env.ledger().set_timestamp(now + seconds);
+}
+
+#[test]
+fn test_withdraw_src() {
+    let s = setup();
+    let order_hash = create_escrow(&s, true);
+    assert_eq!(s.token.balance(&s.escrow.address), 1_100);
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+
+    assert_eq!(s.token.balance(&s.taker), 1_000);
+    assert_eq!(s.token.balance(&s.maker), 1_000_000 - 1_000);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+}
+
+#[test]
+#[should_panic(expected = "Invalid rescue delay")]
+fn test_rescue_delay_above_max() {
+    let s = setup();
+    s.escrow.create_escrow(&CreateEscrowArgs {
+        rescue_delay: MAX_RESCUE_DELAY + 1,
+        ..escrow_args(&s, true, 1_000)
+    });
+}
+
+#[test]
+fn test_schedule_and_cancel_upgrade() {
+    let s = setup();
+    let wasm_hash = BytesN::from_array(&s.env, &[1u8; 32]);
+
+    let eta = s.escrow.schedule_upgrade(&wasm_hash);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(eta, s.env.ledger().timestamp() + UPGRADE_DELAY);
+    assert_eq!(
+        s.escrow.get_pending_upgrade(),
+        Some(PendingUpgrade { wasm_hash, eta })
+    );
+
+    s.escrow.cancel_upgrade();
+    assert_eq!(s.escrow.get_pending_upgrade(), None);
+}
+
+#[test]
+fn test_upgrade_delay_outlives_escrows() {
+    let s = setup();
+    s.escrow
+        .schedule_upgrade(&BytesN::from_array(&s.env, &[1u8; 32]));
+
+    // An escrow created right after the announcement with the longest
+    // allowed schedule is publicly cancellable before the upgrade lands.
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        rescue_delay: MAX_RESCUE_DELAY,
+        ..escrow_args(&s, true, 1_000)
+    });
+    let escrow = s.escrow.get_escrow_info(&order_hash).unwrap();
+    let public_cancellation = escrow.timelocks.get(STAGE_SRC_PUBLIC_CANCELLATION).unwrap();
+    assert!(public_cancellation < s.escrow.get_pending_upgrade().unwrap().eta);
+}
+
+#[test]
+#[should_panic(expected = "Upgrade delay not elapsed")]
+fn test_apply_upgrade_before_delay() {
+    let s = setup();
+    s.escrow
+        .schedule_upgrade(&BytesN::from_array(&s.env, &[1u8; 32]));
+
+    advance(&s.env, UPGRADE_DELAY - 1);
+    s.escrow.apply_upgrade();
+}
+
+/// Pretend the deployment still runs storage version 1.
+fn set_storage_version_1(s: &Setup) {
+    s.env.as_contract(&s.escrow.address, || {
+        upgrade::write_storage_version(&s.env, 1);
+    });
+}
+
+#[test]
+#[should_panic(expected = "Storage migration pending")]
+fn test_apply_upgrade_before_migration() {
+    let s = setup();
+    set_storage_version_1(&s);
+    s.escrow
+        .schedule_upgrade(&BytesN::from_array(&s.env, &[1u8; 32]));
+
+    advance(&s.env, UPGRADE_DELAY);
+    s.escrow.apply_upgrade();
+}
+
+#[test]
+fn test_migrate_checks_stored_version() {
+    let s = setup();
+    assert_eq!(s.escrow.get_storage_version(), STORAGE_VERSION);
+    assert!(s.escrow.try_migrate(&STORAGE_VERSION).is_err());
+
+    set_storage_version_1(&s);
+    assert!(s.escrow.try_migrate(&2).is_err());
+    assert_eq!(s.escrow.migrate(&1), STORAGE_VERSION);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(
+        s.env.events().all(),
+        vec![
+            &s.env,
+            (
+                s.escrow.address.clone(),
+                (symbol_short!("upgrade"), symbol_short!("migrated")).into_val(&s.env),
+                StorageMigratedEvent {
+                    from_version: 1,
+                    to_version: STORAGE_VERSION,
+                }
+                .into_val(&s.env),
+            ),
+        ]
+    );
+    assert_eq!(s.escrow.get_storage_version(), STORAGE_VERSION);
+
+    // A migration only runs once
+    assert!(s.escrow.try_migrate(&1).is_err());
+}
+
+#[test]
+fn test_version_1_escrow_keeps_working() {
+    let s = setup();
+    let (preimage, hashlock) = secret(&s.env);
+    let order_hash = BytesN::from_array(&s.env, &[9u8; 32]);
+    let now = s.env.ledger().timestamp();
+    let delay = RESCUE_DELAY as u64;
+    let legacy = upgrade::EscrowV1 {
+        maker: s.maker.clone(),
+        taker: s.taker.clone(),
+        token: s.token.address.clone(),
+        amount: 1_000,
+        safety_deposit: 100,
+        hashlock: upgrade::HashLockV1 {
+            value: hashlock.clone(),
+        },
+        timelocks: vec![
+            &s.env,
+            now,
+            now + delay,
+            now + 2 * delay,
+            now + 3 * delay,
+            now + 4 * delay,
+        ],
+        claimed: false,
+        cancelled: false,
+        is_src: true,
+        merkle_root: BytesN::from_array(&s.env, &[3u8; 32]),
+        parts: 2,
+        used_parts: vec![&s.env, false, false],
+    };
+    s.env.as_contract(&s.escrow.address, || {
+        s.env
+            .storage()
+            .persistent()
+            .set(&DataKey::Escrow(order_hash.clone()), &legacy);
+        upgrade::write_storage_version(&s.env, 1);
+    });
+    StellarAssetClient::new(&s.env, &s.token.address).mint(&s.escrow.address, &1_100);
+
+    s.escrow.migrate(&1);
+    let escrow = s.escrow.get_escrow_info(&order_hash).unwrap();
+    assert_eq!(escrow.status, EscrowStatus::Active);
+    assert_eq!(escrow.funder, s.maker);
+    assert_eq!(escrow.hashlock.value, hashlock);
+    assert_eq!(escrow.parts, 1);
+
+    advance(&s.env, delay);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+    assert_eq!(
+        s.escrow.get_status(&order_hash),
+        Some(EscrowStatus::Withdrawn)
+    );
+    assert_eq!(s.token.balance(&s.taker), 1_000);
+    assert_eq!(s.token.balance(&s.maker), 1_000_000 + 100);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+}
+
+#[test]
+#[should_panic(expected = "Upgrade already scheduled")]
+fn test_schedule_upgrade_twice() {
+    let s = setup();
+    s.escrow
+        .schedule_upgrade(&BytesN::from_array(&s.env, &[1u8; 32]));
+    s.escrow
+        .schedule_upgrade(&BytesN::from_array(&s.env, &[2u8; 32]));
+}
+
+#[test]
+fn test_set_admin_emits_event() {
+    let s = setup();
+    let new_admin = Address::generate(&s.env);
+
+    s.escrow.set_admin(&new_admin);
+    assert_eq!(s.env.auths()[0].0, s.admin);
+    assert_eq!(
+        s.env.events().all(),
+        vec![
+            &s.env,
+            (
+                s.escrow.address.clone(),
+                (symbol_short!("admin"),).into_val(&s.env),
+                AdminChangedEvent {
+                    old_admin: s.admin.clone(),
+                    new_admin: new_admin.clone(),
+                }
+                .into_val(&s.env),
+            ),
+        ]
+    );
+
+    // Only the new admin can schedule upgrades from now on
+    s.escrow
+        .schedule_upgrade(&BytesN::from_array(&s.env, &[1u8; 32]));
+    assert_eq!(s.env.auths()[0].0, new_admin);
+}
+
+#[test]
+fn test_batch_withdraw_skips_stale_items() {
+    let s = setup();
+    let first = create_escrow_with_amount(&s, true, 1_000);
+    let second = create_escrow_with_amount(&s, true, 2_000);
+    let missing = BytesN::from_array(&s.env, &[9u8; 32]);
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    let item = |order_hash: &BytesN<32>| WithdrawArgs {
+        order_hash: order_hash.clone(),
+        preimage: preimage.clone(),
+        merkle_proof: Vec::new(&s.env),
+        fill_index: 0,
+    };
+
+    let results = s.escrow.batch_withdraw(&vec![
+        &s.env,
+        item(&first),
+        item(&missing),
+        item(&second),
+        item(&first),
+    ]);
+    assert_eq!(
+        results,
+        vec![
+            &s.env,
+            BatchResult::Success,
+            BatchResult::Failed(EscrowError::EscrowNotFound as u32),
+            BatchResult::Success,
+            BatchResult::Failed(EscrowError::AlreadyFinalized as u32),
+        ]
+    );
+    assert_eq!(s.token.balance(&s.taker), 3_000);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+}
+
+#[test]
+fn test_batch_cancel_and_public_cancel() {
+    let s = setup();
+    let executor = Address::generate(&s.env);
+    let src = create_escrow_with_amount(&s, true, 1_000);
+    let dst = create_escrow_with_amount(&s, false, 2_000);
+
+    advance(&s.env, 3 * RESCUE_DELAY as u64);
+    let results = s
+        .escrow
+        .batch_public_cancel(&vec![&s.env, src.clone(), dst.clone()], &executor);
+    assert_eq!(
+        results,
+        vec![
+            &s.env,
+            BatchResult::Failed(EscrowError::StageNotReached as u32),
+            BatchResult::Failed(EscrowError::Unauthorized as u32),
+        ]
+    );
+
+    let results = s.escrow.batch_cancel(&vec![&s.env, src, dst]);
+    assert_eq!(
+        results,
+        vec![&s.env, BatchResult::Success, BatchResult::Success]
+    );
+    // The maker funded both escrows and gets everything back
+    assert_eq!(s.token.balance(&s.maker), 1_000_000);
+    assert_eq!(s.token.balance(&s.taker), 0);
+    assert_eq!(s.token.balance(&executor), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #7)")]
+fn test_cancel_before_cancellation() {
+    let s = setup();
+    let order_hash = create_escrow(&s, true);
+    s.escrow.cancel(&order_hash);
+}
+
+#[test]
+fn test_escrow_indexes() {
+    let s = setup();
+    let other_taker = Address::generate(&s.env);
+    let first = create_escrow_with_amount(&s, true, 1_000);
+    let second = create_escrow_with_amount(&s, true, 2_000);
+    let third = create_escrow_with_amount(&s, true, 3_000);
+
+    let other = s.escrow.create_escrow(&CreateEscrowArgs {
+        taker: other_taker.clone(),
+        ..escrow_args(&s, true, 4_000)
+    });
+
+    let page = s.escrow.escrows_by_taker(&s.taker, &0, &2);
+    assert_eq!(
+        page.order_hashes,
+        vec![&s.env, first.clone(), second.clone()]
+    );
+    assert_eq!(page.next_cursor, Some(2));
+    assert_eq!(page.total, 3);
+
+    let page = s.escrow.escrows_by_taker(&s.taker, &2, &2);
+    assert_eq!(page.order_hashes, vec![&s.env, third.clone()]);
+    assert_eq!(page.next_cursor, None);
+
+    assert_eq!(s.escrow.escrows_by_maker(&s.maker, &0, &10).total, 4);
+    assert_eq!(
+        s.escrow
+            .escrows_by_taker(&other_taker, &0, &10)
+            .order_hashes,
+        vec![&s.env, other.clone()]
+    );
+
+    // Finalizing an escrow removes it from every index
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow.withdraw(&first, &preimage, &Vec::new(&s.env), &0);
+
+    assert_eq!(
+        s.escrow.escrows_by_taker(&s.taker, &0, &10).order_hashes,
+        vec![&s.env, third.clone(), second.clone()]
+    );
+    assert_eq!(
+        s.escrow.active_escrows(&0, &10).order_hashes,
+        vec![&s.env, other, second, third]
+    );
+    assert_eq!(s.escrow.escrows_by_maker(&s.maker, &0, &10).total, 3);
+}
+
+#[test]
+fn test_indexes_outlive_default_ttl() {
+    let s = setup();
+    let first = create_escrow_with_amount(&s, true, 1_000);
+    let second = create_escrow_with_amount(&s, true, 2_000);
+
+    // Only the index entries are under test, the instance is kept alive
+    // the way a deployment would be
+    let max_ttl = s.env.ledger().get().max_entry_ttl - 1;
+    s.env.as_contract(&s.escrow.address, || {
+        s.env.storage().instance().extend_ttl(max_ttl, max_ttl)
+    });
+    let default_ttl = s.env.ledger().get().min_persistent_entry_ttl;
+    s.env.ledger().with_mut(|ledger| {
+        ledger.sequence_number += default_ttl + 1;
+        ledger.timestamp += 3 * RESCUE_DELAY as u64;
+    });
+
+    assert_eq!(
+        s.escrow.escrows_by_maker(&s.maker, &0, &10).order_hashes,
+        vec![&s.env, first.clone(), second.clone()]
+    );
+    s.escrow.cancel(&first);
+    let third = create_escrow_with_amount(&s, true, 3_000);
+    assert_eq!(
+        s.escrow.active_escrows(&0, &10).order_hashes,
+        vec![&s.env, second, third]
+    );
+}
+
+#[test]
+fn test_status_lifecycle() {
+    let s = setup();
+    let executor = Address::generate(&s.env);
+    assert_eq!(
+        s.escrow.get_status(&BytesN::from_array(&s.env, &[9u8; 32])),
+        None
+    );
+
+    let withdrawn = create_escrow_with_amount(&s, true, 1_000);
+    let public_cancelled = create_escrow_with_amount(&s, true, 2_000);
+    assert_eq!(s.escrow.get_status(&withdrawn), Some(EscrowStatus::Active));
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow
+        .withdraw(&withdrawn, &preimage, &Vec::new(&s.env), &0);
+    assert_eq!(
+        s.escrow.get_status(&withdrawn),
+        Some(EscrowStatus::Withdrawn)
+    );
+
+    // Public cancellation keeps a tombstone instead of deleting the entry
+    advance(&s.env, 3 * RESCUE_DELAY as u64);
+    s.escrow.public_cancel(&public_cancelled, &executor);
+    let (_, _, data) = s
+        .env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| *contract == s.escrow.address)
+        .last()
+        .unwrap();
+    let event: FundsRefundedEvent = data.into_val(&s.env);
+    assert_eq!(event.status, EscrowStatus::PublicCancelled);
+    assert_eq!(
+        s.escrow.get_status(&public_cancelled),
+        Some(EscrowStatus::PublicCancelled)
+    );
+    assert!(s.escrow.escrow_exists(&public_cancelled));
+    assert_eq!(s.escrow.active_escrows(&0, &10).total, 0);
+}
+
+#[test]
+fn test_partial_fill_then_cancel() {
+    let s = setup();
+    let (preimage, hashlock) = secret(&s.env);
+    let (merkle_root, proofs) = fill_tree(
+        &s.env,
+        &[hashlock.clone(), hashlock.clone(), hashlock.clone()],
+    );
+
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        hashlock: merkle_root.clone(),
+        merkle_root,
+        parts: 2,
+        ..escrow_args(&s, true, 1_001)
+    });
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    s.escrow.withdraw(&order_hash, &preimage, &proofs[0], &0);
+    assert_eq!(
+        s.escrow.get_status(&order_hash),
+        Some(EscrowStatus::PartiallyFilled)
+    );
+    assert_eq!(s.token.balance(&s.taker), 500);
+    assert_eq!(s.escrow.active_escrows(&0, &10).total, 1);
+
+    advance(&s.env, 2 * RESCUE_DELAY as u64);
+    s.escrow.cancel(&order_hash);
+    assert_eq!(
+        s.escrow.get_status(&order_hash),
+        Some(EscrowStatus::Cancelled)
+    );
+    assert_eq!(s.token.balance(&s.maker), 1_000_000 - 500);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+}
+
+#[test]
+fn test_partial_fills_with_own_secrets() {
+    let s = setup();
+    let secrets =
+        [[1u8; 32], [2u8; 32], [3u8; 32]].map(|secret| BytesN::from_array(&s.env, &secret));
+    let hashes = secrets
+        .clone()
+        .map(|secret| settlement::hash_secret(&s.env, HashAlgorithm::Keccak256, &secret));
+    let (merkle_root, proofs) = fill_tree(&s.env, &hashes);
+
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        hashlock: merkle_root.clone(),
+        merkle_root,
+        parts: 2,
+        ..escrow_args(&s, true, 1_000)
+    });
+    advance(&s.env, RESCUE_DELAY as u64);
+
+    // A secret only opens the fill level it was generated for
+    assert_eq!(
+        s.escrow
+            .try_withdraw(&order_hash, &secrets[1], &proofs[1], &0),
+        Err(Ok(EscrowError::InvalidMerkleProof.into()))
+    );
+    s.escrow.withdraw(&order_hash, &secrets[0], &proofs[0], &0);
+    assert_eq!(s.token.balance(&s.taker), 500);
+
+    // Fill levels only go up
+    assert_eq!(
+        s.escrow
+            .try_withdraw(&order_hash, &secrets[0], &proofs[0], &0),
+        Err(Ok(EscrowError::PartAlreadyUsed.into()))
+    );
+    assert_eq!(
+        s.escrow
+            .try_withdraw(&order_hash, &secrets[2], &proofs[2], &3),
+        Err(Ok(EscrowError::InvalidFillIndex.into()))
+    );
+
+    // The last secret completes the fill with what is left
+    s.escrow.withdraw(&order_hash, &secrets[2], &proofs[2], &2);
+    assert_eq!(
+        s.escrow.get_status(&order_hash),
+        Some(EscrowStatus::Withdrawn)
+    );
+    assert_eq!(s.token.balance(&s.taker), 1_000);
+    assert_eq!(s.token.balance(&s.maker), 1_000_000 - 1_000);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+}
+
+#[test]
+fn test_final_fill_index_releases_everything() {
+    let s = setup();
+    let secrets =
+        [[1u8; 32], [2u8; 32], [3u8; 32]].map(|secret| BytesN::from_array(&s.env, &secret));
+    let hashes = secrets
+        .clone()
+        .map(|secret| settlement::hash_secret(&s.env, HashAlgorithm::Keccak256, &secret));
+    let (merkle_root, proofs) = fill_tree(&s.env, &hashes);
+
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        hashlock: merkle_root.clone(),
+        merkle_root,
+        parts: 2,
+        ..escrow_args(&s, true, 1_001)
+    });
+    advance(&s.env, RESCUE_DELAY as u64);
+
+    // Filling the whole order at once skips straight to the last index
+    s.escrow.withdraw(&order_hash, &secrets[2], &proofs[2], &2);
+    assert_eq!(
+        s.escrow.get_status(&order_hash),
+        Some(EscrowStatus::Withdrawn)
+    );
+    assert_eq!(s.token.balance(&s.taker), 1_001);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
+    assert_eq!(
+        s.escrow
+            .try_withdraw(&order_hash, &secrets[0], &proofs[0], &0),
+        Err(Ok(EscrowError::AlreadyFinalized.into()))
+    );
+}
+
+#[test]
+fn test_stage_and_schedule() {
+    let s = setup();
+    let start = s.env.ledger().timestamp();
+    let order_hash = create_escrow(&s, true);
+    let delay = RESCUE_DELAY as u64;
+
+    assert_eq!(s.escrow.get_current_stage(&order_hash), Stage::Finality);
+    let schedule = s.escrow.get_schedule(&order_hash);
+    assert_eq!(schedule.current, Stage::Finality);
+    assert_eq!(schedule.next_stage_in, Some(delay));
+    assert_eq!(
+        schedule.stages,
+        vec![
+            &s.env,
+            StageWindow {
+                stage: Stage::Finality,
+                start,
+                end: Some(start + delay)
+            },
+            StageWindow {
+                stage: Stage::Withdrawal,
+                start: start + delay,
+                end: Some(start + 2 * delay)
+            },
+            StageWindow {
+                stage: Stage::PublicWithdrawal,
+                start: start + 2 * delay,
+                end: Some(start + 3 * delay)
+            },
+            StageWindow {
+                stage: Stage::Cancellation,
+                start: start + 3 * delay,
+                end: Some(start + 4 * delay)
+            },
+            StageWindow {
+                stage: Stage::PublicCancellation,
+                start: start + 4 * delay,
+                end: None
+            },
+        ]
+    );
+
+    advance(&s.env, 2 * delay + 10);
+    let schedule = s.escrow.get_schedule(&order_hash);
+    assert_eq!(schedule.current, Stage::PublicWithdrawal);
+    assert_eq!(schedule.next_stage_in, Some(delay - 10));
+
+    advance(&s.env, 2 * delay);
+    let schedule = s.escrow.get_schedule(&order_hash);
+    assert_eq!(schedule.current, Stage::PublicCancellation);
+    assert_eq!(schedule.next_stage_in, None);
+}
+
+#[test]
+fn test_dst_withdraw_pays_receiver() {
+    let s = setup();
+    let receiver = Address::generate(&s.env);
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        receiver: Some(receiver.clone()),
+        ..escrow_args(&s, false, 1_000)
+    });
+    assert_eq!(
+        s.escrow.get_escrow_info(&order_hash).unwrap().receiver,
+        receiver
+    );
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+
+    assert_eq!(s.token.balance(&receiver), 1_000);
+    assert_eq!(s.token.balance(&s.maker), 1_000_000 - 1_000);
+}
+
+#[test]
+fn test_receiver_defaults_to_maker() {
+    let s = setup();
+    let order_hash = create_escrow(&s, false);
+    assert_eq!(
+        s.escrow.get_escrow_info(&order_hash).unwrap().receiver,
+        s.maker
+    );
+
+    // The receiver is part of the order hash
+    let explicit = s.escrow.create_escrow(&CreateEscrowArgs {
+        receiver: Some(Address::generate(&s.env)),
+        ..escrow_args(&s, false, 1_000)
+    });
+    assert_ne!(order_hash, explicit);
+}
 
 #[test]
-fn test() {
-    let env = Env::default();
-    let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(&env, &contract_id);
+fn test_bundle_released_and_refunded_together() {
+    let s = setup();
+    let xlm_admin = Address::generate(&s.env);
+    let xlm_id = s
+        .env
+        .register_stellar_asset_contract_v2(xlm_admin)
+        .address();
+    StellarAssetClient::new(&s.env, &xlm_id).mint(&s.maker, &50_000);
+    let xlm = TokenClient::new(&s.env, &xlm_id);
+
+    let bundle = vec![
+        &s.env,
+        TokenAmount {
+            token: xlm_id.clone(),
+            amount: 5_000,
+        },
+    ];
+    let withdrawn = s.escrow.create_escrow(&CreateEscrowArgs {
+        bundle: bundle.clone(),
+        ..escrow_args(&s, true, 1_000)
+    });
+    let cancelled = s.escrow.create_escrow(&CreateEscrowArgs {
+        bundle,
+        ..escrow_args(&s, true, 2_000)
+    });
+    assert_eq!(xlm.balance(&s.escrow.address), 10_000);
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow
+        .withdraw(&withdrawn, &preimage, &Vec::new(&s.env), &0);
+    assert_eq!(s.token.balance(&s.taker), 1_000);
+    assert_eq!(xlm.balance(&s.taker), 5_000);
+
+    advance(&s.env, 2 * RESCUE_DELAY as u64);
+    s.escrow.cancel(&cancelled);
+    let (_, _, data) = s
+        .env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| *contract == s.escrow.address)
+        .last()
+        .unwrap();
+    assert_eq!(xlm.balance(&s.maker), 45_000);
+    assert_eq!(xlm.balance(&s.escrow.address), 0);
+    assert_eq!(s.token.balance(&s.escrow.address), 0);
 
-    let words = client.hello(&String::from_str(&env, "Dev"));
+    let event: FundsRefundedEvent = data.into_val(&s.env);
+    assert_eq!(event.status, EscrowStatus::Cancelled);
     assert_eq!(
-        words,
+        event.legs,
         vec![
-            &env,
-            String::from_str(&env, "Hello"),
-            String::from_str(&env, "Dev"),
+            &s.env,
+            TokenAmount {
+                token: s.token.address.clone(),
+                amount: 2_000,
+            },
+            TokenAmount {
+                token: xlm_id,
+                amount: 5_000,
+            },
         ]
     );
 }
+
+#[test]
+#[should_panic(expected = "Hashlock must be the merkle root of a partially fillable escrow")]
+fn test_parts_require_merkle_root_hashlock() {
+    let s = setup();
+    let (_, hashlock) = secret(&s.env);
+    let mut leaves = Bytes::new(&s.env);
+    leaves.append(&hashlock.clone().into());
+    leaves.append(&hashlock.clone().into());
+    let merkle_root = s.env.crypto().keccak256(&leaves).to_bytes();
+
+    s.escrow.create_escrow(&CreateEscrowArgs {
+        merkle_root,
+        parts: 2,
+        ..escrow_args(&s, true, 1_000)
+    });
+}
+
+#[test]
+#[should_panic(expected = "Invalid parts")]
+fn test_no_parts() {
+    let s = setup();
+    s.escrow.create_escrow(&CreateEscrowArgs {
+        parts: 0,
+        ..escrow_args(&s, true, 1_000)
+    });
+}
+
+#[test]
+#[should_panic(expected = "Invalid parts")]
+fn test_parts_above_max() {
+    let s = setup();
+    let (_, hashlock) = secret(&s.env);
+    s.escrow.create_escrow(&CreateEscrowArgs {
+        merkle_root: hashlock,
+        parts: MAX_PARTS + 1,
+        ..escrow_args(&s, true, 1_000)
+    });
+}
+
+#[test]
+#[should_panic(expected = "Bundles cannot be partially filled")]
+fn test_bundle_with_parts() {
+    let s = setup();
+    s.escrow.create_escrow(&CreateEscrowArgs {
+        bundle: vec![
+            &s.env,
+            TokenAmount {
+                token: s.token.address.clone(),
+                amount: 5,
+            },
+        ],
+        parts: 2,
+        ..escrow_args(&s, true, 1_000)
+    });
+}
+
+#[test]
+fn test_prefunded_dst_escrow() {
+    let s = setup();
+    StellarAssetClient::new(&s.env, &s.token.address).mint(&s.taker, &10_000);
+
+    let order_hash = s.escrow.create_dst_escrow(&escrow_args(&s, false, 1_000));
+    // Only the resolver signs, the maker is just the beneficiary
+    assert_eq!(s.env.auths().len(), 1);
+    assert_eq!(s.env.auths()[0].0, s.taker);
+    assert_eq!(s.token.balance(&s.taker), 10_000 - 1_100);
+    assert_eq!(s.token.balance(&s.maker), 1_000_000);
+
+    let escrow = s.escrow.get_escrow_info(&order_hash).unwrap();
+    assert_eq!(escrow.funder, s.taker);
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+
+    // Maker receives the amount, the safety deposit goes back to the resolver
+    assert_eq!(s.token.balance(&s.maker), 1_000_000 + 1_000);
+    assert_eq!(s.token.balance(&s.taker), 10_000 - 1_000);
+}
+
+#[test]
+fn test_prefunded_dst_escrow_cancel() {
+    let s = setup();
+    StellarAssetClient::new(&s.env, &s.token.address).mint(&s.taker, &10_000);
+    let order_hash = s.escrow.create_dst_escrow(&escrow_args(&s, false, 1_000));
+
+    advance(&s.env, 3 * RESCUE_DELAY as u64);
+    s.escrow.cancel(&order_hash);
+    assert_eq!(s.token.balance(&s.taker), 10_000);
+    assert_eq!(s.token.balance(&s.maker), 1_000_000);
+}
+
+#[test]
+#[should_panic(expected = "Pre-funded escrows must be destination escrows")]
+fn test_prefunded_src_escrow() {
+    let s = setup();
+    s.escrow.create_dst_escrow(&escrow_args(&s, true, 1_000));
+}
+
+fn setup_regulated_token<'a>(s: &Setup) -> tokens::TokenClient<'a> {
+    let token_admin = Address::generate(&s.env);
+    let token_id = s.env.register(
+        tokens::Token,
+        (
+            &token_admin,
+            7_u32,
+            soroban_sdk::String::from_str(&s.env, "USD Coin"),
+            soroban_sdk::String::from_str(&s.env, "USDC"),
+            None::<i128>,
+            None::<tokens::FaucetConfig>,
+        ),
+    );
+    let token = tokens::TokenClient::new(&s.env, &token_id);
+    token.mint(&s.maker, &1_000_000);
+    token
+}
+
+fn regulated_escrow_args(s: &Setup, token: &Address) -> CreateEscrowArgs {
+    CreateEscrowArgs {
+        token: token.clone(),
+        ..escrow_args(s, true, 1_000)
+    }
+}
+
+#[test]
+fn test_create_escrow_with_frozen_maker() {
+    let s = setup();
+    let token = setup_regulated_token(&s);
+    token.set_authorized(&s.maker, &false);
+
+    let args = regulated_escrow_args(&s, &token.address);
+    assert!(s.escrow.try_create_escrow(&args).is_err());
+    assert_eq!(token.balance(&s.maker), 1_000_000);
+}
+
+#[test]
+fn test_withdraw_blocked_while_escrow_frozen() {
+    let s = setup();
+    let token = setup_regulated_token(&s);
+    let order_hash = s
+        .escrow
+        .create_escrow(&regulated_escrow_args(&s, &token.address));
+
+    // Freezing the escrow contract's balance blocks the release...
+    token.set_authorized(&s.escrow.address, &false);
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    assert!(s
+        .escrow
+        .try_withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0)
+        .is_err());
+    assert_eq!(s.escrow.get_status(&order_hash), Some(EscrowStatus::Active));
+
+    // ...and the escrow stays withdrawable once the issuer lifts the freeze
+    token.set_authorized(&s.escrow.address, &true);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+    assert_eq!(token.balance(&s.taker), 1_000);
+}
+
+#[test]
+fn test_frozen_recipient_falls_back_to_cancel() {
+    let s = setup();
+    let token = setup_regulated_token(&s);
+    let order_hash = s
+        .escrow
+        .create_escrow(&regulated_escrow_args(&s, &token.address));
+
+    // A frozen taker cannot receive the source funds
+    token.set_authorized(&s.taker, &false);
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    assert!(s
+        .escrow
+        .try_withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0)
+        .is_err());
+
+    // The taker's cancel refunds the maker, which is still authorized
+    advance(&s.env, 2 * RESCUE_DELAY as u64);
+    s.escrow.cancel(&order_hash);
+    assert_eq!(token.balance(&s.maker), 1_000_000);
+    assert_eq!(
+        s.escrow.get_status(&order_hash),
+        Some(EscrowStatus::Cancelled)
+    );
+}
+
+#[test]
+fn test_clawback_blocks_settlement() {
+    let s = setup();
+    let token = setup_regulated_token(&s);
+    let order_hash = s
+        .escrow
+        .create_escrow(&regulated_escrow_args(&s, &token.address));
+
+    // The issuer claws back part of the locked amount
+    token.clawback(&s.escrow.address, &500);
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    assert!(s
+        .escrow
+        .try_withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0)
+        .is_err());
+
+    advance(&s.env, 2 * RESCUE_DELAY as u64);
+    assert!(s.escrow.try_cancel(&order_hash).is_err());
+
+    // Nothing moved and the escrow is still open
+    assert_eq!(token.balance(&s.escrow.address), 600);
+    assert_eq!(s.escrow.get_status(&order_hash), Some(EscrowStatus::Active));
+}
+
+#[test]
+fn test_clawback_shortfall_hits_last_settled_escrow() {
+    let s = setup();
+    let token = setup_regulated_token(&s);
+    let first = s.escrow.create_escrow(&CreateEscrowArgs {
+        amount: 1_000,
+        ..regulated_escrow_args(&s, &token.address)
+    });
+    let second = s.escrow.create_escrow(&CreateEscrowArgs {
+        amount: 2_000,
+        ..regulated_escrow_args(&s, &token.address)
+    });
+
+    // Escrows share the contract balance, so a clawback against one order
+    // only surfaces when the pool can no longer cover a settlement.
+    token.clawback(&s.escrow.address, &1_000);
+    assert_eq!(token.balance(&s.escrow.address), 2_200);
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow.withdraw(&second, &preimage, &Vec::new(&s.env), &0);
+    assert_eq!(token.balance(&s.taker), 2_000);
+
+    assert!(s
+        .escrow
+        .try_withdraw(&first, &preimage, &Vec::new(&s.env), &0)
+        .is_err());
+    assert_eq!(s.escrow.get_status(&first), Some(EscrowStatus::Active));
+}
+
+#[test]
+fn test_sha256_hashlock() {
+    let s = setup();
+    let (preimage, _) = secret(&s.env);
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        hashlock: s.env.crypto().sha256(&preimage.clone().into()).to_bytes(),
+        hash_algorithm: HashAlgorithm::Sha256,
+        ..escrow_args(&s, true, 1_000)
+    });
+    assert_eq!(
+        s.escrow
+            .get_escrow_info(&order_hash)
+            .unwrap()
+            .hashlock
+            .algorithm,
+        HashAlgorithm::Sha256
+    );
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+    assert_eq!(s.token.balance(&s.taker), 1_000);
+}
+
+#[test]
+fn test_hashlock_checked_with_recorded_algorithm() {
+    let s = setup();
+    let (preimage, keccak_hashlock) = secret(&s.env);
+
+    // A Keccak256 hashlock recorded as SHA-256 never opens
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        hash_algorithm: HashAlgorithm::Sha256,
+        ..escrow_args(&s, true, 1_000)
+    });
+    advance(&s.env, RESCUE_DELAY as u64);
+    assert_eq!(
+        s.escrow
+            .try_withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0),
+        Err(Ok(EscrowError::InvalidPreimage.into()))
+    );
+
+    // The algorithm is part of the order hash, so the same hashlock can be
+    // used by a Keccak256 escrow alongside
+    let keccak_order_hash = s.escrow.create_escrow(&escrow_args(&s, true, 1_000));
+    assert_ne!(order_hash, keccak_order_hash);
+    assert_eq!(
+        s.escrow
+            .get_escrow_info(&keccak_order_hash)
+            .unwrap()
+            .hashlock
+            .value,
+        keccak_hashlock
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn prop_secret_hash_matches_evm(secret in any::<[u8; 32]>()) {
+        let env = unsnapshotted_env();
+        let preimage = BytesN::from_array(&env, &secret);
+        let hashlock = settlement::hash_secret(&env, HashAlgorithm::Keccak256, &preimage);
+        prop_assert_eq!(hashlock.to_array(), evm_hashlock(&secret));
+    }
+
+    #[test]
+    fn prop_evm_hashlock_opens_stellar_escrow(
+        secret in any::<[u8; 32]>(),
+        other in any::<[u8; 32]>(),
+    ) {
+        prop_assume!(secret != other);
+        let s = setup_in(unsnapshotted_env());
+        let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+            hashlock: BytesN::from_array(&s.env, &evm_hashlock(&secret)),
+            ..escrow_args(&s, true, 1_000)
+        });
+        advance(&s.env, RESCUE_DELAY as u64);
+
+        // Only the secret the EVM side accepts opens the escrow
+        prop_assert_eq!(
+            s.escrow.try_withdraw(
+                &order_hash,
+                &BytesN::from_array(&s.env, &other),
+                &Vec::new(&s.env),
+                &0
+            ),
+            Err(Ok(EscrowError::InvalidPreimage.into()))
+        );
+        s.escrow.withdraw(
+            &order_hash,
+            &BytesN::from_array(&s.env, &secret),
+            &Vec::new(&s.env),
+            &0,
+        );
+        prop_assert_eq!(s.token.balance(&s.taker), 1_000);
+    }
+}
+
+fn later_timelocks(env: &Env, timelocks: &Vec<u64>, delay: u64) -> Vec<u64> {
+    let mut extended = vec![env, timelocks.get(0).unwrap()];
+    for timelock in timelocks.iter().skip(1) {
+        extended.push_back(timelock + delay);
+    }
+    extended
+}
+
+fn escrow_ttl(s: &Setup, order_hash: &BytesN<32>) -> u32 {
+    s.env.as_contract(&s.escrow.address, || {
+        s.env
+            .storage()
+            .persistent()
+            .get_ttl(&DataKey::Escrow(order_hash.clone()))
+    })
+}
+
+#[test]
+fn test_extend_timelocks() {
+    let s = setup();
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        src_cancellation: Some(s.env.ledger().timestamp() + 8 * RESCUE_DELAY as u64),
+        ..escrow_args(&s, false, 1_000)
+    });
+    let escrow = s.escrow.get_escrow_info(&order_hash).unwrap();
+    let ttl = escrow_ttl(&s, &order_hash);
+
+    let new_timelocks = later_timelocks(&s.env, &escrow.timelocks, 2 * RESCUE_DELAY as u64);
+    s.escrow.extend_timelocks(&order_hash, &new_timelocks);
+    let signers: std::vec::Vec<Address> = s.env.auths().into_iter().map(|(a, _)| a).collect();
+    assert_eq!(signers, std::vec![s.maker.clone(), s.taker.clone()]);
+    let (_, _, data) = s.env.events().all().last().unwrap();
+    let event: TimelocksExtendedEvent = data.into_val(&s.env);
+    assert_eq!(
+        event,
+        TimelocksExtendedEvent {
+            order_hash: order_hash.clone(),
+            old_timelocks: escrow.timelocks.clone(),
+            new_timelocks: new_timelocks.clone(),
+        }
+    );
+    assert_eq!(
+        s.escrow.get_escrow_info(&order_hash).unwrap().timelocks,
+        new_timelocks
+    );
+    assert!(escrow_ttl(&s, &order_hash) > ttl);
+
+    // The taker can no longer cancel at the original deadline
+    advance(&s.env, 3 * RESCUE_DELAY as u64);
+    assert_eq!(s.escrow.get_current_stage(&order_hash), Stage::Withdrawal);
+    assert_eq!(
+        s.escrow.try_cancel(&order_hash),
+        Err(Ok(EscrowError::StageNotReached.into()))
+    );
+    advance(&s.env, 2 * RESCUE_DELAY as u64);
+    s.escrow.cancel(&order_hash);
+}
+
+#[test]
+fn test_extend_timelocks_rejects_invalid_timelocks() {
+    let s = setup();
+    let order_hash = create_escrow(&s, true);
+    let timelocks = s.escrow.get_escrow_info(&order_hash).unwrap().timelocks;
+    let created = timelocks.get(0).unwrap();
+
+    let mut earlier = later_timelocks(&s.env, &timelocks, 0);
+    earlier.set(
+        STAGE_SRC_CANCELLATION,
+        timelocks.get(STAGE_SRC_CANCELLATION).unwrap() - 1,
+    );
+
+    let mut out_of_order = later_timelocks(&s.env, &timelocks, 0);
+    out_of_order.set(
+        STAGE_SRC_WITHDRAWAL,
+        timelocks.get(STAGE_SRC_PUBLIC_WITHDRAWAL).unwrap() + 1,
+    );
+
+    let mut finality_moved = later_timelocks(&s.env, &timelocks, 60);
+    finality_moved.set(STAGE_FINALITY, created + 60);
+
+    let mut too_long = later_timelocks(&s.env, &timelocks, 0);
+    too_long.set(
+        STAGE_SRC_PUBLIC_CANCELLATION,
+        created + upgrade::MAX_ESCROW_LIFETIME + 1,
+    );
+
+    let mut too_short = timelocks.clone();
+    too_short.pop_back();
+
+    for invalid in [earlier, out_of_order, finality_moved, too_long, too_short] {
+        assert_eq!(
+            s.escrow.try_extend_timelocks(&order_hash, &invalid),
+            Err(Ok(EscrowError::InvalidTimelocks.into()))
+        );
+    }
+    assert_eq!(
+        s.escrow.get_escrow_info(&order_hash).unwrap().timelocks,
+        timelocks
+    );
+
+    // Public cancellation may land exactly on the lifetime bound
+    let mut longest = later_timelocks(&s.env, &timelocks, 0);
+    longest.set(
+        STAGE_SRC_PUBLIC_CANCELLATION,
+        created + upgrade::MAX_ESCROW_LIFETIME,
+    );
+    s.escrow.extend_timelocks(&order_hash, &longest);
+}
+
+#[test]
+fn test_extend_timelocks_after_finalization() {
+    let s = setup();
+    let order_hash = create_escrow(&s, true);
+    let timelocks = s.escrow.get_escrow_info(&order_hash).unwrap().timelocks;
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+
+    assert_eq!(
+        s.escrow.try_extend_timelocks(
+            &order_hash,
+            &later_timelocks(&s.env, &timelocks, RESCUE_DELAY as u64)
+        ),
+        Err(Ok(EscrowError::AlreadyFinalized.into()))
+    );
+}
+
+fn whitelisted_args(s: &Setup, taker: &Address, resolvers: AllowedResolvers) -> CreateEscrowArgs {
+    CreateEscrowArgs {
+        taker: taker.clone(),
+        resolvers,
+        ..escrow_args(s, true, 1_000)
+    }
+}
+
+#[test]
+fn test_whitelisted_resolver_becomes_taker() {
+    let s = setup();
+    let resolver1 = Address::generate(&s.env);
+    let resolver2 = Address::generate(&s.env);
+    let args = whitelisted_args(
+        &s,
+        &resolver2,
+        AllowedResolvers::List(vec![&s.env, resolver1.clone(), resolver2.clone()]),
+    );
+    let order_hash = s.escrow.create_escrow(&args);
+
+    // The maker signed the order without naming the resolver
+    let auths = s.env.auths();
+    let (_, maker_invocation) = auths.iter().find(|(a, _)| *a == s.maker).unwrap();
+    let AuthorizedFunction::Contract((_, _, maker_args)) = &maker_invocation.function else {
+        panic!("expected a contract invocation");
+    };
+    assert_eq!(*maker_args, resolvers::maker_auth_args(&s.env, &args));
+    assert!(auths.iter().any(|(a, _)| *a == resolver2));
+
+    let escrow = s.escrow.get_escrow_info(&order_hash).unwrap();
+    assert_eq!(escrow.taker, resolver2);
+    advance(&s.env, RESCUE_DELAY as u64);
+    let (preimage, _) = secret(&s.env);
+    s.escrow
+        .withdraw(&order_hash, &preimage, &Vec::new(&s.env), &0);
+    assert_eq!(s.token.balance(&resolver2), 1_000);
+}
+
+#[test]
+fn test_resolver_outside_whitelist_rejected() {
+    let s = setup();
+    let resolver1 = Address::generate(&s.env);
+    let outsider = Address::generate(&s.env);
+    let args = whitelisted_args(
+        &s,
+        &outsider,
+        AllowedResolvers::List(vec![&s.env, resolver1]),
+    );
+
+    assert_eq!(
+        s.escrow.try_create_escrow(&args),
+        Err(Ok(EscrowError::Unauthorized.into()))
+    );
+    assert_eq!(s.token.balance(&s.maker), 1_000_000);
+}
+
+#[test]
+fn test_merkle_whitelisted_resolver() {
+    let s = setup();
+    let resolver1 = Address::generate(&s.env);
+    let resolver2 = Address::generate(&s.env);
+    let outsider = Address::generate(&s.env);
+
+    // Two-leaf sorted-pair tree, each leaf is the other's proof
+    let leaf1 = resolvers::resolver_leaf(&s.env, &resolver1);
+    let leaf2 = resolvers::resolver_leaf(&s.env, &resolver2);
+    let (low, high) = if leaf1 < leaf2 {
+        (leaf1.clone(), leaf2.clone())
+    } else {
+        (leaf2.clone(), leaf1.clone())
+    };
+    let mut pair = Bytes::from(low);
+    pair.append(&high.into());
+    let root = s.env.crypto().keccak256(&pair).to_bytes();
+
+    let outsider_args = CreateEscrowArgs {
+        resolver_proof: vec![&s.env, leaf2.clone()],
+        ..whitelisted_args(&s, &outsider, AllowedResolvers::MerkleRoot(root.clone()))
+    };
+    assert_eq!(
+        s.escrow.try_create_escrow(&outsider_args),
+        Err(Ok(EscrowError::Unauthorized.into()))
+    );
+
+    let args = CreateEscrowArgs {
+        resolver_proof: vec![&s.env, leaf2],
+        ..whitelisted_args(&s, &resolver1, AllowedResolvers::MerkleRoot(root))
+    };
+    let order_hash = s.escrow.create_escrow(&args);
+    assert_eq!(
+        s.escrow.get_escrow_info(&order_hash).unwrap().taker,
+        resolver1
+    );
+}
+
+#[test]
+fn test_whitelist_rejected_for_destination_escrows() {
+    let s = setup();
+    let args = CreateEscrowArgs {
+        is_src: false,
+        ..whitelisted_args(
+            &s,
+            &s.taker,
+            AllowedResolvers::List(vec![&s.env, s.taker.clone()]),
+        )
+    };
+    StellarAssetClient::new(&s.env, &s.token.address).mint(&s.taker, &10_000);
+
+    assert!(s.escrow.try_create_escrow(&args).is_err());
+    assert!(s.escrow.try_create_dst_escrow(&args).is_err());
+}
+
+#[test]
+fn test_dst_escrow_requires_cancellation_margin() {
+    let s = setup();
+    let now = s.env.ledger().timestamp();
+    let dst_cancellation = now + 3 * RESCUE_DELAY as u64;
+    let margin = 15 * 60 + 60 + 5 * 60;
+
+    let missing = CreateEscrowArgs {
+        src_cancellation: None,
+        ..escrow_args(&s, false, 1_000)
+    };
+    assert!(s.escrow.try_create_escrow(&missing).is_err());
+
+    let unsafe_args = CreateEscrowArgs {
+        src_cancellation: Some(dst_cancellation + margin - 1),
+        ..escrow_args(&s, false, 1_000)
+    };
+    assert_eq!(
+        s.escrow.try_create_escrow(&unsafe_args),
+        Err(Ok(EscrowError::UnsafeTimelocks.into()))
+    );
+
+    let order_hash = s.escrow.create_escrow(&CreateEscrowArgs {
+        src_cancellation: Some(dst_cancellation + margin),
+        ..escrow_args(&s, false, 1_000)
+    });
+    assert_eq!(
+        s.escrow
+            .get_escrow_info(&order_hash)
+            .unwrap()
+            .src_cancellation,
+        Some(dst_cancellation + margin)
+    );
+}
+
+#[test]
+fn test_src_cancellation_is_committed() {
+    let s = setup();
+    let src_cancellation = s.env.ledger().timestamp() + 4 * RESCUE_DELAY as u64;
+    let first = create_escrow(&s, false);
+    let (_, _, data) = s.env.events().all().last().unwrap();
+    let event: EscrowCreatedEvent = data.into_val(&s.env);
+    assert_eq!(event.src_cancellation, Some(src_cancellation));
+
+    // The same order against a later source cancellation is another escrow
+    let second = s.escrow.create_escrow(&CreateEscrowArgs {
+        src_cancellation: Some(src_cancellation + 1),
+        ..escrow_args(&s, false, 1_000)
+    });
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_extension_keeps_cancellation_margin() {
+    let s = setup();
+    let order_hash = create_escrow(&s, false);
+    let escrow = s.escrow.get_escrow_info(&order_hash).unwrap();
+    let src_cancellation = escrow.src_cancellation.unwrap();
+
+    // Pushing the destination cancellation up to the source one is unsafe
+    let mut late = later_timelocks(&s.env, &escrow.timelocks, 0);
+    late.set(STAGE_SRC_CANCELLATION, src_cancellation);
+    late.set(STAGE_SRC_PUBLIC_CANCELLATION, src_cancellation);
+    assert_eq!(
+        s.escrow.try_extend_timelocks(&order_hash, &late),
+        Err(Ok(EscrowError::UnsafeTimelocks.into()))
+    );
+
+    // Extending only the withdrawal windows is fine
+    let mut longer_withdrawal = later_timelocks(&s.env, &escrow.timelocks, 0);
+    longer_withdrawal.set(
+        STAGE_SRC_PUBLIC_WITHDRAWAL,
+        escrow.timelocks.get(STAGE_SRC_CANCELLATION).unwrap(),
+    );
+    s.escrow.extend_timelocks(&order_hash, &longer_withdrawal);
+}
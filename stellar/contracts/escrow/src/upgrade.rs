@@ -0,0 +1,160 @@
+use soroban_sdk::{
+    contracttype, symbol_short, Address, BytesN, Env, Map, Symbol, TryFromVal, Val, Vec,
+};
+
+use crate::{DataKey, Escrow, EscrowStatus, HashAlgorithm, HashLock};
+
+pub(crate) const DAY_IN_SECONDS: u64 = 24 * 60 * 60;
+
+/// Longest interval accepted between two consecutive escrow stages.
+pub(crate) const MAX_RESCUE_DELAY: u32 = 7 * DAY_IN_SECONDS as u32;
+
+/// Time from creation until an escrow reaches public cancellation.
+pub(crate) const MAX_ESCROW_LIFETIME: u64 = 4 * MAX_RESCUE_DELAY as u64;
+
+/// An upgrade can only go live after every escrow created before it was
+/// announced has become publicly cancellable.
+pub(crate) const UPGRADE_DELAY: u64 = MAX_ESCROW_LIFETIME + DAY_IN_SECONDS;
+
+/// Layout version of the data written by this build of the contract.
+///
+/// 1. `claimed` and `cancelled` flags, a hashlock without algorithm
+/// 2. `status` replaces the flags
+/// 3. `receiver`
+/// 4. `bundle` legs
+/// 5. `funder`
+/// 6. hash algorithm in the hashlock
+/// 7. `src_cancellation`
+/// 8. cumulative fill levels with a `used_parts` flag per secret
+pub(crate) const STORAGE_VERSION: u32 = 8;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingUpgrade {
+    pub wasm_hash: BytesN<32>,
+    pub eta: u64,
+}
+
+pub fn read_admin(env: &Env) -> Address {
+    env.storage().instance().get(&DataKey::Admin).unwrap()
+}
+
+pub fn write_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&DataKey::Admin, admin);
+}
+
+pub fn read_pending_upgrade(env: &Env) -> Option<PendingUpgrade> {
+    env.storage().instance().get(&DataKey::PendingUpgrade)
+}
+
+pub fn write_pending_upgrade(env: &Env, upgrade: &PendingUpgrade) {
+    env.storage()
+        .instance()
+        .set(&DataKey::PendingUpgrade, upgrade);
+}
+
+pub fn remove_pending_upgrade(env: &Env) {
+    env.storage().instance().remove(&DataKey::PendingUpgrade);
+}
+
+/// Deployments that predate the version key are treated as version 1.
+pub fn read_storage_version(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::StorageVersion)
+        .unwrap_or(1)
+}
+
+pub fn write_storage_version(env: &Env, version: u32) {
+    env.storage()
+        .instance()
+        .set(&DataKey::StorageVersion, &version);
+}
+
+/// Bring storage written by version `from` up to `STORAGE_VERSION`.
+///
+/// Escrow entries cannot all be rewritten in one invocation, so they are
+/// converted when next read, see `read_escrow`. Only version 1 entries can be
+/// converted.
+pub fn migrate(env: &Env, from: u32) {
+    if from >= STORAGE_VERSION {
+        panic!("Nothing to migrate");
+    }
+    if from != 1 {
+        panic!("Unsupported storage version");
+    }
+    write_storage_version(env, STORAGE_VERSION);
+}
+
+/// `HashLock` of storage version 1, always Keccak256.
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HashLockV1 {
+    pub value: BytesN<32>,
+}
+
+/// `Escrow` of storage version 1.
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowV1 {
+    pub maker: Address,
+    pub taker: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub safety_deposit: i128,
+    pub hashlock: HashLockV1,
+    pub timelocks: Vec<u64>,
+    pub claimed: bool,
+    pub cancelled: bool,
+    pub is_src: bool,
+    pub merkle_root: BytesN<32>,
+    pub parts: u32,
+    pub used_parts: Vec<bool>,
+}
+
+impl EscrowV1 {
+    /// Version 1 escrows were funded by the maker and released in full by
+    /// the secret of their hashlock, also when split into parts, so they
+    /// carry on as single part escrows.
+    fn upgrade(self, env: &Env) -> Escrow {
+        let status = if self.claimed {
+            EscrowStatus::Withdrawn
+        } else if self.cancelled {
+            EscrowStatus::Cancelled
+        } else {
+            EscrowStatus::Active
+        };
+        Escrow {
+            maker: self.maker.clone(),
+            taker: self.taker,
+            receiver: self.maker.clone(),
+            funder: self.maker,
+            token: self.token,
+            amount: self.amount,
+            bundle: Vec::new(env),
+            safety_deposit: self.safety_deposit,
+            hashlock: HashLock {
+                value: self.hashlock.value,
+                algorithm: HashAlgorithm::Keccak256,
+            },
+            timelocks: self.timelocks,
+            status,
+            is_src: self.is_src,
+            merkle_root: self.merkle_root,
+            parts: 1,
+            used_parts: Vec::from_array(env, [false]),
+            src_cancellation: None,
+        }
+    }
+}
+
+/// Decode a stored escrow entry, converting it from an older layout.
+pub fn read_escrow(env: &Env, stored: Val) -> Escrow {
+    // Decoding into the wrong layout traps, so look at the fields first
+    let fields = Map::<Symbol, Val>::try_from_val(env, &stored).unwrap();
+    if fields.contains_key(symbol_short!("claimed")) {
+        EscrowV1::try_from_val(env, &stored).unwrap().upgrade(env)
+    } else {
+        Escrow::try_from_val(env, &stored).unwrap()
+    }
+}
@@ -1,15 +1,18 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, contractclient, Address, Bytes, BytesN, Env, Vec,
+    contract, contractimpl, contracttype, contractclient, Address, BytesN, Env, Vec,
     contracterror, panic_with_error, symbol_short
 };
 
+mod test;
+
 /// Error codes
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(u32)]
 pub enum RelayerError {
     Unauthorized = 1,
+    InvalidPartIndex = 2,
 }
 
 /// Storage keys
@@ -26,22 +29,37 @@ pub struct SecretSharedEvent {
     pub part_idx: u32,
 }
 
+/// `EscrowStatus` of the escrow contract, kept in sync by
+/// `test_escrow_types_match_escrow_contract`
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscrowStatus {
+    Active,
+    PartiallyFilled,
+    Withdrawn,
+    Cancelled,
+    PublicCancelled,
+}
+
+/// `Stage` of the escrow contract, kept in sync by
+/// `test_escrow_types_match_escrow_contract`
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stage {
+    BeforeFinality,
+    Finality,
+    Withdrawal,
+    PublicWithdrawal,
+    Cancellation,
+    PublicCancellation,
+}
+
 /// External escrow contract client
 #[contractclient(name = "EscrowContractClient")]
 pub trait EscrowContractTrait {
-    fn escrow_exists(local_escrow_addr: Address) -> bool;
-    fn get_escrow_info(local_escrow_addr: Address) -> (Address, Address, i128, BytesN<32>, Vec<u64>, bool, bool, bool, BytesN<32>);
-    fn compute_order_hash_from_info(
-        maker: Address,
-        taker: Address,
-        token_type: Address,
-        amount: i128,
-        hashlock: BytesN<32>,
-        timelocks: Vec<u64>,
-        is_src: bool,
-    ) -> BytesN<32>;
-    fn get_escrow_token_type(local_escrow_addr: Address) -> Address;
-    fn get_escrow_safety_deposit(local_escrow_addr: Address) -> i128;
+    fn get_status(order_hash: BytesN<32>) -> Option<EscrowStatus>;
+    fn get_current_stage(order_hash: BytesN<32>) -> Stage;
+    fn get_secret_count(order_hash: BytesN<32>) -> u32;
 }
 
 #[contract]
@@ -98,11 +116,15 @@ impl SecretRelayerContract {
     }
 
     /// Emit secret shared event - only callable by authorized relayers
+    ///
+    /// `local_escrow_addr` is not used: escrows are looked up by
+    /// `order_hash` in `escrow_contract`. It is kept so existing callers of
+    /// the contract keep working.
     pub fn emit_secret_shared(
         env: Env,
         relayer: Address,
         admin: Address,
-        local_escrow_addr: Address,
+        _local_escrow_addr: Address,
         order_hash: BytesN<32>,
         part_idx: u32,
         escrow_contract: Address,
@@ -128,46 +150,23 @@ impl SecretRelayerContract {
 
         let escrow_client = EscrowContractClient::new(&env, &escrow_contract);
 
-        // 1. Verify local escrow exists
-        if !escrow_client.escrow_exists(&local_escrow_addr) {
-            panic_with_error!(&env, RelayerError::Unauthorized);
+        // 1. Verify the escrow exists and is not already finalized
+        match escrow_client.get_status(&order_hash) {
+            Some(EscrowStatus::Active) | Some(EscrowStatus::PartiallyFilled) => {}
+            _ => panic_with_error!(&env, RelayerError::Unauthorized),
         }
 
-        // 2. Get local escrow info
-        let (maker, taker, amount, hashlock, timelocks, claimed, cancelled, is_src, _) = 
-            escrow_client.get_escrow_info(&local_escrow_addr);
-
-        // 3. Verify escrow is not already finalized
-        if claimed || cancelled {
-            panic_with_error!(&env, RelayerError::Unauthorized);
-        }
-
-        // 4. Verify local escrow has passed STAGE_FINALITY timelock
-        let current_time = env.ledger().timestamp();
-        let finality_deadline = timelocks.get(0).unwrap(); // STAGE_FINALITY = 0
-        if current_time < finality_deadline {
-            panic_with_error!(&env, RelayerError::Unauthorized);
-        }
-
-        // 5. Verify order hash matches local escrow (prevents replay attacks)
-        let token_type = escrow_client.get_escrow_token_type(&local_escrow_addr);
-        let recomputed_hash = escrow_client.compute_order_hash_from_info(
-            &maker,
-            &taker,
-            &token_type,
-            &amount,
-            &hashlock,
-            &timelocks,
-            &is_src
-        );
-        if recomputed_hash != order_hash {
-            panic_with_error!(&env, RelayerError::Unauthorized);
+        // 2. Verify the escrow has passed its finality lock
+        match escrow_client.get_current_stage(&order_hash) {
+            Stage::BeforeFinality | Stage::Finality => {
+                panic_with_error!(&env, RelayerError::Unauthorized)
+            }
+            _ => {}
         }
 
-        // 6. Verify safety deposit exists (incentive mechanism)
-        let safety_deposit = escrow_client.get_escrow_safety_deposit(&local_escrow_addr);
-        if safety_deposit <= 0 {
-            panic_with_error!(&env, RelayerError::Unauthorized);
+        // 3. Verify the escrow has a secret for `part_idx`
+        if part_idx >= escrow_client.get_secret_count(&order_hash) {
+            panic_with_error!(&env, RelayerError::InvalidPartIndex);
         }
 
         // Emit the secret shared event
@@ -1,21 +1,208 @@
 #![cfg(test)]
+extern crate std;
 
 use super::*;
-use soroban_sdk::{vec, Env, String};
+use escrow::{AllowedResolvers, CreateEscrowArgs, EscrowContract, HashAlgorithm};
+use soroban_sdk::{
+    testutils::{Address as _, Events, Ledger},
+    token::StellarAssetClient,
+    vec, IntoVal, TryFromVal, Val,
+};
 
-#[test]
-fn test() {
+const RESCUE_DELAY: u32 = 3600;
+
+struct Setup<'a> {
+    env: Env,
+    admin: Address,
+    relayer: Address,
+    escrow: escrow::EscrowContractClient<'a>,
+    contract: SecretRelayerContractClient<'a>,
+    order_hash: BytesN<32>,
+}
+
+fn setup<'a>() -> Setup<'a> {
     let env = Env::default();
-    let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(&env, &contract_id);
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000_000);
+
+    let maker = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    StellarAssetClient::new(&env, &token).mint(&maker, &1_000_000);
+    let escrow = escrow::EscrowContractClient::new(
+        &env,
+        &env.register(EscrowContract, (Address::generate(&env),)),
+    );
+    let order_hash = escrow.create_escrow(&CreateEscrowArgs {
+        maker: maker.clone(),
+        taker: Address::generate(&env),
+        receiver: None,
+        token,
+        amount: 1_000,
+        bundle: Vec::new(&env),
+        safety_deposit: 100,
+        hashlock: env
+            .crypto()
+            .keccak256(&BytesN::from_array(&env, &[7u8; 32]).into())
+            .to_bytes(),
+        hash_algorithm: HashAlgorithm::Keccak256,
+        rescue_delay: RESCUE_DELAY,
+        is_src: true,
+        merkle_root: BytesN::from_array(&env, &[0u8; 32]),
+        parts: 1,
+        resolvers: AllowedResolvers::Taker,
+        resolver_proof: Vec::new(&env),
+        src_cancellation: None,
+    });
+
+    let admin = Address::generate(&env);
+    let relayer = Address::generate(&env);
+    let contract = SecretRelayerContractClient::new(&env, &env.register(SecretRelayerContract, ()));
+    contract.initialize(&admin);
+    contract.add_relayer(&admin, &relayer);
+
+    Setup {
+        env,
+        admin,
+        relayer,
+        escrow,
+        contract,
+        order_hash,
+    }
+}
 
-    let words = client.hello(&String::from_str(&env, "Dev"));
+fn advance(env: &Env, seconds: u64) {
+    let now = env.ledger().timestamp();
+    env.ledger().set_timestamp(now + seconds);
+}
+
+fn emit(s: &Setup, relayer: &Address, order_hash: &BytesN<32>) -> bool {
+    s.contract
+        .try_emit_secret_shared(
+            relayer,
+            &s.admin,
+            &s.escrow.address,
+            order_hash,
+            &0,
+            &s.escrow.address,
+        )
+        .is_ok()
+}
+
+#[test]
+fn test_emit_secret_shared_after_finality() {
+    let s = setup();
+    assert!(!emit(&s, &s.relayer, &s.order_hash));
+
+    advance(&s.env, RESCUE_DELAY as u64);
+    assert!(emit(&s, &s.relayer, &s.order_hash));
     assert_eq!(
-        words,
+        s.env.events().all(),
         vec![
-            &env,
-            String::from_str(&env, "Hello"),
-            String::from_str(&env, "Dev"),
+            &s.env,
+            (
+                s.contract.address.clone(),
+                (symbol_short!("secret"),).into_val(&s.env),
+                SecretSharedEvent {
+                    order_hash: s.order_hash.clone(),
+                    part_idx: 0,
+                }
+                .into_val(&s.env),
+            ),
         ]
     );
 }
+
+#[test]
+fn test_emit_secret_shared_requires_authorized_relayer() {
+    let s = setup();
+    advance(&s.env, RESCUE_DELAY as u64);
+    assert!(!emit(&s, &Address::generate(&s.env), &s.order_hash));
+
+    s.contract.remove_relayer(&s.admin, &s.relayer);
+    assert!(!emit(&s, &s.relayer, &s.order_hash));
+}
+
+#[test]
+fn test_emit_secret_shared_requires_open_escrow() {
+    let s = setup();
+    advance(&s.env, RESCUE_DELAY as u64);
+    assert!(!emit(
+        &s,
+        &s.relayer,
+        &BytesN::from_array(&s.env, &[9u8; 32])
+    ));
+
+    advance(&s.env, 2 * RESCUE_DELAY as u64);
+    s.escrow.cancel(&s.order_hash);
+    assert!(!emit(&s, &s.relayer, &s.order_hash));
+}
+
+#[test]
+fn test_emit_secret_shared_requires_part_of_escrow() {
+    let s = setup();
+    advance(&s.env, RESCUE_DELAY as u64);
+    assert_eq!(s.escrow.get_secret_count(&s.order_hash), 1);
+    assert_eq!(
+        s.contract.try_emit_secret_shared(
+            &s.relayer,
+            &s.admin,
+            &s.escrow.address,
+            &s.order_hash,
+            &1,
+            &s.escrow.address,
+        ),
+        Err(Ok(RelayerError::InvalidPartIndex.into()))
+    );
+    assert!(s.env.events().all().is_empty());
+}
+
+/// The copies of `EscrowStatus` and `Stage` in this crate have to decode
+/// every value the escrow contract returns. A variant added to the escrow
+/// contract fails to compile in the matches below.
+#[test]
+fn test_escrow_types_match_escrow_contract() {
+    let env = Env::default();
+
+    let status = |status: escrow::EscrowStatus| match status {
+        escrow::EscrowStatus::Active => EscrowStatus::Active,
+        escrow::EscrowStatus::PartiallyFilled => EscrowStatus::PartiallyFilled,
+        escrow::EscrowStatus::Withdrawn => EscrowStatus::Withdrawn,
+        escrow::EscrowStatus::Cancelled => EscrowStatus::Cancelled,
+        escrow::EscrowStatus::PublicCancelled => EscrowStatus::PublicCancelled,
+    };
+    for escrow_status in [
+        escrow::EscrowStatus::Active,
+        escrow::EscrowStatus::PartiallyFilled,
+        escrow::EscrowStatus::Withdrawn,
+        escrow::EscrowStatus::Cancelled,
+        escrow::EscrowStatus::PublicCancelled,
+    ] {
+        let val: Val = escrow_status.into_val(&env);
+        assert_eq!(
+            EscrowStatus::try_from_val(&env, &val),
+            Ok(status(escrow_status))
+        );
+    }
+
+    let stage = |stage: escrow::Stage| match stage {
+        escrow::Stage::BeforeFinality => Stage::BeforeFinality,
+        escrow::Stage::Finality => Stage::Finality,
+        escrow::Stage::Withdrawal => Stage::Withdrawal,
+        escrow::Stage::PublicWithdrawal => Stage::PublicWithdrawal,
+        escrow::Stage::Cancellation => Stage::Cancellation,
+        escrow::Stage::PublicCancellation => Stage::PublicCancellation,
+    };
+    for escrow_stage in [
+        escrow::Stage::BeforeFinality,
+        escrow::Stage::Finality,
+        escrow::Stage::Withdrawal,
+        escrow::Stage::PublicWithdrawal,
+        escrow::Stage::Cancellation,
+        escrow::Stage::PublicCancellation,
+    ] {
+        let val: Val = escrow_stage.into_val(&env);
+        assert_eq!(Stage::try_from_val(&env, &val), Ok(stage(escrow_stage)));
+    }
+}
@@ -0,0 +1,28 @@
+use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use soroban_sdk::{Address, Env};
+
+pub fn read_authorization(e: &Env, addr: Address) -> bool {
+    let key = DataKey::State(addr);
+    if let Some(authorized) = e.storage().persistent().get::<DataKey, bool>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        authorized
+    } else {
+        true
+    }
+}
+
+pub fn write_authorization(e: &Env, addr: Address, authorize: bool) {
+    let key = DataKey::State(addr);
+    e.storage().persistent().set(&key, &authorize);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+pub fn check_authorized(e: &Env, addr: Address) {
+    if !read_authorization(e, addr) {
+        panic!("account is deauthorized");
+    }
+}
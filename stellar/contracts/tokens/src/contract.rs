@@ -2,13 +2,31 @@
 //! interface.
 use crate::admin::{read_administrator, write_administrator};
 use crate::allowance::{read_allowance, spend_allowance, write_allowance};
+use crate::authorization::{check_authorized, read_authorization, write_authorization};
 use crate::balance::{read_balance, receive_balance, spend_balance};
-use crate::metadata::{read_decimal, read_name, read_symbol, write_metadata};
+use crate::faucet::{read_faucet_config, record_claim, write_faucet_config};
+use crate::metadata::{
+    read_decimal, read_extended_metadata, read_name, read_symbol, write_extended_metadata,
+    write_metadata, write_name, write_symbol,
+};
+use crate::minter::{
+    read_minter, read_remaining_quota, remove_minter, spend_quota, write_minter_quota,
+};
+use crate::permit::{
+    account_public_key, read_nonce, read_permit_enabled, spend_nonce, write_permit_enabled,
+    PermitMessage,
+};
 #[cfg(test)]
 use crate::storage_types::{AllowanceDataKey, AllowanceValue, DataKey};
-use crate::storage_types::{INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD};
+use crate::storage_types::{
+    ExtendedMetadata, FaucetConfig, MinterInfo, TokenInfo, INSTANCE_BUMP_AMOUNT,
+    INSTANCE_LIFETIME_THRESHOLD,
+};
+use crate::supply::{
+    decrease_supply, increase_supply, read_max_supply, read_total_supply, write_max_supply,
+};
 use soroban_sdk::token::{self, Interface as _};
-use soroban_sdk::{contract, contractimpl, Address, Env, String};
+use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, String, Symbol};
 use soroban_token_sdk::metadata::TokenMetadata;
 use soroban_token_sdk::TokenUtils;
 
@@ -23,10 +41,26 @@ pub struct Token;
 
 #[contractimpl]
 impl Token {
-    pub fn __constructor(e: Env, admin: Address, decimal: u32, name: String, symbol: String) {
+    pub fn __constructor(
+        e: Env,
+        admin: Address,
+        decimal: u32,
+        name: String,
+        symbol: String,
+        max_supply: Option<i128>,
+        faucet: Option<FaucetConfig>,
+    ) {
         if decimal > 18 {
             panic!("Decimal must not be greater than 18");
         }
+        if let Some(max_supply) = max_supply {
+            check_nonnegative_amount(max_supply);
+            write_max_supply(&e, max_supply);
+        }
+        if let Some(faucet) = faucet {
+            check_nonnegative_amount(faucet.amount);
+            write_faucet_config(&e, &faucet);
+        }
         write_administrator(&e, &admin);
         write_metadata(
             &e,
@@ -47,10 +81,99 @@ impl Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        check_authorized(&e, to.clone());
+        increase_supply(&e, amount);
         receive_balance(&e, to.clone(), amount);
         TokenUtils::new(&e).events().mint(admin, to, amount);
     }
 
+    /// Mint `amount` to `to` as a `minter` holding the minter role, within
+    /// its daily quota.
+    pub fn minter_mint(e: Env, minter: Address, to: Address, amount: i128) {
+        check_nonnegative_amount(amount);
+        minter.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        spend_quota(&e, minter.clone(), amount);
+        check_authorized(&e, to.clone());
+        increase_supply(&e, amount);
+        receive_balance(&e, to.clone(), amount);
+        TokenUtils::new(&e).events().mint(minter, to, amount);
+    }
+
+    pub fn set_minter(e: Env, minter: Address, daily_quota: i128) {
+        check_nonnegative_amount(daily_quota);
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        write_minter_quota(&e, minter.clone(), daily_quota);
+        e.events()
+            .publish((Symbol::new(&e, "set_minter"), admin, minter), daily_quota);
+    }
+
+    pub fn remove_minter(e: Env, minter: Address) {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        remove_minter(&e, minter.clone());
+        e.events()
+            .publish((Symbol::new(&e, "remove_minter"), admin, minter), ());
+    }
+
+    pub fn minter(e: Env, id: Address) -> Option<MinterInfo> {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_minter(&e, id)
+    }
+
+    /// Amount `id` can still mint in the current window, zero for non-minters.
+    pub fn remaining_quota(e: Env, id: Address) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_remaining_quota(&e, id)
+    }
+
+    /// Mint the configured faucet amount to `to`. Only available when the
+    /// token was deployed with a faucet, and at most once per cooldown for
+    /// each address.
+    pub fn faucet(e: Env, to: Address) {
+        let Some(faucet) = read_faucet_config(&e) else {
+            panic!("faucet is disabled");
+        };
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        record_claim(&e, to.clone(), faucet.cooldown_ledgers);
+        check_authorized(&e, to.clone());
+        increase_supply(&e, faucet.amount);
+        receive_balance(&e, to.clone(), faucet.amount);
+        TokenUtils::new(&e)
+            .events()
+            .mint(e.current_contract_address(), to, faucet.amount);
+    }
+
+    pub fn faucet_config(e: Env) -> Option<FaucetConfig> {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_faucet_config(&e)
+    }
+
     pub fn set_admin(e: Env, new_admin: Address) {
         let admin = read_administrator(&e);
         admin.require_auth();
@@ -63,11 +186,197 @@ impl Token {
         TokenUtils::new(&e).events().set_admin(admin, new_admin);
     }
 
-    #[cfg(test)]
+    pub fn set_authorized(e: Env, id: Address, authorize: bool) {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        write_authorization(&e, id.clone(), authorize);
+        TokenUtils::new(&e)
+            .events()
+            .set_authorized(admin, id, authorize);
+    }
+
+    pub fn clawback(e: Env, from: Address, amount: i128) {
+        check_nonnegative_amount(amount);
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
+        TokenUtils::new(&e).events().clawback(admin, from, amount);
+    }
+
+    /// Set an allowance from an ed25519 signature by `owner` over the
+    /// `PermitMessage` digest, so the owner does not have to submit `approve`
+    /// themselves. Each permit consumes the owner's next nonce.
+    ///
+    /// Only the account's master key can sign permits; its other signers,
+    /// weights and thresholds are not consulted. Permits are therefore off
+    /// until the owner turns them on with `set_permit_enabled`, which is
+    /// authorized like any other call.
+    pub fn permit(
+        e: Env,
+        owner: Address,
+        spender: Address,
+        amount: i128,
+        expiration_ledger: u32,
+        nonce: u64,
+        signature: BytesN<64>,
+    ) {
+        check_nonnegative_amount(amount);
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        let message = PermitMessage {
+            network_id: e.ledger().network_id(),
+            token: e.current_contract_address(),
+            owner: owner.clone(),
+            spender: spender.clone(),
+            amount,
+            expiration_ledger,
+            nonce,
+        };
+        let public_key = account_public_key(&e, &owner);
+        if !read_permit_enabled(&e, owner.clone()) {
+            panic!("permits are not enabled for owner");
+        }
+        e.crypto()
+            .ed25519_verify(&public_key, &message.digest(&e).into(), &signature);
+        spend_nonce(&e, owner.clone(), nonce);
+
+        write_allowance(
+            &e,
+            owner.clone(),
+            spender.clone(),
+            amount,
+            expiration_ledger,
+        );
+        TokenUtils::new(&e)
+            .events()
+            .approve(owner, spender, amount, expiration_ledger);
+    }
+
+    /// Allow or stop permits signed by the master key of `owner`.
+    pub fn set_permit_enabled(e: Env, owner: Address, enabled: bool) {
+        owner.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        write_permit_enabled(&e, owner.clone(), enabled);
+        e.events()
+            .publish((Symbol::new(&e, "set_permit_enabled"), owner), enabled);
+    }
+
+    pub fn permit_enabled(e: Env, owner: Address) -> bool {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_permit_enabled(&e, owner)
+    }
+
+    /// Next nonce `owner` has to sign a permit with.
+    pub fn nonce(e: Env, owner: Address) -> u64 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_nonce(&e, owner)
+    }
+
+    pub fn set_name(e: Env, name: String) {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        write_name(&e, name.clone());
+        e.events()
+            .publish((Symbol::new(&e, "set_name"), admin), name);
+    }
+
+    pub fn set_symbol(e: Env, symbol: String) {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        write_symbol(&e, symbol.clone());
+        e.events()
+            .publish((Symbol::new(&e, "set_symbol"), admin), symbol);
+    }
+
+    pub fn set_extended_metadata(e: Env, metadata: ExtendedMetadata) {
+        let admin = read_administrator(&e);
+        admin.require_auth();
+
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+        write_extended_metadata(&e, &metadata);
+        e.events()
+            .publish((Symbol::new(&e, "set_extended_metadata"), admin), metadata);
+    }
+
+    /// Standard and extended metadata in a single call.
+    pub fn metadata(e: Env) -> TokenInfo {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        let extended = read_extended_metadata(&e);
+        TokenInfo {
+            decimal: read_decimal(&e),
+            name: read_name(&e),
+            symbol: read_symbol(&e),
+            icon_url: extended.icon_url,
+            home_domain: extended.home_domain,
+            issuer: extended.issuer,
+        }
+    }
+
+    pub fn total_supply(e: Env) -> i128 {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_total_supply(&e)
+    }
+
+    pub fn max_supply(e: Env) -> Option<i128> {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_max_supply(&e)
+    }
+
+    pub fn authorized(e: Env, id: Address) -> bool {
+        e.storage()
+            .instance()
+            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        read_authorization(&e, id)
+    }
+}
+
+#[cfg(test)]
+#[contractimpl]
+impl Token {
     pub fn get_allowance(e: Env, from: Address, spender: Address) -> Option<AllowanceValue> {
         let key = DataKey::Allowance(AllowanceDataKey { from, spender });
-        let allowance = e.storage().temporary().get::<_, AllowanceValue>(&key);
-        allowance
+        e.storage().temporary().get::<_, AllowanceValue>(&key)
     }
 }
 
@@ -111,6 +420,8 @@ impl token::Interface for Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        check_authorized(&e, from.clone());
+        check_authorized(&e, to.clone());
         spend_balance(&e, from.clone(), amount);
         receive_balance(&e, to.clone(), amount);
         TokenUtils::new(&e).events().transfer(from, to, amount);
@@ -125,6 +436,8 @@ impl token::Interface for Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        check_authorized(&e, from.clone());
+        check_authorized(&e, to.clone());
         spend_allowance(&e, from.clone(), spender, amount);
         spend_balance(&e, from.clone(), amount);
         receive_balance(&e, to.clone(), amount);
@@ -140,7 +453,9 @@ impl token::Interface for Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        check_authorized(&e, from.clone());
         spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
         TokenUtils::new(&e).events().burn(from, amount);
     }
 
@@ -153,8 +468,10 @@ impl token::Interface for Token {
             .instance()
             .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+        check_authorized(&e, from.clone());
         spend_allowance(&e, from.clone(), spender, amount);
         spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
         TokenUtils::new(&e).events().burn(from, amount)
     }
 
@@ -169,4 +486,4 @@ impl token::Interface for Token {
     fn symbol(e: Env) -> String {
         read_symbol(&e)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,31 @@
+use crate::storage_types::{DataKey, FaucetConfig};
+use soroban_sdk::{Address, Env};
+
+pub fn read_faucet_config(e: &Env) -> Option<FaucetConfig> {
+    let key = DataKey::Faucet;
+    e.storage().instance().get(&key)
+}
+
+pub fn write_faucet_config(e: &Env, config: &FaucetConfig) {
+    let key = DataKey::Faucet;
+    e.storage().instance().set(&key, config);
+}
+
+/// Record a faucet claim for `addr`, panicking if its last claim is still
+/// within the cooldown. Claims only need to live as long as the cooldown, so
+/// they are kept in temporary storage.
+pub fn record_claim(e: &Env, addr: Address, cooldown_ledgers: u32) {
+    let key = DataKey::FaucetClaim(addr);
+    let sequence = e.ledger().sequence();
+    if let Some(last_claim) = e.storage().temporary().get::<DataKey, u32>(&key) {
+        if sequence < last_claim.saturating_add(cooldown_ledgers) {
+            panic!("faucet cooldown active");
+        }
+    }
+    e.storage().temporary().set(&key, &sequence);
+    if cooldown_ledgers > 0 {
+        e.storage()
+            .temporary()
+            .extend_ttl(&key, cooldown_ledgers, cooldown_ledgers);
+    }
+}
@@ -2,10 +2,17 @@
 
 mod admin;
 mod allowance;
+mod authorization;
 mod balance;
 mod contract;
+mod faucet;
 mod metadata;
+mod minter;
+mod permit;
 mod storage_types;
+mod supply;
 mod test;
 
-pub use crate::contract::TokenClient;
\ No newline at end of file
+pub use crate::contract::{Token, TokenClient};
+pub use crate::permit::{PermitMessage, PERMIT_DOMAIN};
+pub use crate::storage_types::{ExtendedMetadata, FaucetConfig, MinterInfo, TokenInfo};
@@ -1,3 +1,4 @@
+use crate::storage_types::{DataKey, ExtendedMetadata};
 use soroban_sdk::{Env, String};
 use soroban_token_sdk::{metadata::TokenMetadata, TokenUtils};
 
@@ -19,4 +20,35 @@ pub fn read_symbol(e: &Env) -> String {
 pub fn write_metadata(e: &Env, metadata: TokenMetadata) {
     let util = TokenUtils::new(e);
     util.metadata().set_metadata(&metadata);
-}
\ No newline at end of file
+}
+
+pub fn write_name(e: &Env, name: String) {
+    let util = TokenUtils::new(e);
+    let metadata = util.metadata().get_metadata();
+    util.metadata()
+        .set_metadata(&TokenMetadata { name, ..metadata });
+}
+
+pub fn write_symbol(e: &Env, symbol: String) {
+    let util = TokenUtils::new(e);
+    let metadata = util.metadata().get_metadata();
+    util.metadata()
+        .set_metadata(&TokenMetadata { symbol, ..metadata });
+}
+
+pub fn read_extended_metadata(e: &Env) -> ExtendedMetadata {
+    let key = DataKey::ExtendedMetadata;
+    e.storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(ExtendedMetadata {
+            icon_url: None,
+            home_domain: None,
+            issuer: None,
+        })
+}
+
+pub fn write_extended_metadata(e: &Env, metadata: &ExtendedMetadata) {
+    let key = DataKey::ExtendedMetadata;
+    e.storage().instance().set(&key, metadata);
+}
@@ -0,0 +1,82 @@
+use crate::storage_types::{
+    DataKey, MinterInfo, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD, DAY_IN_LEDGERS,
+};
+use soroban_sdk::{Address, Env};
+
+pub fn read_minter(e: &Env, addr: Address) -> Option<MinterInfo> {
+    let key = DataKey::Minter(addr);
+    let minter = e.storage().persistent().get::<DataKey, MinterInfo>(&key);
+    if minter.is_some() {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    }
+    minter
+}
+
+fn write_minter(e: &Env, addr: Address, minter: &MinterInfo) {
+    let key = DataKey::Minter(addr);
+    e.storage().persistent().set(&key, minter);
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+pub fn remove_minter(e: &Env, addr: Address) {
+    let key = DataKey::Minter(addr);
+    e.storage().persistent().remove(&key);
+}
+
+/// Grant the minter role or change the quota of an existing minter. Usage in
+/// the current window is kept, so lowering a quota takes effect immediately.
+pub fn write_minter_quota(e: &Env, addr: Address, daily_quota: i128) {
+    let minter = match read_minter(e, addr.clone()) {
+        Some(minter) => MinterInfo {
+            daily_quota,
+            ..minter
+        },
+        None => MinterInfo {
+            daily_quota,
+            window_start: e.ledger().sequence(),
+            minted: 0,
+        },
+    };
+    write_minter(e, addr, &minter);
+}
+
+/// Start a fresh window once `DAY_IN_LEDGERS` ledgers have passed since the
+/// current one opened.
+fn current_window(e: &Env, minter: MinterInfo) -> MinterInfo {
+    let sequence = e.ledger().sequence();
+    if sequence >= minter.window_start.saturating_add(DAY_IN_LEDGERS) {
+        MinterInfo {
+            window_start: sequence,
+            minted: 0,
+            ..minter
+        }
+    } else {
+        minter
+    }
+}
+
+pub fn read_remaining_quota(e: &Env, addr: Address) -> i128 {
+    match read_minter(e, addr) {
+        Some(minter) => {
+            let minter = current_window(e, minter);
+            (minter.daily_quota - minter.minted).max(0)
+        }
+        None => 0,
+    }
+}
+
+pub fn spend_quota(e: &Env, addr: Address, amount: i128) {
+    let Some(minter) = read_minter(e, addr.clone()) else {
+        panic!("not a minter");
+    };
+    let mut minter = current_window(e, minter);
+    if minter.daily_quota - minter.minted < amount {
+        panic!("minter quota exceeded");
+    }
+    minter.minted += amount;
+    write_minter(e, addr, &minter);
+}
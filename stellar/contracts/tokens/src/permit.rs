@@ -0,0 +1,92 @@
+use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use soroban_sdk::{contracttype, xdr::ToXdr, Address, Bytes, BytesN, Env};
+
+/// Prefix of every signed permit so the signature cannot be replayed as any
+/// other kind of message.
+pub const PERMIT_DOMAIN: &[u8] = b"setu-swap:token-permit:v1";
+
+/// Fields covered by a permit signature. The network and token contract are
+/// included so a permit is only valid for the token it was signed for.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PermitMessage {
+    pub network_id: BytesN<32>,
+    pub token: Address,
+    pub owner: Address,
+    pub spender: Address,
+    pub amount: i128,
+    pub expiration_ledger: u32,
+    pub nonce: u64,
+}
+
+impl PermitMessage {
+    /// The 32 bytes the owner signs: `sha256(PERMIT_DOMAIN || xdr(self))`.
+    pub fn digest(&self, e: &Env) -> BytesN<32> {
+        let mut message = Bytes::from_slice(e, PERMIT_DOMAIN);
+        message.append(&self.clone().to_xdr(e));
+        e.crypto().sha256(&message).into()
+    }
+}
+
+pub fn read_nonce(e: &Env, owner: Address) -> u64 {
+    let key = DataKey::Nonce(owner);
+    if let Some(nonce) = e.storage().persistent().get::<DataKey, u64>(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        nonce
+    } else {
+        0
+    }
+}
+
+/// Consume `nonce` for `owner`, which must be the next unused one.
+pub fn spend_nonce(e: &Env, owner: Address, nonce: u64) {
+    if read_nonce(e, owner.clone()) != nonce {
+        panic!("invalid nonce");
+    }
+    let key = DataKey::Nonce(owner);
+    e.storage().persistent().set(&key, &(nonce + 1));
+    e.storage()
+        .persistent()
+        .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+pub fn read_permit_enabled(e: &Env, owner: Address) -> bool {
+    let key = DataKey::PermitEnabled(owner);
+    if e.storage().persistent().has(&key) {
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+        true
+    } else {
+        false
+    }
+}
+
+pub fn write_permit_enabled(e: &Env, owner: Address, enabled: bool) {
+    let key = DataKey::PermitEnabled(owner);
+    if enabled {
+        e.storage().persistent().set(&key, &());
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    } else {
+        e.storage().persistent().remove(&key);
+    }
+}
+
+/// ed25519 key behind an account address.
+///
+/// An account address encodes to XDR as the `ScVal` and `ScAddress` tags, the
+/// `PublicKey` type and then the 32 key bytes. Contract addresses have no key
+/// and cannot sign permits.
+pub fn account_public_key(e: &Env, addr: &Address) -> BytesN<32> {
+    let xdr = addr.clone().to_xdr(e);
+    // SCV_ADDRESS, SC_ADDRESS_TYPE_ACCOUNT, PUBLIC_KEY_TYPE_ED25519
+    let header = Bytes::from_array(e, &[0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0]);
+    if xdr.len() != 44 || xdr.slice(0..12) != header {
+        panic!("permit owner must be an account");
+    }
+    xdr.slice(12..).try_into().unwrap()
+}
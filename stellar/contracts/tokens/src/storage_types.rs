@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address};
+use soroban_sdk::{contracttype, Address, String};
 
 pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
 pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
@@ -20,6 +20,48 @@ pub struct AllowanceValue {
     pub expiration_ledger: u32,
 }
 
+/// Optional display information beyond the standard name, symbol and decimals
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ExtendedMetadata {
+    pub icon_url: Option<String>,
+    pub home_domain: Option<String>,
+    /// Name or contact of the issuing organisation
+    pub issuer: Option<String>,
+}
+
+/// Everything returned by the `metadata` view
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TokenInfo {
+    pub decimal: u32,
+    pub name: String,
+    pub symbol: String,
+    pub icon_url: Option<String>,
+    pub home_domain: Option<String>,
+    pub issuer: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FaucetConfig {
+    /// Amount paid out by each `faucet` call
+    pub amount: i128,
+    /// Ledgers an address has to wait between two claims
+    pub cooldown_ledgers: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct MinterInfo {
+    /// Largest amount the minter may mint within one window
+    pub daily_quota: i128,
+    /// Ledger at which the current window opened
+    pub window_start: u32,
+    /// Amount minted since `window_start`
+    pub minted: i128,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
@@ -27,4 +69,12 @@ pub enum DataKey {
     Balance(Address),
     State(Address),
     Admin,
-}
\ No newline at end of file
+    TotalSupply,
+    MaxSupply,
+    Minter(Address),
+    Faucet,
+    FaucetClaim(Address),
+    Nonce(Address),
+    PermitEnabled(Address),
+    ExtendedMetadata,
+}
@@ -1,14 +1,34 @@
 #![cfg(test)]
 extern crate std;
 
-use crate::{contract::Token, TokenClient};
+use crate::{
+    contract::Token,
+    storage_types::{ExtendedMetadata, FaucetConfig, TokenInfo, DAY_IN_LEDGERS},
+    PermitMessage, TokenClient,
+};
+use ed25519_dalek::{Signer, SigningKey};
 use soroban_sdk::{
     symbol_short,
-    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
-    Address, Env, FromVal, IntoVal, String, Symbol,
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger},
+    vec,
+    xdr::FromXdr,
+    Address, Bytes, BytesN, Env, FromVal, IntoVal, String, Symbol,
 };
 
 fn create_token<'a>(e: &Env, admin: &Address) -> TokenClient<'a> {
+    create_capped_token(e, admin, None)
+}
+
+fn create_capped_token<'a>(e: &Env, admin: &Address, max_supply: Option<i128>) -> TokenClient<'a> {
+    deploy_token(e, admin, max_supply, None)
+}
+
+fn deploy_token<'a>(
+    e: &Env,
+    admin: &Address,
+    max_supply: Option<i128>,
+    faucet: Option<FaucetConfig>,
+) -> TokenClient<'a> {
     let token_contract = e.register(
         Token,
         (
@@ -16,6 +36,8 @@ fn create_token<'a>(e: &Env, admin: &Address) -> TokenClient<'a> {
             7_u32,
             String::from_val(e, &"name"),
             String::from_val(e, &"symbol"),
+            max_supply,
+            faucet,
         ),
     );
     TokenClient::new(e, &token_contract)
@@ -252,6 +274,8 @@ fn decimal_is_over_eighteen() {
                 19_u32,
                 String::from_val(&e, &"name"),
                 String::from_val(&e, &"symbol"),
+                None::<i128>,
+                None::<FaucetConfig>,
             ),
         ),
     );
@@ -270,4 +294,584 @@ fn test_zero_allowance() {
 
     token.transfer_from(&spender, &from, &spender, &0);
     assert!(token.get_allowance(&from, &spender).is_none());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_set_authorized() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.mint(&user1, &1000);
+    assert!(token.authorized(&user1));
+
+    token.set_authorized(&user1, &false);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "set_authorized"),
+                    (&user1, false).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert!(!token.authorized(&user1));
+    assert!(token.try_transfer(&user1, &user2, &100).is_err());
+    assert!(token.try_transfer(&user2, &user1, &0).is_err());
+    assert!(token.try_burn(&user1, &100).is_err());
+    assert!(token.try_mint(&user1, &100).is_err());
+
+    token.set_authorized(&user1, &true);
+    token.transfer(&user1, &user2, &100);
+    assert_eq!(token.balance(&user1), 900);
+    assert_eq!(token.balance(&user2), 100);
+}
+
+#[test]
+#[should_panic(expected = "account is deauthorized")]
+fn transfer_from_deauthorized() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let user3 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.mint(&user1, &1000);
+    token.approve(&user1, &user3, &500, &200);
+    token.set_authorized(&user1, &false);
+
+    token.transfer_from(&user3, &user1, &user2, &100);
+}
+
+#[test]
+fn test_clawback() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.mint(&user1, &1000);
+    token.set_authorized(&user1, &false);
+
+    // Clawback works on deauthorized accounts
+    token.clawback(&user1, &400);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    symbol_short!("clawback"),
+                    (&user1, 400_i128).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(token.balance(&user1), 600);
+}
+
+#[test]
+#[should_panic(expected = "insufficient balance")]
+fn clawback_insufficient_balance() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.mint(&user1, &1000);
+    token.clawback(&user1, &1001);
+}
+
+#[test]
+fn test_total_supply() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    assert_eq!(token.total_supply(), 0);
+    assert_eq!(token.max_supply(), None);
+
+    token.mint(&user1, &1000);
+    token.mint(&user2, &500);
+    assert_eq!(token.total_supply(), 1500);
+
+    token.burn(&user1, &100);
+    token.approve(&user2, &user1, &200, &200);
+    token.burn_from(&user1, &user2, &200);
+    token.clawback(&user1, &100);
+    assert_eq!(token.total_supply(), 1100);
+
+    // Transfers leave the supply untouched
+    token.transfer(&user1, &user2, &300);
+    assert_eq!(token.total_supply(), 1100);
+}
+
+#[test]
+fn test_max_supply() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_capped_token(&e, &admin, Some(1000));
+    assert_eq!(token.max_supply(), Some(1000));
+
+    token.mint(&user1, &1000);
+    assert!(token.try_mint(&user1, &1).is_err());
+
+    // Burning frees room under the cap
+    token.burn(&user1, &10);
+    token.mint(&user1, &10);
+    assert_eq!(token.total_supply(), 1000);
+}
+
+#[test]
+#[should_panic(expected = "max supply exceeded")]
+fn mint_over_max_supply() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_capped_token(&e, &admin, Some(1000));
+
+    token.mint(&user1, &1001);
+}
+
+#[test]
+fn test_minter_quota() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    assert_eq!(token.minter(&minter), None);
+    assert!(token.try_minter_mint(&minter, &user1, &1).is_err());
+
+    token.set_minter(&minter, &1000);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "set_minter"),
+                    (&minter, 1000_i128).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(token.remaining_quota(&minter), 1000);
+
+    token.minter_mint(&minter, &user1, &600);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            minter.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "minter_mint"),
+                    (&minter, &user1, 600_i128).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(token.balance(&user1), 600);
+    assert_eq!(token.remaining_quota(&minter), 400);
+    assert!(token.try_minter_mint(&minter, &user1, &401).is_err());
+
+    // The admin is not bound by any quota
+    token.mint(&user1, &5000);
+    assert_eq!(token.remaining_quota(&minter), 400);
+
+    // The quota refills once the window has passed
+    e.ledger()
+        .with_mut(|li| li.sequence_number += DAY_IN_LEDGERS);
+    assert_eq!(token.remaining_quota(&minter), 1000);
+    token.minter_mint(&minter, &user1, &1000);
+    assert_eq!(token.balance(&user1), 6600);
+}
+
+#[test]
+fn test_remove_minter() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.set_minter(&minter, &1000);
+    token.minter_mint(&minter, &user1, &300);
+
+    // Changing the quota keeps what was already minted in the window
+    token.set_minter(&minter, &500);
+    assert_eq!(token.remaining_quota(&minter), 200);
+
+    token.remove_minter(&minter);
+    assert_eq!(
+        e.events().all(),
+        vec![
+            &e,
+            (
+                token.address.clone(),
+                (
+                    Symbol::new(&e, "remove_minter"),
+                    admin.clone(),
+                    minter.clone()
+                )
+                    .into_val(&e),
+                ().into_val(&e),
+            )
+        ]
+    );
+    assert_eq!(token.minter(&minter), None);
+    assert_eq!(token.remaining_quota(&minter), 0);
+    assert!(token.try_minter_mint(&minter, &user1, &1).is_err());
+}
+
+#[test]
+#[should_panic(expected = "minter quota exceeded")]
+fn mint_over_minter_quota() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let minter = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    token.set_minter(&minter, &1000);
+    token.minter_mint(&minter, &user1, &1001);
+}
+
+#[test]
+fn test_faucet() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let faucet = FaucetConfig {
+        amount: 100,
+        cooldown_ledgers: DAY_IN_LEDGERS,
+    };
+    let token = deploy_token(&e, &admin, None, Some(faucet.clone()));
+    assert_eq!(token.faucet_config(), Some(faucet));
+
+    // No signature is needed to claim
+    token.faucet(&user1);
+    assert_eq!(e.auths(), std::vec![]);
+    assert_eq!(token.balance(&user1), 100);
+    assert_eq!(token.total_supply(), 100);
+
+    // Each address has its own cooldown
+    assert!(token.try_faucet(&user1).is_err());
+    token.faucet(&user2);
+    assert_eq!(token.balance(&user2), 100);
+
+    e.ledger()
+        .with_mut(|li| li.sequence_number += DAY_IN_LEDGERS - 1);
+    assert!(token.try_faucet(&user1).is_err());
+    e.ledger().with_mut(|li| li.sequence_number += 1);
+    token.faucet(&user1);
+    assert_eq!(token.balance(&user1), 200);
+}
+
+#[test]
+fn test_faucet_respects_max_supply() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let user2 = Address::generate(&e);
+    let faucet = FaucetConfig {
+        amount: 100,
+        cooldown_ledgers: DAY_IN_LEDGERS,
+    };
+    let token = deploy_token(&e, &admin, Some(150), Some(faucet));
+
+    token.faucet(&user1);
+    assert!(token.try_faucet(&user2).is_err());
+    assert_eq!(token.balance(&user2), 0);
+}
+
+#[test]
+#[should_panic(expected = "faucet is disabled")]
+fn faucet_disabled() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let user1 = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    assert_eq!(token.faucet_config(), None);
+
+    token.faucet(&user1);
+}
+
+/// Account address controlled by `key`, built from its `ScVal` XDR encoding.
+fn account_address(e: &Env, key: &SigningKey) -> Address {
+    let mut xdr = Bytes::from_array(e, &[0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0]);
+    xdr.extend_from_array(&key.verifying_key().to_bytes());
+    Address::from_xdr(e, &xdr).unwrap()
+}
+
+fn sign_permit(
+    e: &Env,
+    key: &SigningKey,
+    token: &TokenClient,
+    spender: &Address,
+    amount: i128,
+    expiration_ledger: u32,
+    nonce: u64,
+) -> BytesN<64> {
+    let message = PermitMessage {
+        network_id: e.ledger().network_id(),
+        token: token.address.clone(),
+        owner: account_address(e, key),
+        spender: spender.clone(),
+        amount,
+        expiration_ledger,
+        nonce,
+    };
+    let signature = key.sign(&message.digest(e).to_array());
+    BytesN::from_array(e, &signature.to_bytes())
+}
+
+#[test]
+fn test_permit() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let key = SigningKey::from_bytes(&[1; 32]);
+    let owner = account_address(&e, &key);
+    let spender = Address::generate(&e);
+    let receiver = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    token.mock_all_auths().mint(&owner, &1000);
+    token.mock_all_auths().set_permit_enabled(&owner, &true);
+    assert_eq!(token.nonce(&owner), 0);
+
+    // The permit itself needs no authorization from the owner
+    let signature = sign_permit(&e, &key, &token, &spender, 500, 200, 0);
+    token.permit(&owner, &spender, &500, &200, &0, &signature);
+    assert_eq!(e.auths(), std::vec![]);
+    assert_eq!(token.allowance(&owner, &spender), 500);
+    assert_eq!(token.nonce(&owner), 1);
+
+    // A used permit cannot be replayed
+    assert!(token
+        .try_permit(&owner, &spender, &500, &200, &0, &signature)
+        .is_err());
+
+    token
+        .mock_all_auths()
+        .transfer_from(&spender, &owner, &receiver, &300);
+    assert_eq!(token.balance(&receiver), 300);
+    assert_eq!(token.allowance(&owner, &spender), 200);
+}
+
+#[test]
+fn test_permit_rejects_bad_signatures() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let key = SigningKey::from_bytes(&[1; 32]);
+    let other_key = SigningKey::from_bytes(&[2; 32]);
+    let owner = account_address(&e, &key);
+    let spender = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    let other_token = create_token(&e, &admin);
+    token.mock_all_auths().set_permit_enabled(&owner, &true);
+
+    // Signed by another key
+    let signature = sign_permit(&e, &other_key, &token, &spender, 500, 200, 0);
+    assert!(token
+        .try_permit(&owner, &spender, &500, &200, &0, &signature)
+        .is_err());
+
+    // Signed for a different amount
+    let signature = sign_permit(&e, &key, &token, &spender, 100, 200, 0);
+    assert!(token
+        .try_permit(&owner, &spender, &500, &200, &0, &signature)
+        .is_err());
+
+    // Signed for a different token
+    let signature = sign_permit(&e, &key, &other_token, &spender, 500, 200, 0);
+    assert!(token
+        .try_permit(&owner, &spender, &500, &200, &0, &signature)
+        .is_err());
+
+    // Signed with a future nonce
+    let signature = sign_permit(&e, &key, &token, &spender, 500, 200, 1);
+    assert!(token
+        .try_permit(&owner, &spender, &500, &200, &1, &signature)
+        .is_err());
+
+    assert_eq!(token.allowance(&owner, &spender), 0);
+    assert_eq!(token.nonce(&owner), 0);
+}
+
+#[test]
+fn test_permit_requires_opt_in() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let key = SigningKey::from_bytes(&[1; 32]);
+    let owner = account_address(&e, &key);
+    let spender = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    assert!(!token.permit_enabled(&owner));
+
+    // The master key alone cannot approve until the account opts in
+    let signature = sign_permit(&e, &key, &token, &spender, 500, 200, 0);
+    assert!(token
+        .try_permit(&owner, &spender, &500, &200, &0, &signature)
+        .is_err());
+
+    token.mock_all_auths().set_permit_enabled(&owner, &true);
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            owner.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "set_permit_enabled"),
+                    (&owner, true).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    token.permit(&owner, &spender, &500, &200, &0, &signature);
+    assert_eq!(token.allowance(&owner, &spender), 500);
+
+    // Turning permits off again rejects later permits
+    token.mock_all_auths().set_permit_enabled(&owner, &false);
+    assert!(!token.permit_enabled(&owner));
+    let signature = sign_permit(&e, &key, &token, &spender, 100, 200, 1);
+    assert!(token
+        .try_permit(&owner, &spender, &100, &200, &1, &signature)
+        .is_err());
+    assert_eq!(token.allowance(&owner, &spender), 500);
+}
+
+#[test]
+#[should_panic(expected = "permit owner must be an account")]
+fn permit_from_contract_address() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let key = SigningKey::from_bytes(&[1; 32]);
+    let spender = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    let signature = sign_permit(&e, &key, &token, &spender, 500, 200, 0);
+    token.permit(&token.address, &spender, &500, &200, &0, &signature);
+}
+
+#[test]
+fn test_metadata() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let admin = Address::generate(&e);
+    let token = create_token(&e, &admin);
+    assert_eq!(
+        token.metadata(),
+        TokenInfo {
+            decimal: 7,
+            name: String::from_str(&e, "name"),
+            symbol: String::from_str(&e, "symbol"),
+            icon_url: None,
+            home_domain: None,
+            issuer: None,
+        }
+    );
+
+    token.set_name(&String::from_str(&e, "USD Coin"));
+    assert_eq!(
+        e.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    token.address.clone(),
+                    Symbol::new(&e, "set_name"),
+                    (String::from_str(&e, "USD Coin"),).into_val(&e),
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    token.set_symbol(&String::from_str(&e, "USDC"));
+    assert_eq!(token.name(), String::from_str(&e, "USD Coin"));
+    assert_eq!(token.symbol(), String::from_str(&e, "USDC"));
+    assert_eq!(token.decimals(), 7);
+
+    let extended = ExtendedMetadata {
+        icon_url: Some(String::from_str(&e, "https://example.com/usdc.png")),
+        home_domain: Some(String::from_str(&e, "example.com")),
+        issuer: None,
+    };
+    token.set_extended_metadata(&extended);
+    assert_eq!(
+        token.metadata(),
+        TokenInfo {
+            decimal: 7,
+            name: String::from_str(&e, "USD Coin"),
+            symbol: String::from_str(&e, "USDC"),
+            icon_url: extended.icon_url,
+            home_domain: extended.home_domain,
+            issuer: None,
+        }
+    );
+}
+
+#[test]
+fn metadata_updates_require_admin() {
+    let e = Env::default();
+
+    let admin = Address::generate(&e);
+    let token = create_token(&e, &admin);
+
+    assert!(token.try_set_name(&String::from_str(&e, "x")).is_err());
+    assert!(token.try_set_symbol(&String::from_str(&e, "x")).is_err());
+    assert!(token
+        .try_set_extended_metadata(&ExtendedMetadata {
+            icon_url: None,
+            home_domain: None,
+            issuer: Some(String::from_str(&e, "x")),
+        })
+        .is_err());
+    assert_eq!(token.name(), String::from_str(&e, "name"));
+}
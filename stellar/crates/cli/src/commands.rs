@@ -0,0 +1,212 @@
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use escrow::{AllowedResolvers, CreateEscrowArgs, HashAlgorithm};
+use setu_client::{EscrowCalls, Invocation};
+use soroban_sdk::xdr::{ScAddress, SorobanAuthorizedInvocation};
+use soroban_sdk::{Address, BytesN, Env, TryFromVal};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Open a source escrow as the maker, or a destination escrow as the
+    /// taker with --dst
+    Create(Create),
+    /// Withdraw with the secret as the taker
+    Withdraw(Withdraw),
+    /// Cancel as the taker
+    Cancel(OrderHash),
+    /// Withdraw with the secret on behalf of the taker, keeping the safety
+    /// deposit
+    PublicWithdraw(Withdraw),
+    /// Cancel on behalf of the taker, keeping the safety deposit
+    PublicCancel(OrderHash),
+    /// Show an escrow
+    Status(OrderHash),
+    /// Show the stages of an escrow
+    Schedule(OrderHash),
+}
+
+#[derive(Debug, Args)]
+pub struct Create {
+    /// Maker, defaults to the signer
+    #[arg(long)]
+    maker: Option<String>,
+    /// Taker, defaults to the signer
+    #[arg(long)]
+    taker: Option<String>,
+    /// Receiver of the funds released from a destination escrow, defaults to
+    /// the maker
+    #[arg(long)]
+    receiver: Option<String>,
+    /// Token locked in the escrow
+    #[arg(long)]
+    token: String,
+    /// Amount locked, in the token's smallest unit
+    #[arg(long)]
+    amount: i128,
+    /// Extra amount of the same token paid to whoever completes the escrow
+    #[arg(long)]
+    safety_deposit: i128,
+    /// Hash of the secret in hex
+    #[arg(long, value_parser = parse_hash)]
+    hashlock: [u8; 32],
+    #[arg(long, value_enum, default_value_t = Algorithm::Keccak256)]
+    hash_algorithm: Algorithm,
+    /// Seconds between consecutive stages
+    #[arg(long)]
+    rescue_delay: u32,
+    /// Open a destination escrow
+    #[arg(long)]
+    dst: bool,
+    /// Cancellation time of the paired source escrow, for destination escrows
+    #[arg(long, required_if_eq("dst", "true"))]
+    src_cancellation: Option<u64>,
+    /// Merkle root over the secrets of a multi-fill order, in hex
+    #[arg(long, value_parser = parse_hash)]
+    merkle_root: Option<[u8; 32]>,
+    /// Number of parts of a multi-fill order
+    #[arg(long, default_value_t = 1)]
+    parts: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct Withdraw {
+    #[arg(long, value_parser = parse_hash)]
+    order_hash: [u8; 32],
+    /// Secret in hex
+    #[arg(long, value_parser = parse_hash)]
+    secret: [u8; 32],
+    /// Merkle proof of the secret for multi-fill orders, comma separated hex
+    #[arg(long, value_parser = parse_hash, value_delimiter = ',')]
+    proof: Vec<[u8; 32]>,
+    /// Index of the secret for multi-fill orders
+    #[arg(long, default_value_t = 0)]
+    fill_index: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct OrderHash {
+    #[arg(long, value_parser = parse_hash)]
+    order_hash: [u8; 32],
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Algorithm {
+    Keccak256,
+    Sha256,
+}
+
+impl From<Algorithm> for HashAlgorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Keccak256 => HashAlgorithm::Keccak256,
+            Algorithm::Sha256 => HashAlgorithm::Sha256,
+        }
+    }
+}
+
+fn parse_hash(s: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+    bytes
+        .try_into()
+        .map_err(|_| "expected 32 bytes".to_string())
+}
+
+pub fn parse_address(env: &Env, s: &str) -> Result<Address> {
+    let Ok(address) = s.parse::<ScAddress>() else {
+        bail!("invalid address {s}");
+    };
+    Address::try_from_val(env, &address).map_err(|e| anyhow::anyhow!("{e:?}"))
+}
+
+impl Command {
+    /// Call made by this command and, for calls that change state, the
+    /// authorization tree `signer` has to sign.
+    pub fn invocation(
+        &self,
+        env: &Env,
+        calls: &EscrowCalls,
+        signer: &Address,
+    ) -> Result<(Invocation, Option<SorobanAuthorizedInvocation>)> {
+        let hash = |bytes: &[u8; 32]| BytesN::from_array(env, bytes);
+        let proof = |proof: &[[u8; 32]]| {
+            let mut hashes = soroban_sdk::Vec::new(env);
+            for node in proof {
+                hashes.push_back(hash(node));
+            }
+            hashes
+        };
+
+        let invocation = match self {
+            Command::Create(create) => {
+                let args = create.escrow_args(env, signer)?;
+                if create.dst {
+                    let auth = calls.create_dst_escrow_auth(&args)?;
+                    return Ok((calls.create_dst_escrow(&args)?, Some(auth)));
+                }
+                let auth = calls.create_escrow_auth(&args)?;
+                return Ok((calls.create_escrow(&args)?, Some(auth)));
+            }
+            Command::Withdraw(w) => calls.withdraw(
+                &hash(&w.order_hash),
+                &hash(&w.secret),
+                &proof(&w.proof),
+                w.fill_index,
+            )?,
+            Command::Cancel(o) => calls.cancel(&hash(&o.order_hash))?,
+            Command::PublicWithdraw(w) => calls.public_withdraw(
+                &hash(&w.order_hash),
+                &hash(&w.secret),
+                &proof(&w.proof),
+                w.fill_index,
+                signer,
+            )?,
+            Command::PublicCancel(o) => calls.public_cancel(&hash(&o.order_hash), signer)?,
+            Command::Status(o) => return Ok((calls.get_escrow_info(&hash(&o.order_hash))?, None)),
+            Command::Schedule(o) => return Ok((calls.get_schedule(&hash(&o.order_hash))?, None)),
+        };
+        let auth = invocation.authorized(vec![])?;
+        Ok((invocation, Some(auth)))
+    }
+}
+
+impl Create {
+    fn escrow_args(&self, env: &Env, signer: &Address) -> Result<CreateEscrowArgs> {
+        let address = |s: &Option<String>| match s {
+            Some(s) => parse_address(env, s),
+            None => Ok(signer.clone()),
+        };
+        let maker = address(&self.maker)?;
+        let taker = address(&self.taker)?;
+        // The signer funds the escrow, so it has to be the side that opens it
+        let funder = if self.dst { &taker } else { &maker };
+        if funder != signer {
+            bail!(
+                "the signer must be the {} of the escrow it opens",
+                if self.dst { "taker" } else { "maker" }
+            );
+        }
+
+        Ok(CreateEscrowArgs {
+            maker,
+            taker,
+            receiver: self
+                .receiver
+                .as_deref()
+                .map(|s| parse_address(env, s))
+                .transpose()?,
+            token: parse_address(env, &self.token)?,
+            amount: self.amount,
+            bundle: soroban_sdk::Vec::new(env),
+            safety_deposit: self.safety_deposit,
+            hashlock: BytesN::from_array(env, &self.hashlock),
+            hash_algorithm: self.hash_algorithm.into(),
+            rescue_delay: self.rescue_delay,
+            is_src: !self.dst,
+            merkle_root: BytesN::from_array(env, &self.merkle_root.unwrap_or_default()),
+            parts: self.parts,
+            resolvers: AllowedResolvers::Taker,
+            resolver_proof: soroban_sdk::Vec::new(env),
+            src_cancellation: self.src_cancellation,
+        })
+    }
+}
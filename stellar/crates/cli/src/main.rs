@@ -0,0 +1,150 @@
+//! `setu` builds `EscrowContract` transactions, signs them with a local key
+//! and prints the base64 XDR envelope for submission from another machine.
+//!
+//! With `--simulate` the call first runs against a ledger snapshot, such as
+//! one written by `stellar snapshot create`, and the envelope carries the
+//! footprint and resource fee the simulation recorded. Without it the
+//! envelope still needs to go through a network simulation before it is
+//! submitted, and the signer is trusted to be the account the call needs
+//! authorization from. `status` and `schedule` only run simulated, printing
+//! the escrow.
+//!
+//! Simulation needs the `sandbox` feature, which builds the testutils host
+//! into the binary.
+
+mod commands;
+mod sandbox;
+mod test;
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Parser};
+use ed25519_dalek::SigningKey;
+use escrow::{Escrow, Schedule};
+use setu_client::{network_id, sign_transaction, source_account_auth_entry, EscrowCalls};
+use soroban_sdk::xdr::{Limits, ScAddress, WriteXdr};
+use soroban_sdk::{Address, Env, TryFromVal};
+
+use crate::commands::{parse_address, Command};
+
+pub(crate) const TESTNET_PASSPHRASE: &str = "Test SDF Network ; September 2015";
+
+#[derive(Debug, Parser)]
+#[command(name = "setu", about = "Build and sign EscrowContract transactions")]
+pub struct Cli {
+    #[command(flatten)]
+    options: Options,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Args)]
+struct Options {
+    /// Address of the EscrowContract
+    #[arg(long, global = true, env = "SETU_ESCROW_CONTRACT")]
+    contract: Option<String>,
+    /// Secret key (S...) of the account that signs and submits the transaction
+    #[arg(long, global = true, env = "SETU_SECRET_KEY", hide_env_values = true)]
+    secret_key: Option<String>,
+    /// Passphrase of the network the transaction is signed for
+    #[arg(long, global = true, env = "SETU_NETWORK_PASSPHRASE", default_value = TESTNET_PASSPHRASE)]
+    network_passphrase: String,
+    /// Sequence number of the transaction, one more than the account's
+    /// current sequence number
+    #[arg(long, global = true)]
+    sequence: Option<i64>,
+    /// Inclusion fee in stroops; the simulated resource fee is added to it
+    #[arg(long, global = true, default_value_t = 100)]
+    fee: u32,
+    /// Run the call against a ledger snapshot first
+    #[arg(long, global = true)]
+    simulate: bool,
+    /// Ledger snapshot used by --simulate
+    #[arg(long, global = true, default_value = "snapshot.json")]
+    snapshot: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let env = if cli.options.simulate {
+        sandbox::load(&cli.options.snapshot)?
+    } else {
+        Env::default()
+    };
+    println!("{}", run(&cli, &env)?);
+    Ok(())
+}
+
+/// Execute `cli` with `env` as the sandbox for `--simulate` and return what
+/// is printed on stdout. Simulated events go to stderr.
+pub fn run(cli: &Cli, env: &Env) -> Result<String> {
+    let options = &cli.options;
+    let contract = options
+        .contract
+        .as_deref()
+        .context("--contract is required")?;
+    let contract = parse_address(env, contract)?;
+    let secret_key = options
+        .secret_key
+        .as_deref()
+        .context("--secret-key is required")?;
+    let secret_key = stellar_strkey::ed25519::PrivateKey::from_string(secret_key)
+        .map_err(|_| anyhow!("invalid secret key"))?;
+    let key = SigningKey::from_bytes(&secret_key.0);
+    let public_key = key.verifying_key().to_bytes();
+    let signer = Address::from_str(
+        env,
+        &stellar_strkey::ed25519::PublicKey(public_key).to_string(),
+    );
+
+    if matches!(cli.command, Command::Status(_) | Command::Schedule(_)) && !options.simulate {
+        bail!("status and schedule need --simulate");
+    }
+
+    let calls = EscrowCalls::new(env, &contract);
+    let (invocation, auth) = cli.command.invocation(env, &calls, &signer)?;
+
+    let mut soroban_data = None;
+    if options.simulate {
+        let simulation = sandbox::simulate(env, &contract, &invocation)?;
+        for event in &simulation.events {
+            eprintln!("{event}");
+        }
+        // The signer authorizes as the source account, so it has to be the
+        // only address the call needs authorization from
+        if let Some(other) = simulation.authorizers.iter().find(|&a| a != &signer) {
+            bail!(
+                "{} has to authorize this call, not the signer",
+                ScAddress::from(other)
+            );
+        }
+        match &cli.command {
+            Command::Status(_) => {
+                let escrow = Option::<Escrow>::try_from_val(env, &simulation.result)
+                    .map_err(|e| anyhow!("{e:?}"))?;
+                return Ok(match escrow {
+                    Some(escrow) => format!("{escrow:#?}"),
+                    None => "escrow not found".to_string(),
+                });
+            }
+            Command::Schedule(_) => {
+                let schedule = Schedule::try_from_val(env, &simulation.result)
+                    .map_err(|e| anyhow!("{e:?}"))?;
+                return Ok(format!("{schedule:#?}"));
+            }
+            _ => soroban_data = Some(simulation.transaction_data),
+        }
+    }
+
+    let sequence = options.sequence.context("--sequence is required")?;
+    let tx = invocation.transaction(
+        &public_key,
+        sequence,
+        options.fee,
+        auth.into_iter().map(source_account_auth_entry).collect(),
+        soroban_data,
+    )?;
+    let envelope = sign_transaction(tx, &network_id(&options.network_passphrase), &key)?;
+    Ok(envelope.to_xdr_base64(Limits::none())?)
+}
@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use setu_client::Invocation;
+use soroban_sdk::xdr::SorobanTransactionData;
+use soroban_sdk::{Address, Env, Val};
+
+#[cfg(any(test, feature = "sandbox"))]
+use {
+    setu_client::{decode_escrow_event, decode_token_event},
+    soroban_env_host::storage::{AccessType, Footprint},
+    soroban_sdk::testutils::Events as _,
+    soroban_sdk::xdr::{ExtensionPoint, LedgerFootprint, ScVal, SorobanResources},
+    soroban_sdk::{Symbol, TryFromVal},
+};
+
+#[cfg(not(any(test, feature = "sandbox")))]
+const NO_SANDBOX: &str = "--simulate needs setu to be built with the `sandbox` feature";
+
+/// Outcome of running an invocation against the sandbox ledger.
+pub struct Simulation {
+    pub result: Val,
+    /// Addresses whose authorization the call required
+    pub authorizers: Vec<Address>,
+    /// Decoded escrow and token events, one line each
+    pub events: Vec<String>,
+    pub transaction_data: SorobanTransactionData,
+}
+
+/// Load the ledger snapshot at `path` as the sandbox ledger.
+#[cfg(any(test, feature = "sandbox"))]
+pub fn load(path: &Path) -> Result<Env> {
+    Ok(Env::from_ledger_snapshot_file(path))
+}
+
+#[cfg(not(any(test, feature = "sandbox")))]
+pub fn load(_path: &Path) -> Result<Env> {
+    Err(anyhow!(NO_SANDBOX))
+}
+
+/// Run `invocation` on `contract` in `env`, recording the ledger entries it
+/// touches and the resources it uses.
+///
+/// Authorization is recorded rather than checked, as a network simulation
+/// does; the signatures are only verified once the transaction is submitted.
+#[cfg(any(test, feature = "sandbox"))]
+pub fn simulate(env: &Env, contract: &Address, invocation: &Invocation) -> Result<Simulation> {
+    env.mock_all_auths();
+    env.host()
+        .with_mut_storage(|storage| {
+            storage.footprint = Footprint::default();
+            Ok(())
+        })
+        .map_err(|e| anyhow!("{e:?}"))?;
+
+    let function = Symbol::new(env, &invocation.function.to_utf8_string()?);
+    let result = env
+        .try_invoke_contract::<Val, soroban_sdk::Error>(
+            contract,
+            &function,
+            invocation.args_val(env)?,
+        )
+        .map_err(|e| anyhow!("simulation failed: {e:?}"))?
+        .map_err(|e| anyhow!("unexpected return value: {e:?}"))?;
+    let authorizers = env
+        .auths()
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect();
+
+    let mut events = Vec::new();
+    for (_, topics, data) in env.events().all().iter() {
+        let topics = topics
+            .iter()
+            .map(|topic| ScVal::try_from_val(env, &topic))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("{e:?}"))?;
+        let data = ScVal::try_from_val(env, &data).map_err(|e| anyhow!("{e:?}"))?;
+        if let Some(event) = decode_escrow_event(env, &topics, &data)? {
+            events.push(format!("{event:?}"));
+        } else if let Some(event) = decode_token_event(env, &topics, &data)? {
+            events.push(format!("{event:?}"));
+        }
+    }
+
+    let budget = env.host().budget_cloned();
+    let footprint = env
+        .host()
+        .with_mut_storage(|storage| {
+            let mut read_only = Vec::new();
+            let mut read_write = Vec::new();
+            for (key, access) in storage.footprint.0.iter(&budget)? {
+                match access {
+                    AccessType::ReadOnly => read_only.push((**key).clone()),
+                    AccessType::ReadWrite => read_write.push((**key).clone()),
+                }
+            }
+            Ok((read_only, read_write))
+        })
+        .map_err(|e| anyhow!("{e:?}"))?;
+
+    let resources = env.cost_estimate().resources();
+    Ok(Simulation {
+        result,
+        authorizers,
+        events,
+        transaction_data: SorobanTransactionData {
+            ext: ExtensionPoint::V0,
+            resources: SorobanResources {
+                footprint: LedgerFootprint {
+                    read_only: footprint.0.try_into()?,
+                    read_write: footprint.1.try_into()?,
+                },
+                instructions: resources.instructions.try_into()?,
+                read_bytes: resources.read_bytes,
+                write_bytes: resources.write_bytes,
+            },
+            resource_fee: env.cost_estimate().fee().total,
+        },
+    })
+}
+
+#[cfg(not(any(test, feature = "sandbox")))]
+pub fn simulate(_env: &Env, _contract: &Address, _invocation: &Invocation) -> Result<Simulation> {
+    Err(anyhow!(NO_SANDBOX))
+}
@@ -0,0 +1,354 @@
+#![cfg(test)]
+
+use clap::Parser;
+use ed25519_dalek::{Signature, SigningKey, Verifier};
+use escrow::{EscrowContract, EscrowContractClient};
+use setu_client::network_id;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::xdr::{
+    Hash, HostFunction, Limits, OperationBody, ReadXdr, ScAddress, SorobanAuthorizedFunction,
+    SorobanCredentials, TransactionEnvelope, TransactionExt, TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction, WriteXdr,
+};
+use soroban_sdk::{Address, BytesN, Env, String};
+use tokens::{FaucetConfig, Token, TokenClient};
+
+use crate::{run, Cli, TESTNET_PASSPHRASE};
+
+const RESCUE_DELAY: u64 = 3600;
+
+struct Sandbox {
+    env: Env,
+    escrow: Address,
+    token: Address,
+    maker_key: SigningKey,
+    taker_key: SigningKey,
+    taker: Address,
+}
+
+fn strkey(address: &Address) -> std::string::String {
+    ScAddress::from(address).to_string()
+}
+
+fn secret(key: &SigningKey) -> std::string::String {
+    stellar_strkey::ed25519::PrivateKey(key.to_bytes()).to_string()
+}
+
+fn account(env: &Env, key: &SigningKey) -> Address {
+    let public_key = stellar_strkey::ed25519::PublicKey(key.verifying_key().to_bytes());
+    Address::from_str(env, &public_key.to_string())
+}
+
+fn sandbox() -> Sandbox {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000_000);
+
+    let maker_key = SigningKey::from_bytes(&[1u8; 32]);
+    let taker_key = SigningKey::from_bytes(&[2u8; 32]);
+    let maker = account(&env, &maker_key);
+    let taker = account(&env, &taker_key);
+
+    let admin = Address::generate(&env);
+    let token = env.register(
+        Token,
+        (
+            &admin,
+            7u32,
+            String::from_str(&env, "Setu"),
+            String::from_str(&env, "SETU"),
+            None::<i128>,
+            None::<FaucetConfig>,
+        ),
+    );
+    TokenClient::new(&env, &token).mint(&maker, &1_000_000);
+    let escrow = env.register(EscrowContract, (Address::generate(&env),));
+
+    Sandbox {
+        env,
+        escrow,
+        token,
+        maker_key,
+        taker_key,
+        taker,
+    }
+}
+
+fn cli(s: &Sandbox, key: &SigningKey, args: &[&str]) -> Cli {
+    let mut argv = vec![
+        "setu".to_string(),
+        "--contract".to_string(),
+        strkey(&s.escrow),
+        "--secret-key".to_string(),
+        secret(key),
+        "--sequence".to_string(),
+        "43".to_string(),
+    ];
+    argv.extend(args.iter().map(|arg| arg.to_string()));
+    Cli::try_parse_from(argv).unwrap()
+}
+
+fn create_args(s: &Sandbox) -> Vec<std::string::String> {
+    let hashlock = s
+        .env
+        .crypto()
+        .keccak256(&soroban_sdk::Bytes::from_array(&s.env, &[7u8; 32]))
+        .to_array();
+    vec![
+        "create".to_string(),
+        "--taker".to_string(),
+        strkey(&s.taker),
+        "--token".to_string(),
+        strkey(&s.token),
+        "--amount".to_string(),
+        "1000".to_string(),
+        "--safety-deposit".to_string(),
+        "100".to_string(),
+        "--hashlock".to_string(),
+        hex::encode(hashlock),
+        "--rescue-delay".to_string(),
+        RESCUE_DELAY.to_string(),
+    ]
+}
+
+fn run_args(
+    s: &Sandbox,
+    key: &SigningKey,
+    args: &[std::string::String],
+) -> anyhow::Result<std::string::String> {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    run(&cli(s, key, &args), &s.env)
+}
+
+fn decode(envelope: &str) -> soroban_sdk::xdr::TransactionV1Envelope {
+    match TransactionEnvelope::from_xdr_base64(envelope, Limits::none()).unwrap() {
+        TransactionEnvelope::Tx(envelope) => envelope,
+        _ => panic!("expected a v1 envelope"),
+    }
+}
+
+fn only_order_hash(s: &Sandbox) -> BytesN<32> {
+    let page = EscrowContractClient::new(&s.env, &s.escrow).active_escrows(&0, &10);
+    assert_eq!(page.total, 1);
+    page.order_hashes.get(0).unwrap()
+}
+
+#[test]
+fn test_create_builds_signed_envelope() {
+    let s = sandbox();
+    let envelope = decode(&run_args(&s, &s.maker_key, &create_args(&s)).unwrap());
+
+    assert_eq!(envelope.tx.seq_num.0, 43);
+    assert_eq!(envelope.tx.fee, 100);
+    assert_eq!(envelope.tx.ext, TransactionExt::V0);
+
+    let OperationBody::InvokeHostFunction(op) = &envelope.tx.operations[0].body else {
+        panic!("expected an invoke host function operation");
+    };
+    let HostFunction::InvokeContract(call) = &op.host_function else {
+        panic!("expected a contract call");
+    };
+    assert_eq!(call.contract_address, ScAddress::from(&s.escrow));
+    assert_eq!(
+        call.function_name.to_utf8_string().unwrap(),
+        "create_escrow"
+    );
+
+    // The maker signs as the source account, including the funding transfer
+    let [auth] = op.auth.as_slice() else {
+        panic!("expected one auth entry");
+    };
+    assert_eq!(auth.credentials, SorobanCredentials::SourceAccount);
+    let [transfer] = auth.root_invocation.sub_invocations.as_slice() else {
+        panic!("expected the funding transfer");
+    };
+    let SorobanAuthorizedFunction::ContractFn(transfer) = &transfer.function else {
+        panic!("expected a contract call");
+    };
+    assert_eq!(transfer.contract_address, ScAddress::from(&s.token));
+
+    let payload = TransactionSignaturePayload {
+        network_id: Hash(network_id(TESTNET_PASSPHRASE)),
+        tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(envelope.tx.clone()),
+    };
+    let hash = s
+        .env
+        .crypto()
+        .sha256(&soroban_sdk::Bytes::from_slice(
+            &s.env,
+            &payload.to_xdr(Limits::none()).unwrap(),
+        ))
+        .to_array();
+    let signature = Signature::from_slice(&envelope.signatures[0].signature.0).unwrap();
+    s.maker_key
+        .verifying_key()
+        .verify(&hash, &signature)
+        .unwrap();
+}
+
+#[test]
+fn test_create_simulated_includes_footprint() {
+    let s = sandbox();
+    let mut args = create_args(&s);
+    args.push("--simulate".to_string());
+    let envelope = decode(&run_args(&s, &s.maker_key, &args).unwrap());
+
+    let TransactionExt::V1(data) = &envelope.tx.ext else {
+        panic!("expected soroban transaction data");
+    };
+    assert!(!data.resources.footprint.read_write.is_empty());
+    assert!(data.resource_fee > 0);
+    assert_eq!(envelope.tx.fee as i64, 100 + data.resource_fee);
+
+    // The sandbox now holds the escrow
+    only_order_hash(&s);
+}
+
+#[test]
+fn test_status_and_schedule_simulated() {
+    let s = sandbox();
+    let mut args = create_args(&s);
+    args.push("--simulate".to_string());
+    run_args(&s, &s.maker_key, &args).unwrap();
+    let order_hash = hex::encode(only_order_hash(&s).to_array());
+
+    let status = run_args(
+        &s,
+        &s.maker_key,
+        &[
+            "status".to_string(),
+            "--order-hash".to_string(),
+            order_hash.clone(),
+            "--simulate".to_string(),
+        ],
+    )
+    .unwrap();
+    assert!(status.contains("status: Active"));
+
+    let schedule = run_args(
+        &s,
+        &s.maker_key,
+        &[
+            "schedule".to_string(),
+            "--order-hash".to_string(),
+            order_hash,
+            "--simulate".to_string(),
+        ],
+    )
+    .unwrap();
+    assert!(schedule.contains("current: Finality"));
+
+    let missing = run_args(
+        &s,
+        &s.maker_key,
+        &[
+            "status".to_string(),
+            "--order-hash".to_string(),
+            hex::encode([9u8; 32]),
+            "--simulate".to_string(),
+        ],
+    )
+    .unwrap();
+    assert_eq!(missing, "escrow not found");
+}
+
+#[test]
+fn test_withdraw_simulated() {
+    let s = sandbox();
+    let mut args = create_args(&s);
+    args.push("--simulate".to_string());
+    run_args(&s, &s.maker_key, &args).unwrap();
+    let order_hash = only_order_hash(&s);
+
+    let now = s.env.ledger().timestamp();
+    s.env.ledger().set_timestamp(now + RESCUE_DELAY + 1);
+    let withdraw = [
+        "withdraw".to_string(),
+        "--order-hash".to_string(),
+        hex::encode(order_hash.to_array()),
+        "--secret".to_string(),
+        hex::encode([7u8; 32]),
+        "--simulate".to_string(),
+    ];
+    let envelope = decode(&run_args(&s, &s.taker_key, &withdraw).unwrap());
+    assert!(matches!(envelope.tx.ext, TransactionExt::V1(_)));
+
+    let token = TokenClient::new(&s.env, &s.token);
+    assert_eq!(token.balance(&s.taker), 1_000);
+}
+
+#[test]
+fn test_status_requires_simulate() {
+    let s = sandbox();
+    for command in ["status", "schedule"] {
+        let args = [
+            command.to_string(),
+            "--order-hash".to_string(),
+            hex::encode([9u8; 32]),
+        ];
+        let err = run_args(&s, &s.maker_key, &args).unwrap_err();
+        assert_eq!(err.to_string(), "status and schedule need --simulate");
+    }
+}
+
+#[test]
+fn test_withdraw_requires_taker_signer() {
+    let s = sandbox();
+    let mut args = create_args(&s);
+    args.push("--simulate".to_string());
+    run_args(&s, &s.maker_key, &args).unwrap();
+    let order_hash = only_order_hash(&s);
+
+    let now = s.env.ledger().timestamp();
+    s.env.ledger().set_timestamp(now + RESCUE_DELAY + 1);
+    let withdraw = [
+        "withdraw".to_string(),
+        "--order-hash".to_string(),
+        hex::encode(order_hash.to_array()),
+        "--secret".to_string(),
+        hex::encode([7u8; 32]),
+        "--simulate".to_string(),
+    ];
+    let err = run_args(&s, &s.maker_key, &withdraw).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "{} has to authorize this call, not the signer",
+            strkey(&s.taker)
+        )
+    );
+}
+
+#[test]
+fn test_failed_simulation_is_reported() {
+    let s = sandbox();
+    let cancel = [
+        "cancel".to_string(),
+        "--order-hash".to_string(),
+        hex::encode([9u8; 32]),
+        "--simulate".to_string(),
+    ];
+    let err = run_args(&s, &s.taker_key, &cancel).unwrap_err();
+    assert!(err.to_string().contains("simulation failed"));
+}
+
+#[test]
+fn test_create_requires_signer_to_fund() {
+    let s = sandbox();
+    let mut args = create_args(&s);
+    args.extend(["--maker".to_string(), strkey(&s.taker)]);
+    let err = run_args(&s, &s.maker_key, &args).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the signer must be the maker of the escrow it opens"
+    );
+}
+
+#[test]
+fn test_dst_create_requires_src_cancellation() {
+    let s = sandbox();
+    let mut argv = vec!["setu".to_string()];
+    argv.extend(create_args(&s));
+    argv.push("--dst".to_string());
+    assert!(Cli::try_parse_from(argv).is_err());
+}
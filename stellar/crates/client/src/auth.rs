@@ -0,0 +1,89 @@
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use soroban_sdk::xdr::{
+    Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization, Limits, ScAddress, ScBytes, ScMap,
+    ScMapEntry, ScSymbol, ScVal, ScVec, SorobanAddressCredentials, SorobanAuthorizationEntry,
+    SorobanAuthorizedInvocation, SorobanCredentials, WriteXdr,
+};
+
+use crate::Error;
+
+/// Authorization entry for `address` over `root_invocation`, still to be
+/// signed with `sign_auth_entry`.
+///
+/// `nonce` must not have been used by `address` before and the entry stops
+/// being valid after `signature_expiration_ledger`.
+pub fn unsigned_auth_entry(
+    address: &ScAddress,
+    nonce: i64,
+    signature_expiration_ledger: u32,
+    root_invocation: SorobanAuthorizedInvocation,
+) -> SorobanAuthorizationEntry {
+    SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: address.clone(),
+            nonce,
+            signature_expiration_ledger,
+            signature: ScVal::Void,
+        }),
+        root_invocation,
+    }
+}
+
+/// Authorization entry for `root_invocation` covered by the transaction
+/// source account's signature.
+pub fn source_account_auth_entry(
+    root_invocation: SorobanAuthorizedInvocation,
+) -> SorobanAuthorizationEntry {
+    SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::SourceAccount,
+        root_invocation,
+    }
+}
+
+/// Sign `entry` with an ed25519 key for the network with id `network_id`
+/// (the SHA-256 of the network passphrase).
+///
+/// The signature uses the layout expected for Stellar accounts: a vector of
+/// `{public_key, signature}` maps. Entries relying on the transaction source
+/// account carry no signature and are returned unchanged.
+pub fn sign_auth_entry(
+    entry: &SorobanAuthorizationEntry,
+    network_id: &[u8; 32],
+    key: &SigningKey,
+) -> Result<SorobanAuthorizationEntry, Error> {
+    let SorobanCredentials::Address(credentials) = &entry.credentials else {
+        return Ok(entry.clone());
+    };
+
+    let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+        network_id: Hash(*network_id),
+        nonce: credentials.nonce,
+        signature_expiration_ledger: credentials.signature_expiration_ledger,
+        invocation: entry.root_invocation.clone(),
+    });
+    let payload: [u8; 32] = Sha256::digest(preimage.to_xdr(Limits::none())?).into();
+    let signature = key.sign(&payload);
+
+    let signature = ScVal::Map(Some(ScMap(
+        vec![
+            ScMapEntry {
+                key: ScVal::Symbol(ScSymbol("public_key".try_into()?)),
+                val: ScVal::Bytes(ScBytes(key.verifying_key().to_bytes().try_into()?)),
+            },
+            ScMapEntry {
+                key: ScVal::Symbol(ScSymbol("signature".try_into()?)),
+                val: ScVal::Bytes(ScBytes(signature.to_bytes().try_into()?)),
+            },
+        ]
+        .try_into()?,
+    )));
+
+    Ok(SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            signature: ScVal::Vec(Some(ScVec(vec![signature].try_into()?))),
+            ..credentials.clone()
+        }),
+        root_invocation: entry.root_invocation.clone(),
+    })
+}
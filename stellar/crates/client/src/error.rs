@@ -0,0 +1,27 @@
+use core::fmt;
+
+use soroban_sdk::xdr;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A value could not be converted between its contract type and XDR
+    Conversion,
+    Xdr(xdr::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Conversion => write!(f, "value does not match the expected contract type"),
+            Error::Xdr(e) => write!(f, "xdr error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<xdr::Error> for Error {
+    fn from(e: xdr::Error) -> Self {
+        Error::Xdr(e)
+    }
+}
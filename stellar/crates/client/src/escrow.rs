@@ -0,0 +1,280 @@
+use escrow::{AllowedResolvers, CreateEscrowArgs, HashLock, TokenAmount, WithdrawArgs};
+use soroban_sdk::xdr::SorobanAuthorizedInvocation;
+use soroban_sdk::{Address, BytesN, Env, IntoVal, Val, Vec};
+
+use crate::{Error, Invocation, TokenCalls};
+
+/// Invocations of an `EscrowContract` deployment.
+#[derive(Clone)]
+pub struct EscrowCalls {
+    env: Env,
+    contract: Address,
+}
+
+impl EscrowCalls {
+    pub fn new(env: &Env, contract: &Address) -> Self {
+        Self {
+            env: env.clone(),
+            contract: contract.clone(),
+        }
+    }
+
+    fn call(&self, function: &str, args: Vec<Val>) -> Result<Invocation, Error> {
+        Invocation::new(&self.env, &self.contract, function, args)
+    }
+
+    pub fn schedule_upgrade(&self, new_wasm_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("schedule_upgrade", (new_wasm_hash,).into_val(&self.env))
+    }
+
+    pub fn cancel_upgrade(&self) -> Result<Invocation, Error> {
+        self.call("cancel_upgrade", Vec::new(&self.env))
+    }
+
+    pub fn apply_upgrade(&self) -> Result<Invocation, Error> {
+        self.call("apply_upgrade", Vec::new(&self.env))
+    }
+
+    pub fn migrate(&self, from_version: u32) -> Result<Invocation, Error> {
+        self.call("migrate", (from_version,).into_val(&self.env))
+    }
+
+    pub fn get_pending_upgrade(&self) -> Result<Invocation, Error> {
+        self.call("get_pending_upgrade", Vec::new(&self.env))
+    }
+
+    pub fn get_storage_version(&self) -> Result<Invocation, Error> {
+        self.call("get_storage_version", Vec::new(&self.env))
+    }
+
+    pub fn set_admin(&self, new_admin: &Address) -> Result<Invocation, Error> {
+        self.call("set_admin", (new_admin,).into_val(&self.env))
+    }
+
+    pub fn create_escrow(&self, args: &CreateEscrowArgs) -> Result<Invocation, Error> {
+        self.call("create_escrow", (args.clone(),).into_val(&self.env))
+    }
+
+    /// Authorization tree the maker signs for `create_escrow`, including the
+    /// token transfers that fund the escrow.
+    ///
+    /// For orders with a resolver whitelist the maker signs the args with
+    /// `taker` set to the maker and an empty `resolver_proof`; the resolver
+    /// signs `create_escrow(args)` itself.
+    pub fn create_escrow_auth(
+        &self,
+        args: &CreateEscrowArgs,
+    ) -> Result<SorobanAuthorizedInvocation, Error> {
+        let signed_args = if args.resolvers == AllowedResolvers::Taker {
+            args.clone()
+        } else {
+            CreateEscrowArgs {
+                taker: args.maker.clone(),
+                resolver_proof: Vec::new(&self.env),
+                ..args.clone()
+            }
+        };
+        self.create_escrow(&signed_args)?
+            .authorized(self.funding_transfers(args, &args.maker)?)
+    }
+
+    pub fn create_dst_escrow(&self, args: &CreateEscrowArgs) -> Result<Invocation, Error> {
+        self.call("create_dst_escrow", (args.clone(),).into_val(&self.env))
+    }
+
+    /// Authorization tree the taker signs for `create_dst_escrow`.
+    pub fn create_dst_escrow_auth(
+        &self,
+        args: &CreateEscrowArgs,
+    ) -> Result<SorobanAuthorizedInvocation, Error> {
+        self.create_dst_escrow(args)?
+            .authorized(self.funding_transfers(args, &args.taker)?)
+    }
+
+    /// Transfers from `funder` made while opening the escrow described by `args`.
+    fn funding_transfers(
+        &self,
+        args: &CreateEscrowArgs,
+        funder: &Address,
+    ) -> Result<std::vec::Vec<SorobanAuthorizedInvocation>, Error> {
+        let mut transfers = std::vec![TokenCalls::new(&self.env, &args.token)
+            .transfer(funder, &self.contract, args.amount + args.safety_deposit)?
+            .authorized(std::vec![])?];
+        for leg in args.bundle.iter() {
+            transfers.push(
+                TokenCalls::new(&self.env, &leg.token)
+                    .transfer(funder, &self.contract, leg.amount)?
+                    .authorized(std::vec![])?,
+            );
+        }
+        Ok(transfers)
+    }
+
+    pub fn extend_timelocks(
+        &self,
+        order_hash: &BytesN<32>,
+        new_timelocks: &Vec<u64>,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "extend_timelocks",
+            (order_hash, new_timelocks.clone()).into_val(&self.env),
+        )
+    }
+
+    pub fn withdraw(
+        &self,
+        order_hash: &BytesN<32>,
+        preimage: &BytesN<32>,
+        merkle_proof: &Vec<BytesN<32>>,
+        fill_index: u32,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "withdraw",
+            (order_hash, preimage, merkle_proof.clone(), fill_index).into_val(&self.env),
+        )
+    }
+
+    pub fn cancel(&self, order_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("cancel", (order_hash,).into_val(&self.env))
+    }
+
+    pub fn public_withdraw(
+        &self,
+        order_hash: &BytesN<32>,
+        preimage: &BytesN<32>,
+        merkle_proof: &Vec<BytesN<32>>,
+        fill_index: u32,
+        executor: &Address,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "public_withdraw",
+            (
+                order_hash,
+                preimage,
+                merkle_proof.clone(),
+                fill_index,
+                executor,
+            )
+                .into_val(&self.env),
+        )
+    }
+
+    pub fn public_cancel(
+        &self,
+        order_hash: &BytesN<32>,
+        executor: &Address,
+    ) -> Result<Invocation, Error> {
+        self.call("public_cancel", (order_hash, executor).into_val(&self.env))
+    }
+
+    pub fn batch_withdraw(&self, items: &Vec<WithdrawArgs>) -> Result<Invocation, Error> {
+        self.call("batch_withdraw", (items.clone(),).into_val(&self.env))
+    }
+
+    pub fn batch_cancel(&self, order_hashes: &Vec<BytesN<32>>) -> Result<Invocation, Error> {
+        self.call("batch_cancel", (order_hashes.clone(),).into_val(&self.env))
+    }
+
+    pub fn batch_public_cancel(
+        &self,
+        order_hashes: &Vec<BytesN<32>>,
+        executor: &Address,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "batch_public_cancel",
+            (order_hashes.clone(), executor).into_val(&self.env),
+        )
+    }
+
+    pub fn get_escrow_info(&self, order_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("get_escrow_info", (order_hash,).into_val(&self.env))
+    }
+
+    pub fn get_status(&self, order_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("get_status", (order_hash,).into_val(&self.env))
+    }
+
+    pub fn escrow_exists(&self, order_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("escrow_exists", (order_hash,).into_val(&self.env))
+    }
+
+    pub fn escrows_by_maker(
+        &self,
+        maker: &Address,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "escrows_by_maker",
+            (maker, cursor, limit).into_val(&self.env),
+        )
+    }
+
+    pub fn escrows_by_taker(
+        &self,
+        taker: &Address,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "escrows_by_taker",
+            (taker, cursor, limit).into_val(&self.env),
+        )
+    }
+
+    pub fn active_escrows(&self, cursor: u32, limit: u32) -> Result<Invocation, Error> {
+        self.call("active_escrows", (cursor, limit).into_val(&self.env))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_order_hash(
+        &self,
+        maker: &Address,
+        taker: &Address,
+        receiver: &Address,
+        token: &Address,
+        amount: i128,
+        bundle: &Vec<TokenAmount>,
+        hashlock: &HashLock,
+        timelocks: &Vec<u64>,
+        is_src: bool,
+        src_cancellation: Option<u64>,
+    ) -> Result<Invocation, Error> {
+        let mut args: Vec<Val> = (maker, taker, receiver, token, amount).into_val(&self.env);
+        args.append(
+            &(
+                bundle.clone(),
+                hashlock.clone(),
+                timelocks.clone(),
+                is_src,
+                src_cancellation,
+            )
+                .into_val(&self.env),
+        );
+        self.call("compute_order_hash", args)
+    }
+
+    pub fn get_timelock_deadline(
+        &self,
+        init_time: u64,
+        stage: u32,
+        rescue_delay: u32,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "get_timelock_deadline",
+            (init_time, stage, rescue_delay).into_val(&self.env),
+        )
+    }
+
+    pub fn get_current_stage(&self, order_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("get_current_stage", (order_hash,).into_val(&self.env))
+    }
+
+    pub fn get_secret_count(&self, order_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("get_secret_count", (order_hash,).into_val(&self.env))
+    }
+
+    pub fn get_schedule(&self, order_hash: &BytesN<32>) -> Result<Invocation, Error> {
+        self.call("get_schedule", (order_hash,).into_val(&self.env))
+    }
+}
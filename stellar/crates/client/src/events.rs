@@ -0,0 +1,266 @@
+use escrow::{
+    EscrowCreatedEvent, EscrowStatus, FundsClaimedEvent, FundsRefundedEvent,
+    SafetyDepositClaimedEvent, TimelocksExtendedEvent, TokenAmount, UpgradeAppliedEvent,
+    UpgradeCancelledEvent, UpgradeScheduledEvent,
+};
+use relayer::SecretSharedEvent;
+use soroban_sdk::xdr::ScVal;
+use soroban_sdk::{
+    contracttype, symbol_short, Address, BytesN, Env, Map, String, Symbol, Val, Vec,
+};
+use tokens::ExtendedMetadata;
+
+use crate::{from_sc_val, Error};
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EscrowEvent {
+    Created(EscrowCreatedEvent),
+    Claimed(FundsClaimedEvent),
+    Refunded(FundsRefundedEvent),
+    SafetyDepositClaimed(SafetyDepositClaimedEvent),
+    TimelocksExtended(TimelocksExtendedEvent),
+    UpgradeScheduled(UpgradeScheduledEvent),
+    UpgradeCancelled(UpgradeCancelledEvent),
+    UpgradeApplied(UpgradeAppliedEvent),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RelayerEvent {
+    SecretShared(SecretSharedEvent),
+}
+
+/// Events of the tokens contract, including the SEP-41 events any token
+/// publishes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TokenEvent {
+    Transfer {
+        from: Address,
+        to: Address,
+        amount: i128,
+    },
+    Mint {
+        admin: Address,
+        to: Address,
+        amount: i128,
+    },
+    Burn {
+        from: Address,
+        amount: i128,
+    },
+    Clawback {
+        admin: Address,
+        from: Address,
+        amount: i128,
+    },
+    Approve {
+        from: Address,
+        spender: Address,
+        amount: i128,
+        expiration_ledger: u32,
+    },
+    SetAdmin {
+        admin: Address,
+        new_admin: Address,
+    },
+    SetAuthorized {
+        admin: Address,
+        id: Address,
+        authorize: bool,
+    },
+    SetMinter {
+        admin: Address,
+        minter: Address,
+        daily_quota: i128,
+    },
+    RemoveMinter {
+        admin: Address,
+        minter: Address,
+    },
+    SetName {
+        admin: Address,
+        name: String,
+    },
+    SetSymbol {
+        admin: Address,
+        symbol: String,
+    },
+    SetExtendedMetadata {
+        admin: Address,
+        metadata: ExtendedMetadata,
+    },
+}
+
+/// `refunded` data published before the event carried the escrow's status
+#[contracttype(export = false)]
+struct FundsRefundedEventV1 {
+    order_hash: BytesN<32>,
+    sender: Address,
+    amount: i128,
+    merkle_root: BytesN<32>,
+    parts: u32,
+    legs: Vec<TokenAmount>,
+}
+
+fn refunded_event(env: &Env, data: &ScVal) -> Result<FundsRefundedEvent, Error> {
+    // Decoding a map into a struct with other fields traps, so look first
+    let fields: Map<Symbol, Val> = from_sc_val(env, data)?;
+    if fields.contains_key(symbol_short!("status")) {
+        return from_sc_val(env, data);
+    }
+    let event: FundsRefundedEventV1 = from_sc_val(env, data)?;
+    // `cancel` published its refunds without parts, `public_cancel` with the
+    // escrow's, which are at least one
+    let status = if event.parts == 0 {
+        EscrowStatus::Cancelled
+    } else {
+        EscrowStatus::PublicCancelled
+    };
+    Ok(FundsRefundedEvent {
+        order_hash: event.order_hash,
+        sender: event.sender,
+        amount: event.amount,
+        merkle_root: event.merkle_root,
+        parts: event.parts,
+        legs: event.legs,
+        status,
+    })
+}
+
+/// Symbol names of the event topics, `None` if any topic is not a symbol.
+fn symbols(topics: &[ScVal]) -> Option<std::vec::Vec<std::string::String>> {
+    topics
+        .iter()
+        .map(|topic| match topic {
+            ScVal::Symbol(symbol) => symbol.to_utf8_string().ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Decode an event published by `EscrowContract`.
+///
+/// Returns `Ok(None)` for events with other topics and an error when the
+/// topics match but the data does not.
+pub fn decode_escrow_event(
+    env: &Env,
+    topics: &[ScVal],
+    data: &ScVal,
+) -> Result<Option<EscrowEvent>, Error> {
+    let Some(topics) = symbols(topics) else {
+        return Ok(None);
+    };
+    let topics: std::vec::Vec<&str> = topics.iter().map(|t| t.as_str()).collect();
+    let event = match topics.as_slice() {
+        ["created"] => EscrowEvent::Created(from_sc_val(env, data)?),
+        ["claimed"] => EscrowEvent::Claimed(from_sc_val(env, data)?),
+        ["refunded"] => EscrowEvent::Refunded(refunded_event(env, data)?),
+        ["safety"] => EscrowEvent::SafetyDepositClaimed(from_sc_val(env, data)?),
+        ["extended"] => EscrowEvent::TimelocksExtended(from_sc_val(env, data)?),
+        ["upgrade", "scheduled"] => EscrowEvent::UpgradeScheduled(from_sc_val(env, data)?),
+        ["upgrade", "cancelled"] => EscrowEvent::UpgradeCancelled(from_sc_val(env, data)?),
+        ["upgrade", "applied"] => EscrowEvent::UpgradeApplied(from_sc_val(env, data)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+/// Decode an event published by `SecretRelayerContract`.
+pub fn decode_relayer_event(
+    env: &Env,
+    topics: &[ScVal],
+    data: &ScVal,
+) -> Result<Option<RelayerEvent>, Error> {
+    match symbols(topics).as_deref() {
+        Some([topic]) if topic == "secret" => {
+            Ok(Some(RelayerEvent::SecretShared(from_sc_val(env, data)?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Decode an event published by a token contract.
+///
+/// Topics after the addresses, such as the asset name added by a Stellar
+/// Asset Contract, are ignored.
+pub fn decode_token_event(
+    env: &Env,
+    topics: &[ScVal],
+    data: &ScVal,
+) -> Result<Option<TokenEvent>, Error> {
+    let Some((ScVal::Symbol(name), addresses)) = topics.split_first() else {
+        return Ok(None);
+    };
+    let Ok(name) = name.to_utf8_string() else {
+        return Ok(None);
+    };
+    // A Stellar Asset Contract appends the asset name after the addresses.
+    let addresses = addresses
+        .iter()
+        .take_while(|topic| matches!(topic, ScVal::Address(_)))
+        .map(|topic| from_sc_val::<Address>(env, topic))
+        .collect::<Result<std::vec::Vec<_>, _>>()?;
+
+    let event = match (name.as_str(), addresses.as_slice()) {
+        ("transfer", [from, to]) => TokenEvent::Transfer {
+            from: from.clone(),
+            to: to.clone(),
+            amount: from_sc_val(env, data)?,
+        },
+        ("mint", [admin, to]) => TokenEvent::Mint {
+            admin: admin.clone(),
+            to: to.clone(),
+            amount: from_sc_val(env, data)?,
+        },
+        ("burn", [from]) => TokenEvent::Burn {
+            from: from.clone(),
+            amount: from_sc_val(env, data)?,
+        },
+        ("clawback", [admin, from]) => TokenEvent::Clawback {
+            admin: admin.clone(),
+            from: from.clone(),
+            amount: from_sc_val(env, data)?,
+        },
+        ("approve", [from, spender]) => {
+            let (amount, expiration_ledger) = from_sc_val(env, data)?;
+            TokenEvent::Approve {
+                from: from.clone(),
+                spender: spender.clone(),
+                amount,
+                expiration_ledger,
+            }
+        }
+        ("set_admin", [admin]) => TokenEvent::SetAdmin {
+            admin: admin.clone(),
+            new_admin: from_sc_val(env, data)?,
+        },
+        ("set_authorized", [admin, id]) => TokenEvent::SetAuthorized {
+            admin: admin.clone(),
+            id: id.clone(),
+            authorize: from_sc_val(env, data)?,
+        },
+        ("set_minter", [admin, minter]) => TokenEvent::SetMinter {
+            admin: admin.clone(),
+            minter: minter.clone(),
+            daily_quota: from_sc_val(env, data)?,
+        },
+        ("remove_minter", [admin, minter]) => TokenEvent::RemoveMinter {
+            admin: admin.clone(),
+            minter: minter.clone(),
+        },
+        ("set_name", [admin]) => TokenEvent::SetName {
+            admin: admin.clone(),
+            name: from_sc_val(env, data)?,
+        },
+        ("set_symbol", [admin]) => TokenEvent::SetSymbol {
+            admin: admin.clone(),
+            symbol: from_sc_val(env, data)?,
+        },
+        ("set_extended_metadata", [admin]) => TokenEvent::SetExtendedMetadata {
+            admin: admin.clone(),
+            metadata: from_sc_val(env, data)?,
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
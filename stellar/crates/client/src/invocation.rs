@@ -0,0 +1,99 @@
+use soroban_sdk::xdr::{
+    HostFunction, InvokeContractArgs, InvokeHostFunctionOp, Limits, Operation, OperationBody,
+    ScAddress, ScSymbol, ScVal, SorobanAuthorizationEntry, SorobanAuthorizedFunction,
+    SorobanAuthorizedInvocation, WriteXdr,
+};
+use soroban_sdk::{Address, Env, IntoVal, TryFromVal, Val};
+
+use crate::Error;
+
+/// Convert a contract value to XDR.
+pub fn to_sc_val<T: IntoVal<Env, Val>>(env: &Env, value: &T) -> Result<ScVal, Error> {
+    let val: Val = value.into_val(env);
+    ScVal::try_from_val(env, &val).map_err(|_| Error::Conversion)
+}
+
+/// Convert an XDR value back to a contract type.
+pub fn from_sc_val<T: TryFromVal<Env, Val>>(env: &Env, value: &ScVal) -> Result<T, Error> {
+    let val = Val::try_from_val(env, value).map_err(|_| Error::Conversion)?;
+    T::try_from_val(env, &val).map_err(|_| Error::Conversion)
+}
+
+/// A call to one contract function with its arguments encoded as XDR.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Invocation {
+    pub contract: ScAddress,
+    pub function: ScSymbol,
+    pub args: Vec<ScVal>,
+}
+
+impl Invocation {
+    pub(crate) fn new(
+        env: &Env,
+        contract: &Address,
+        function: &str,
+        args: soroban_sdk::Vec<Val>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            contract: contract.into(),
+            function: function.try_into().map_err(|_| Error::Conversion)?,
+            args: args
+                .iter()
+                .map(|arg| ScVal::try_from_val(env, &arg).map_err(|_| Error::Conversion))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Arguments as contract values, e.g. to call the contract in a test `Env`.
+    pub fn args_val(&self, env: &Env) -> Result<soroban_sdk::Vec<Val>, Error> {
+        let mut args = soroban_sdk::Vec::new(env);
+        for arg in &self.args {
+            args.push_back(Val::try_from_val(env, arg).map_err(|_| Error::Conversion)?);
+        }
+        Ok(args)
+    }
+
+    pub fn invoke_contract_args(&self) -> Result<InvokeContractArgs, Error> {
+        Ok(InvokeContractArgs {
+            contract_address: self.contract.clone(),
+            function_name: self.function.clone(),
+            args: self.args.clone().try_into()?,
+        })
+    }
+
+    pub fn host_function(&self) -> Result<HostFunction, Error> {
+        Ok(HostFunction::InvokeContract(self.invoke_contract_args()?))
+    }
+
+    /// Node of an authorization tree for this call. `sub_invocations` are the
+    /// nested calls that require auth from the same address.
+    pub fn authorized(
+        &self,
+        sub_invocations: Vec<SorobanAuthorizedInvocation>,
+    ) -> Result<SorobanAuthorizedInvocation, Error> {
+        Ok(SorobanAuthorizedInvocation {
+            function: SorobanAuthorizedFunction::ContractFn(self.invoke_contract_args()?),
+            sub_invocations: sub_invocations.try_into()?,
+        })
+    }
+
+    /// `InvokeHostFunction` operation carrying `auth`, ready to be added to a
+    /// transaction.
+    pub fn operation(&self, auth: Vec<SorobanAuthorizationEntry>) -> Result<Operation, Error> {
+        Ok(Operation {
+            source_account: None,
+            body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+                host_function: self.host_function()?,
+                auth: auth.try_into()?,
+            }),
+        })
+    }
+
+    /// Base64 XDR of `operation`.
+    pub fn operation_xdr_base64(
+        &self,
+        auth: Vec<SorobanAuthorizationEntry>,
+    ) -> Result<String, Error> {
+        Ok(self.operation(auth)?.to_xdr_base64(Limits::none())?)
+    }
+}
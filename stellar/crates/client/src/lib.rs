@@ -0,0 +1,34 @@
+//! Off-chain companion for the Setu Soroban contracts.
+//!
+//! Every callable function of `EscrowContract`, `SecretRelayerContract` and
+//! the tokens contract has a typed builder returning an [`Invocation`], which
+//! converts to the XDR needed for a transaction: the `InvokeHostFunction`
+//! operation and the `SorobanAuthorizationEntry` values its signers provide.
+//! Contract events decode back into the event structs the contracts publish.
+//! Invocations also build complete transactions, signed with an ed25519 key.
+//!
+//! Contract types such as `Address` are tied to a Soroban `Env`, so every
+//! builder holds one. Off-chain callers can use `Env::default()` as a scratch
+//! environment for the conversions.
+
+mod auth;
+mod error;
+mod escrow;
+mod events;
+mod invocation;
+mod relayer;
+mod test;
+mod token;
+mod transaction;
+
+pub use crate::auth::{sign_auth_entry, source_account_auth_entry, unsigned_auth_entry};
+pub use crate::error::Error;
+pub use crate::escrow::EscrowCalls;
+pub use crate::events::{
+    decode_escrow_event, decode_relayer_event, decode_token_event, EscrowEvent, RelayerEvent,
+    TokenEvent,
+};
+pub use crate::invocation::{from_sc_val, to_sc_val, Invocation};
+pub use crate::relayer::RelayerCalls;
+pub use crate::token::TokenCalls;
+pub use crate::transaction::{network_id, sign_transaction};
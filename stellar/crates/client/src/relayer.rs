@@ -0,0 +1,73 @@
+use soroban_sdk::{Address, BytesN, Env, IntoVal, Val, Vec};
+
+use crate::{Error, Invocation};
+
+/// Invocations of a `SecretRelayerContract` deployment.
+#[derive(Clone)]
+pub struct RelayerCalls {
+    env: Env,
+    contract: Address,
+}
+
+impl RelayerCalls {
+    pub fn new(env: &Env, contract: &Address) -> Self {
+        Self {
+            env: env.clone(),
+            contract: contract.clone(),
+        }
+    }
+
+    fn call(&self, function: &str, args: Vec<Val>) -> Result<Invocation, Error> {
+        Invocation::new(&self.env, &self.contract, function, args)
+    }
+
+    pub fn initialize(&self, account: &Address) -> Result<Invocation, Error> {
+        self.call("initialize", (account,).into_val(&self.env))
+    }
+
+    pub fn add_relayer(&self, admin: &Address, relayer: &Address) -> Result<Invocation, Error> {
+        self.call("add_relayer", (admin, relayer).into_val(&self.env))
+    }
+
+    pub fn remove_relayer(&self, admin: &Address, relayer: &Address) -> Result<Invocation, Error> {
+        self.call("remove_relayer", (admin, relayer).into_val(&self.env))
+    }
+
+    pub fn emit_secret_shared(
+        &self,
+        relayer: &Address,
+        admin: &Address,
+        local_escrow_addr: &Address,
+        order_hash: &BytesN<32>,
+        part_idx: u32,
+        escrow_contract: &Address,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "emit_secret_shared",
+            (
+                relayer,
+                admin,
+                local_escrow_addr,
+                order_hash,
+                part_idx,
+                escrow_contract,
+            )
+                .into_val(&self.env),
+        )
+    }
+
+    pub fn is_authorized_relayer(
+        &self,
+        admin: &Address,
+        relayer: &Address,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "is_authorized_relayer",
+            (admin, relayer).into_val(&self.env),
+        )
+    }
+
+    pub fn get_authorized_relayers(&self, admin: &Address) -> Result<Invocation, Error> {
+        self.call("get_authorized_relayers", (admin,).into_val(&self.env))
+    }
+}
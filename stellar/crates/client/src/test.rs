@@ -0,0 +1,482 @@
+#![cfg(test)]
+extern crate std;
+
+use ed25519_dalek::SigningKey;
+use escrow::{
+    AllowedResolvers, CreateEscrowArgs, EscrowContract, EscrowStatus, HashAlgorithm, TokenAmount,
+};
+use relayer::{SecretRelayerContract, SecretSharedEvent};
+use soroban_sdk::auth::{Context, CustomAccountInterface};
+use soroban_sdk::crypto::Hash;
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::token::{StellarAssetClient, TokenClient};
+use soroban_sdk::xdr::{Limits, Operation, ReadXdr, ScAddress, ScVal};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, vec, Address, BytesN, Env,
+    String, Symbol, TryFromVal, Val, Vec,
+};
+use tokens::{FaucetConfig, Token};
+
+use crate::{
+    decode_escrow_event, decode_relayer_event, decode_token_event, from_sc_val, sign_auth_entry,
+    to_sc_val, unsigned_auth_entry, EscrowCalls, EscrowEvent, Invocation, RelayerCalls,
+    RelayerEvent, TokenCalls, TokenEvent,
+};
+
+const RESCUE_DELAY: u32 = 3600;
+
+#[contracttype]
+#[derive(Clone)]
+pub struct AccountSignature {
+    pub public_key: BytesN<32>,
+    pub signature: BytesN<64>,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum AccountError {
+    UnknownSigner = 1,
+}
+
+/// Contract account controlled by one ed25519 key, verifying signatures in
+/// the layout `sign_auth_entry` produces.
+#[contract]
+struct Ed25519Account;
+
+#[contractimpl]
+impl Ed25519Account {
+    pub fn __constructor(env: Env, public_key: BytesN<32>) {
+        env.storage()
+            .instance()
+            .set(&symbol_short!("signer"), &public_key);
+    }
+}
+
+#[contractimpl]
+impl CustomAccountInterface for Ed25519Account {
+    type Signature = Vec<AccountSignature>;
+    type Error = AccountError;
+
+    fn __check_auth(
+        env: Env,
+        signature_payload: Hash<32>,
+        signatures: Vec<AccountSignature>,
+        _auth_contexts: Vec<Context>,
+    ) -> Result<(), AccountError> {
+        let signer: BytesN<32> = env
+            .storage()
+            .instance()
+            .get(&symbol_short!("signer"))
+            .unwrap();
+        for signature in signatures.iter() {
+            if signature.public_key != signer {
+                return Err(AccountError::UnknownSigner);
+            }
+            env.crypto().ed25519_verify(
+                &signature.public_key,
+                &signature_payload.clone().into(),
+                &signature.signature,
+            );
+        }
+        Ok(())
+    }
+}
+
+struct Setup {
+    env: Env,
+    maker: Address,
+    maker_key: SigningKey,
+    taker: Address,
+    taker_key: SigningKey,
+    token: Address,
+    escrow: Address,
+}
+
+fn account(env: &Env, key: &SigningKey) -> Address {
+    let public_key = BytesN::from_array(env, &key.verifying_key().to_bytes());
+    env.register(Ed25519Account, (public_key,))
+}
+
+fn setup() -> Setup {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000_000);
+    env.ledger().set_sequence_number(100);
+
+    let maker_key = SigningKey::from_bytes(&[1u8; 32]);
+    let taker_key = SigningKey::from_bytes(&[2u8; 32]);
+    let maker = account(&env, &maker_key);
+    let taker = account(&env, &taker_key);
+
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    StellarAssetClient::new(&env, &token).mint(&maker, &1_000_000);
+    let escrow = env.register(EscrowContract, (Address::generate(&env),));
+
+    Setup {
+        env,
+        maker,
+        maker_key,
+        taker,
+        taker_key,
+        token,
+        escrow,
+    }
+}
+
+fn escrow_args(s: &Setup) -> CreateEscrowArgs {
+    let preimage = BytesN::from_array(&s.env, &[7u8; 32]);
+    CreateEscrowArgs {
+        maker: s.maker.clone(),
+        taker: s.taker.clone(),
+        receiver: None,
+        token: s.token.clone(),
+        amount: 1_000,
+        bundle: Vec::new(&s.env),
+        safety_deposit: 100,
+        hashlock: s.env.crypto().keccak256(&preimage.into()).to_bytes(),
+        hash_algorithm: HashAlgorithm::Keccak256,
+        rescue_delay: RESCUE_DELAY,
+        is_src: true,
+        merkle_root: BytesN::from_array(&s.env, &[0u8; 32]),
+        parts: 1,
+        resolvers: AllowedResolvers::Taker,
+        resolver_proof: Vec::new(&s.env),
+        src_cancellation: None,
+    }
+}
+
+fn invoke<T: TryFromVal<Env, Val>>(env: &Env, contract: &Address, invocation: &Invocation) -> T {
+    let function = Symbol::new(env, &invocation.function.to_utf8_string().unwrap());
+    env.invoke_contract(contract, &function, invocation.args_val(env).unwrap())
+}
+
+fn signed_entry(
+    env: &Env,
+    signer: &Address,
+    key: &SigningKey,
+    nonce: i64,
+    root_invocation: soroban_sdk::xdr::SorobanAuthorizedInvocation,
+) -> soroban_sdk::xdr::SorobanAuthorizationEntry {
+    let entry = unsigned_auth_entry(
+        &ScAddress::from(signer),
+        nonce,
+        env.ledger().sequence() + 10,
+        root_invocation,
+    );
+    let network_id = env.ledger().network_id().to_array();
+    sign_auth_entry(&entry, &network_id, key).unwrap()
+}
+
+/// Events of the last invocation as the XDR topics and data an RPC returns.
+fn xdr_events(env: &Env) -> std::vec::Vec<(std::vec::Vec<ScVal>, ScVal)> {
+    env.events()
+        .all()
+        .iter()
+        .map(|(_, topics, data)| {
+            let topics = topics
+                .iter()
+                .map(|topic| ScVal::try_from_val(env, &topic).unwrap())
+                .collect();
+            (topics, ScVal::try_from_val(env, &data).unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn test_create_escrow_with_signed_auth() {
+    let s = setup();
+    let calls = EscrowCalls::new(&s.env, &s.escrow);
+    let args = escrow_args(&s);
+
+    let auth = calls.create_escrow_auth(&args).unwrap();
+    s.env
+        .set_auths(&[signed_entry(&s.env, &s.maker, &s.maker_key, 1, auth)]);
+    let order_hash: BytesN<32> = invoke(&s.env, &s.escrow, &calls.create_escrow(&args).unwrap());
+
+    let token = TokenClient::new(&s.env, &s.token);
+    assert_eq!(token.balance(&s.escrow), 1_100);
+    let status: Option<EscrowStatus> =
+        invoke(&s.env, &s.escrow, &calls.get_status(&order_hash).unwrap());
+    assert_eq!(status, Some(EscrowStatus::Active));
+}
+
+#[test]
+fn test_auth_signed_by_wrong_key_is_rejected() {
+    let s = setup();
+    let calls = EscrowCalls::new(&s.env, &s.escrow);
+    let args = escrow_args(&s);
+
+    let auth = calls.create_escrow_auth(&args).unwrap();
+    s.env
+        .set_auths(&[signed_entry(&s.env, &s.maker, &s.taker_key, 1, auth)]);
+    let invocation = calls.create_escrow(&args).unwrap();
+    let result = s.env.try_invoke_contract::<BytesN<32>, soroban_sdk::Error>(
+        &s.escrow,
+        &Symbol::new(&s.env, "create_escrow"),
+        invocation.args_val(&s.env).unwrap(),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_create_whitelisted_escrow_with_signed_auth() {
+    let s = setup();
+    let calls = EscrowCalls::new(&s.env, &s.escrow);
+    let args = CreateEscrowArgs {
+        resolvers: AllowedResolvers::List(vec![&s.env, s.taker.clone()]),
+        ..escrow_args(&s)
+    };
+
+    // The maker signs without knowing the resolver, the resolver signs the
+    // call it submits
+    let maker_auth = calls.create_escrow_auth(&args).unwrap();
+    let taker_auth = calls
+        .create_escrow(&args)
+        .unwrap()
+        .authorized(std::vec![])
+        .unwrap();
+    s.env.set_auths(&[
+        signed_entry(&s.env, &s.maker, &s.maker_key, 1, maker_auth),
+        signed_entry(&s.env, &s.taker, &s.taker_key, 2, taker_auth),
+    ]);
+    let order_hash: BytesN<32> = invoke(&s.env, &s.escrow, &calls.create_escrow(&args).unwrap());
+
+    let exists: bool = invoke(
+        &s.env,
+        &s.escrow,
+        &calls.escrow_exists(&order_hash).unwrap(),
+    );
+    assert!(exists);
+}
+
+#[test]
+fn test_create_dst_escrow_with_signed_auth() {
+    let s = setup();
+    StellarAssetClient::new(&s.env, &s.token).mint(&s.taker, &1_000_000);
+    let calls = EscrowCalls::new(&s.env, &s.escrow);
+    let args = CreateEscrowArgs {
+        is_src: false,
+        src_cancellation: Some(s.env.ledger().timestamp() + 4 * RESCUE_DELAY as u64),
+        ..escrow_args(&s)
+    };
+
+    let auth = calls.create_dst_escrow_auth(&args).unwrap();
+    s.env
+        .set_auths(&[signed_entry(&s.env, &s.taker, &s.taker_key, 1, auth)]);
+    let _: BytesN<32> = invoke(&s.env, &s.escrow, &calls.create_dst_escrow(&args).unwrap());
+
+    let token = TokenClient::new(&s.env, &s.token);
+    assert_eq!(token.balance(&s.escrow), 1_100);
+}
+
+#[test]
+fn test_decode_escrow_and_token_events() {
+    let s = setup();
+    let calls = EscrowCalls::new(&s.env, &s.escrow);
+    let order_hash: BytesN<32> = invoke(
+        &s.env,
+        &s.escrow,
+        &calls.create_escrow(&escrow_args(&s)).unwrap(),
+    );
+
+    let events = xdr_events(&s.env);
+    let escrow_events: std::vec::Vec<_> = events
+        .iter()
+        .filter_map(|(topics, data)| decode_escrow_event(&s.env, topics, data).unwrap())
+        .collect();
+    let [EscrowEvent::Created(created)] = escrow_events.as_slice() else {
+        panic!("expected one created event, got {escrow_events:?}");
+    };
+    assert_eq!(created.order_hash, order_hash);
+    assert_eq!(created.amount, 1_000);
+
+    let token_events: std::vec::Vec<_> = events
+        .iter()
+        .filter_map(|(topics, data)| decode_token_event(&s.env, topics, data).unwrap())
+        .collect();
+    assert_eq!(
+        token_events,
+        std::vec![TokenEvent::Transfer {
+            from: s.maker.clone(),
+            to: s.escrow.clone(),
+            amount: 1_100,
+        }]
+    );
+}
+
+#[test]
+fn test_decode_rejects_mismatched_data() {
+    let env = Env::default();
+    let topics = [to_sc_val(&env, &symbol_short!("created")).unwrap()];
+    let data = to_sc_val(&env, &5u32).unwrap();
+    assert!(decode_escrow_event(&env, &topics, &data).is_err());
+
+    let topics = [to_sc_val(&env, &symbol_short!("other")).unwrap()];
+    assert_eq!(decode_escrow_event(&env, &topics, &data).unwrap(), None);
+}
+
+#[test]
+fn test_decode_refunded_event_without_status() {
+    #[contracttype]
+    struct LegacyRefundedEvent {
+        order_hash: BytesN<32>,
+        sender: Address,
+        amount: i128,
+        merkle_root: BytesN<32>,
+        parts: u32,
+        legs: Vec<TokenAmount>,
+    }
+
+    let env = Env::default();
+    let topics = [to_sc_val(&env, &symbol_short!("refunded")).unwrap()];
+    let refunded = |parts: u32| {
+        let data = to_sc_val(
+            &env,
+            &LegacyRefundedEvent {
+                order_hash: BytesN::from_array(&env, &[3u8; 32]),
+                sender: Address::generate(&env),
+                amount: 2_000,
+                merkle_root: BytesN::from_array(&env, &[0u8; 32]),
+                parts,
+                legs: Vec::new(&env),
+            },
+        )
+        .unwrap();
+        match decode_escrow_event(&env, &topics, &data).unwrap() {
+            Some(EscrowEvent::Refunded(event)) => {
+                assert_eq!((event.amount, event.parts), (2_000, parts));
+                event.status
+            }
+            other => panic!("expected a refund, got {other:?}"),
+        }
+    };
+    assert_eq!(refunded(0), EscrowStatus::Cancelled);
+    assert_eq!(refunded(1), EscrowStatus::PublicCancelled);
+}
+
+#[test]
+fn test_decode_relayer_event() {
+    let env = Env::default();
+    let event = SecretSharedEvent {
+        order_hash: BytesN::from_array(&env, &[3u8; 32]),
+        part_idx: 2,
+    };
+    let topics = [to_sc_val(&env, &symbol_short!("secret")).unwrap()];
+    let data = to_sc_val(&env, &event).unwrap();
+    assert_eq!(
+        decode_relayer_event(&env, &topics, &data).unwrap(),
+        Some(RelayerEvent::SecretShared(event))
+    );
+}
+
+#[test]
+fn test_relayer_calls() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract = env.register(SecretRelayerContract, ());
+    let calls = RelayerCalls::new(&env, &contract);
+    let admin = Address::generate(&env);
+    let relayer = Address::generate(&env);
+
+    let _: () = invoke(&env, &contract, &calls.initialize(&admin).unwrap());
+    let _: () = invoke(
+        &env,
+        &contract,
+        &calls.add_relayer(&admin, &relayer).unwrap(),
+    );
+    let authorized: bool = invoke(
+        &env,
+        &contract,
+        &calls.is_authorized_relayer(&admin, &relayer).unwrap(),
+    );
+    assert!(authorized);
+    let relayers: Vec<Address> = invoke(
+        &env,
+        &contract,
+        &calls.get_authorized_relayers(&admin).unwrap(),
+    );
+    assert_eq!(relayers, vec![&env, relayer]);
+}
+
+#[test]
+fn test_token_calls_and_events() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let contract = env.register(
+        Token,
+        (
+            &admin,
+            7u32,
+            String::from_str(&env, "Setu"),
+            String::from_str(&env, "SETU"),
+            None::<i128>,
+            None::<FaucetConfig>,
+        ),
+    );
+    let calls = TokenCalls::new(&env, &contract);
+    let user = Address::generate(&env);
+
+    let _: () = invoke(&env, &contract, &calls.mint(&user, 500).unwrap());
+    let events = xdr_events(&env);
+    let (topics, data) = &events[0];
+    assert_eq!(
+        decode_token_event(&env, topics, data).unwrap(),
+        Some(TokenEvent::Mint {
+            admin: admin.clone(),
+            to: user.clone(),
+            amount: 500,
+        })
+    );
+
+    let _: () = invoke(&env, &contract, &calls.set_minter(&user, 50).unwrap());
+    let (topics, data) = &xdr_events(&env)[0];
+    assert_eq!(
+        decode_token_event(&env, topics, data).unwrap(),
+        Some(TokenEvent::SetMinter {
+            admin,
+            minter: user.clone(),
+            daily_quota: 50,
+        })
+    );
+
+    let balance: i128 = invoke(&env, &contract, &calls.balance(&user).unwrap());
+    assert_eq!(balance, 500);
+}
+
+#[test]
+fn test_operation_xdr_roundtrip() {
+    let s = setup();
+    let calls = EscrowCalls::new(&s.env, &s.escrow);
+    let args = escrow_args(&s);
+    let invocation = calls.create_escrow(&args).unwrap();
+    let entry = signed_entry(
+        &s.env,
+        &s.maker,
+        &s.maker_key,
+        1,
+        calls.create_escrow_auth(&args).unwrap(),
+    );
+
+    let encoded = invocation.operation_xdr_base64(std::vec![entry]).unwrap();
+    let operation = Operation::from_xdr_base64(encoded, Limits::none()).unwrap();
+    assert_eq!(
+        operation,
+        invocation.operation(operation_auth(&operation)).unwrap()
+    );
+
+    let decoded: CreateEscrowArgs = from_sc_val(&s.env, &invocation.args[0]).unwrap();
+    assert_eq!(decoded, args);
+}
+
+fn operation_auth(
+    operation: &Operation,
+) -> std::vec::Vec<soroban_sdk::xdr::SorobanAuthorizationEntry> {
+    match &operation.body {
+        soroban_sdk::xdr::OperationBody::InvokeHostFunction(op) => op.auth.to_vec(),
+        _ => panic!("not an invoke host function operation"),
+    }
+}
@@ -0,0 +1,201 @@
+use soroban_sdk::{Address, BytesN, Env, IntoVal, String, Val, Vec};
+use tokens::ExtendedMetadata;
+
+use crate::{Error, Invocation};
+
+/// Invocations of a tokens contract deployment. The token interface
+/// functions also work against any SEP-41 token, such as a Stellar Asset
+/// Contract.
+#[derive(Clone)]
+pub struct TokenCalls {
+    env: Env,
+    contract: Address,
+}
+
+impl TokenCalls {
+    pub fn new(env: &Env, contract: &Address) -> Self {
+        Self {
+            env: env.clone(),
+            contract: contract.clone(),
+        }
+    }
+
+    fn call(&self, function: &str, args: Vec<Val>) -> Result<Invocation, Error> {
+        Invocation::new(&self.env, &self.contract, function, args)
+    }
+
+    pub fn allowance(&self, from: &Address, spender: &Address) -> Result<Invocation, Error> {
+        self.call("allowance", (from, spender).into_val(&self.env))
+    }
+
+    pub fn approve(
+        &self,
+        from: &Address,
+        spender: &Address,
+        amount: i128,
+        expiration_ledger: u32,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "approve",
+            (from, spender, amount, expiration_ledger).into_val(&self.env),
+        )
+    }
+
+    pub fn balance(&self, id: &Address) -> Result<Invocation, Error> {
+        self.call("balance", (id,).into_val(&self.env))
+    }
+
+    pub fn transfer(
+        &self,
+        from: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> Result<Invocation, Error> {
+        self.call("transfer", (from, to, amount).into_val(&self.env))
+    }
+
+    pub fn transfer_from(
+        &self,
+        spender: &Address,
+        from: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "transfer_from",
+            (spender, from, to, amount).into_val(&self.env),
+        )
+    }
+
+    pub fn burn(&self, from: &Address, amount: i128) -> Result<Invocation, Error> {
+        self.call("burn", (from, amount).into_val(&self.env))
+    }
+
+    pub fn burn_from(
+        &self,
+        spender: &Address,
+        from: &Address,
+        amount: i128,
+    ) -> Result<Invocation, Error> {
+        self.call("burn_from", (spender, from, amount).into_val(&self.env))
+    }
+
+    pub fn decimals(&self) -> Result<Invocation, Error> {
+        self.call("decimals", Vec::new(&self.env))
+    }
+
+    pub fn name(&self) -> Result<Invocation, Error> {
+        self.call("name", Vec::new(&self.env))
+    }
+
+    pub fn symbol(&self) -> Result<Invocation, Error> {
+        self.call("symbol", Vec::new(&self.env))
+    }
+
+    pub fn mint(&self, to: &Address, amount: i128) -> Result<Invocation, Error> {
+        self.call("mint", (to, amount).into_val(&self.env))
+    }
+
+    pub fn minter_mint(
+        &self,
+        minter: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> Result<Invocation, Error> {
+        self.call("minter_mint", (minter, to, amount).into_val(&self.env))
+    }
+
+    pub fn set_minter(&self, minter: &Address, daily_quota: i128) -> Result<Invocation, Error> {
+        self.call("set_minter", (minter, daily_quota).into_val(&self.env))
+    }
+
+    pub fn remove_minter(&self, minter: &Address) -> Result<Invocation, Error> {
+        self.call("remove_minter", (minter,).into_val(&self.env))
+    }
+
+    pub fn minter(&self, id: &Address) -> Result<Invocation, Error> {
+        self.call("minter", (id,).into_val(&self.env))
+    }
+
+    pub fn remaining_quota(&self, id: &Address) -> Result<Invocation, Error> {
+        self.call("remaining_quota", (id,).into_val(&self.env))
+    }
+
+    pub fn faucet(&self, to: &Address) -> Result<Invocation, Error> {
+        self.call("faucet", (to,).into_val(&self.env))
+    }
+
+    pub fn faucet_config(&self) -> Result<Invocation, Error> {
+        self.call("faucet_config", Vec::new(&self.env))
+    }
+
+    pub fn set_admin(&self, new_admin: &Address) -> Result<Invocation, Error> {
+        self.call("set_admin", (new_admin,).into_val(&self.env))
+    }
+
+    pub fn set_authorized(&self, id: &Address, authorize: bool) -> Result<Invocation, Error> {
+        self.call("set_authorized", (id, authorize).into_val(&self.env))
+    }
+
+    pub fn clawback(&self, from: &Address, amount: i128) -> Result<Invocation, Error> {
+        self.call("clawback", (from, amount).into_val(&self.env))
+    }
+
+    pub fn permit(
+        &self,
+        owner: &Address,
+        spender: &Address,
+        amount: i128,
+        expiration_ledger: u32,
+        nonce: u64,
+        signature: &BytesN<64>,
+    ) -> Result<Invocation, Error> {
+        self.call(
+            "permit",
+            (owner, spender, amount, expiration_ledger, nonce, signature).into_val(&self.env),
+        )
+    }
+
+    pub fn set_permit_enabled(&self, owner: &Address, enabled: bool) -> Result<Invocation, Error> {
+        self.call("set_permit_enabled", (owner, enabled).into_val(&self.env))
+    }
+
+    pub fn permit_enabled(&self, owner: &Address) -> Result<Invocation, Error> {
+        self.call("permit_enabled", (owner,).into_val(&self.env))
+    }
+
+    pub fn nonce(&self, owner: &Address) -> Result<Invocation, Error> {
+        self.call("nonce", (owner,).into_val(&self.env))
+    }
+
+    pub fn set_name(&self, name: &String) -> Result<Invocation, Error> {
+        self.call("set_name", (name.clone(),).into_val(&self.env))
+    }
+
+    pub fn set_symbol(&self, symbol: &String) -> Result<Invocation, Error> {
+        self.call("set_symbol", (symbol.clone(),).into_val(&self.env))
+    }
+
+    pub fn set_extended_metadata(&self, metadata: &ExtendedMetadata) -> Result<Invocation, Error> {
+        self.call(
+            "set_extended_metadata",
+            (metadata.clone(),).into_val(&self.env),
+        )
+    }
+
+    pub fn metadata(&self) -> Result<Invocation, Error> {
+        self.call("metadata", Vec::new(&self.env))
+    }
+
+    pub fn total_supply(&self) -> Result<Invocation, Error> {
+        self.call("total_supply", Vec::new(&self.env))
+    }
+
+    pub fn max_supply(&self) -> Result<Invocation, Error> {
+        self.call("max_supply", Vec::new(&self.env))
+    }
+
+    pub fn authorized(&self, id: &Address) -> Result<Invocation, Error> {
+        self.call("authorized", (id,).into_val(&self.env))
+    }
+}
@@ -0,0 +1,74 @@
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use soroban_sdk::xdr::{
+    DecoratedSignature, Hash, Limits, Memo, MuxedAccount, Preconditions, SequenceNumber, Signature,
+    SignatureHint, SorobanAuthorizationEntry, SorobanTransactionData, Transaction,
+    TransactionEnvelope, TransactionExt, TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction, TransactionV1Envelope, Uint256, WriteXdr,
+};
+
+use crate::{Error, Invocation};
+
+/// Network id for `passphrase`, e.g. `"Test SDF Network ; September 2015"`.
+pub fn network_id(passphrase: &str) -> [u8; 32] {
+    Sha256::digest(passphrase.as_bytes()).into()
+}
+
+impl Invocation {
+    /// Transaction with this call as its only operation, sent from the
+    /// account of `source`.
+    ///
+    /// `soroban_data` carries the footprint and resource fee from a
+    /// simulation; its resource fee is added on top of `fee`. Without it the
+    /// transaction still has to be assembled through a simulation before it
+    /// can be submitted.
+    pub fn transaction(
+        &self,
+        source: &[u8; 32],
+        sequence: i64,
+        fee: u32,
+        auth: Vec<SorobanAuthorizationEntry>,
+        soroban_data: Option<SorobanTransactionData>,
+    ) -> Result<Transaction, Error> {
+        let resource_fee = soroban_data.as_ref().map_or(0, |data| data.resource_fee);
+        let fee = u32::try_from(resource_fee)
+            .ok()
+            .and_then(|resource_fee| fee.checked_add(resource_fee))
+            .ok_or(Error::Conversion)?;
+        Ok(Transaction {
+            source_account: MuxedAccount::Ed25519(Uint256(*source)),
+            fee,
+            seq_num: SequenceNumber(sequence),
+            cond: Preconditions::None,
+            memo: Memo::None,
+            operations: vec![self.operation(auth)?].try_into()?,
+            ext: match soroban_data {
+                Some(data) => TransactionExt::V1(data),
+                None => TransactionExt::V0,
+            },
+        })
+    }
+}
+
+/// Sign `tx` for the network with id `network_id`.
+pub fn sign_transaction(
+    tx: Transaction,
+    network_id: &[u8; 32],
+    key: &SigningKey,
+) -> Result<TransactionEnvelope, Error> {
+    let payload = TransactionSignaturePayload {
+        network_id: Hash(*network_id),
+        tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(tx.clone()),
+    };
+    let hash: [u8; 32] = Sha256::digest(payload.to_xdr(Limits::none())?).into();
+
+    let public_key = key.verifying_key().to_bytes();
+    let signature = DecoratedSignature {
+        hint: SignatureHint(public_key[28..].try_into().unwrap()),
+        signature: Signature(key.sign(&hash).to_bytes().try_into()?),
+    };
+    Ok(TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx,
+        signatures: vec![signature].try_into()?,
+    }))
+}
@@ -0,0 +1,47 @@
+use core::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// An event's topics or data could not be decoded
+    Decode(setu_client::Error),
+    Json(serde_json::Error),
+    Postgres(postgres::Error),
+    Rpc(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Decode(e) => write!(f, "decode error: {e}"),
+            Error::Json(e) => write!(f, "json error: {e}"),
+            Error::Postgres(e) => write!(f, "postgres error: {e}"),
+            Error::Rpc(e) => write!(f, "rpc error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<setu_client::Error> for Error {
+    fn from(e: setu_client::Error) -> Self {
+        Error::Decode(e)
+    }
+}
+
+impl From<soroban_sdk::xdr::Error> for Error {
+    fn from(e: soroban_sdk::xdr::Error) -> Self {
+        Error::Decode(e.into())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<postgres::Error> for Error {
+    fn from(e: postgres::Error) -> Self {
+        Error::Postgres(e)
+    }
+}
@@ -0,0 +1,28 @@
+//! Indexes what happens to escrows on Stellar into the Postgres database
+//! the maker CLI writes orders to.
+//!
+//! Events come as `getEvents` RPC pages, fetched with [`RpcClient`] or read
+//! from recorded JSON. [`Decoder`] turns the `created`, `claimed`,
+//! `refunded` and `safety` events of `EscrowContract` and the `secret`
+//! events of `SecretRelayerContract` into [`Record`]s, and [`PgStore`]
+//! upserts them together with the cursor of the page.
+
+mod error;
+mod record;
+mod rpc;
+mod store;
+mod test;
+
+pub use crate::error::Error;
+pub use crate::record::{Decoder, IndexedEvent, Record};
+pub use crate::rpc::{EventPage, RpcClient, RpcEvent, Start};
+pub use crate::store::PgStore;
+
+/// Decode the events of `page` that the database keeps.
+pub fn decode_page(decoder: &Decoder, page: &EventPage) -> Result<Vec<IndexedEvent>, Error> {
+    let mut events = Vec::new();
+    for event in &page.events {
+        events.extend(decoder.decode(event)?);
+    }
+    Ok(events)
+}
@@ -0,0 +1,109 @@
+//! `setu-indexer` reads `EscrowContract` and `SecretRelayerContract` events
+//! and writes them to the database in `DATABASE_URL`.
+//!
+//! Given files, each holds one recorded `getEvents` response. Otherwise the
+//! events are fetched from `--rpc-url`, continuing from the stored cursor or
+//! `--start-ledger`, until the RPC has no newer events, or forever with
+//! `--follow`.
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use setu_indexer::{decode_page, Decoder, EventPage, PgStore, RpcClient, Start};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "setu-indexer",
+    about = "Index escrow and relayer events into Postgres"
+)]
+struct Cli {
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: String,
+    /// Address of an EscrowContract to index
+    #[arg(long = "escrow", env = "SETU_ESCROW_CONTRACT", required = true)]
+    escrow_contracts: Vec<String>,
+    /// Address of a SecretRelayerContract to index
+    #[arg(long = "relayer")]
+    relayer_contracts: Vec<String>,
+    /// Name the cursor is stored under
+    #[arg(long, default_value = "stellar")]
+    stream: String,
+    /// Stellar RPC server to fetch events from when no files are given
+    #[arg(long, required_unless_present = "files")]
+    rpc_url: Option<String>,
+    /// First ledger to fetch when no cursor is stored yet
+    #[arg(long)]
+    start_ledger: Option<u32>,
+    /// Events per RPC request
+    #[arg(long, default_value_t = 100)]
+    limit: u32,
+    /// Keep polling once caught up
+    #[arg(long)]
+    follow: bool,
+    /// Seconds between polls once caught up
+    #[arg(long, default_value_t = 5)]
+    poll_interval: u64,
+    /// Recorded getEvents responses, applied in order
+    files: Vec<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let decoder = Decoder::new(&cli.escrow_contracts, &cli.relayer_contracts);
+    let mut store = PgStore::connect(&cli.database_url).context("connecting to the database")?;
+    let stored_cursor = store.cursor(&cli.stream)?;
+
+    let mut indexed = 0;
+    let mut apply = |page: &EventPage| -> Result<()> {
+        let events = decode_page(&decoder, page)?;
+        store.apply(&cli.stream, &events, page.next_cursor())?;
+        indexed += events.len();
+        Ok(())
+    };
+
+    if !cli.files.is_empty() {
+        for file in &cli.files {
+            let json = std::fs::read_to_string(file)
+                .with_context(|| format!("reading {}", file.display()))?;
+            apply(&EventPage::from_json(&json)?)?;
+        }
+    } else {
+        let rpc_url = cli
+            .rpc_url
+            .as_deref()
+            .context("--rpc-url is required without files")?;
+        let rpc = RpcClient::new(rpc_url);
+        let mut start = match stored_cursor {
+            Some(cursor) => Start::Cursor(cursor),
+            None => Start::Ledger(
+                cli.start_ledger
+                    .ok_or_else(|| anyhow!("--start-ledger is required without a stored cursor"))?,
+            ),
+        };
+        let contracts: Vec<String> = cli
+            .escrow_contracts
+            .iter()
+            .chain(&cli.relayer_contracts)
+            .cloned()
+            .collect();
+        loop {
+            let page = rpc.get_events(&start, &contracts, cli.limit)?;
+            apply(&page)?;
+            if let Some(cursor) = page.next_cursor() {
+                start = Start::Cursor(cursor.to_string());
+            }
+            if page.events.is_empty() {
+                if !cli.follow {
+                    break;
+                }
+                thread::sleep(Duration::from_secs(cli.poll_interval));
+            }
+        }
+    }
+
+    println!("indexed {indexed} events");
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::collections::HashSet;
+
+use escrow::{EscrowStatus, HashAlgorithm};
+use setu_client::{decode_escrow_event, decode_relayer_event, EscrowEvent, RelayerEvent};
+use soroban_sdk::xdr::{Limits, ReadXdr, ScAddress, ScVal};
+use soroban_sdk::{Address, BytesN, Env};
+
+use crate::{Error, RpcEvent};
+
+/// An indexed event with where it was published.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexedEvent {
+    pub id: String,
+    pub ledger: u32,
+    pub tx_hash: String,
+    pub contract_id: String,
+    pub record: Record,
+}
+
+/// Event fields the database keeps, with addresses as strkeys and hashes as
+/// `0x`-prefixed hex like the `orders` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Record {
+    Created {
+        order_hash: String,
+        maker: String,
+        taker: String,
+        receiver: String,
+        token: String,
+        amount: i128,
+        hashlock: String,
+        hash_algorithm: HashAlgorithm,
+        is_src: bool,
+        parts: u32,
+    },
+    /// One part, or all, of an escrow released to its recipient
+    Claimed {
+        order_hash: String,
+        preimage: String,
+        recipient: String,
+        amount: i128,
+    },
+    /// The rest of an escrow returned, by the taker's cancel or a public one
+    Refunded {
+        order_hash: String,
+        sender: String,
+        amount: i128,
+        status: EscrowStatus,
+    },
+    SafetyDepositClaimed {
+        order_hash: String,
+        executor: String,
+        amount: i128,
+    },
+    SecretShared {
+        order_hash: String,
+        part_idx: u32,
+    },
+}
+
+impl Record {
+    pub fn order_hash(&self) -> &str {
+        match self {
+            Record::Created { order_hash, .. }
+            | Record::Claimed { order_hash, .. }
+            | Record::Refunded { order_hash, .. }
+            | Record::SafetyDepositClaimed { order_hash, .. }
+            | Record::SecretShared { order_hash, .. } => order_hash,
+        }
+    }
+}
+
+pub(crate) fn hex(bytes: &BytesN<32>) -> String {
+    format!("0x{}", hex::encode(bytes.to_array()))
+}
+
+fn strkey(address: &Address) -> String {
+    ScAddress::from(address).to_string()
+}
+
+/// Decodes the events of the escrow and relayer contracts being indexed.
+pub struct Decoder {
+    escrow_contracts: HashSet<String>,
+    relayer_contracts: HashSet<String>,
+}
+
+impl Decoder {
+    pub fn new(escrow_contracts: &[String], relayer_contracts: &[String]) -> Self {
+        Self {
+            escrow_contracts: escrow_contracts.iter().cloned().collect(),
+            relayer_contracts: relayer_contracts.iter().cloned().collect(),
+        }
+    }
+
+    /// Decode `event` into a record.
+    ///
+    /// Returns `Ok(None)` for events of other contracts, events rolled back
+    /// with a failed call and events the database does not keep, such as
+    /// upgrades.
+    pub fn decode(&self, event: &RpcEvent) -> Result<Option<IndexedEvent>, Error> {
+        if event.kind != "contract" || !event.in_successful_contract_call {
+            return Ok(None);
+        }
+        let is_escrow = self.escrow_contracts.contains(&event.contract_id);
+        let is_relayer = self.relayer_contracts.contains(&event.contract_id);
+        if !is_escrow && !is_relayer {
+            return Ok(None);
+        }
+
+        let topics = event
+            .topic
+            .iter()
+            .map(|topic| ScVal::from_xdr_base64(topic, Limits::none()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = ScVal::from_xdr_base64(&event.value, Limits::none())?;
+
+        // A fresh host per event, so its budget and objects don't build up
+        // over the life of the indexer
+        let env = Env::default();
+        let record = if is_escrow {
+            decode_escrow_event(&env, &topics, &data)?.and_then(escrow_record)
+        } else {
+            decode_relayer_event(&env, &topics, &data)?.map(relayer_record)
+        };
+        Ok(record.map(|record| IndexedEvent {
+            id: event.id.clone(),
+            ledger: event.ledger,
+            tx_hash: event.tx_hash.clone(),
+            contract_id: event.contract_id.clone(),
+            record,
+        }))
+    }
+}
+
+fn escrow_record(event: EscrowEvent) -> Option<Record> {
+    let record = match event {
+        EscrowEvent::Created(e) => Record::Created {
+            order_hash: hex(&e.order_hash),
+            maker: strkey(&e.maker),
+            taker: strkey(&e.taker),
+            receiver: strkey(&e.receiver),
+            token: strkey(&e.token),
+            amount: e.amount,
+            hashlock: hex(&e.hashlock),
+            hash_algorithm: e.hash_algorithm,
+            is_src: e.is_src,
+            parts: e.parts,
+        },
+        EscrowEvent::Claimed(e) => Record::Claimed {
+            order_hash: hex(&e.order_hash),
+            preimage: hex(&e.preimage),
+            recipient: strkey(&e.recipient),
+            amount: e.amount,
+        },
+        EscrowEvent::Refunded(e) => Record::Refunded {
+            order_hash: hex(&e.order_hash),
+            sender: strkey(&e.sender),
+            amount: e.amount,
+            status: e.status,
+        },
+        EscrowEvent::SafetyDepositClaimed(e) => Record::SafetyDepositClaimed {
+            order_hash: hex(&e.order_hash),
+            executor: strkey(&e.executor),
+            amount: e.amount,
+        },
+        _ => return None,
+    };
+    Some(record)
+}
+
+fn relayer_record(event: RelayerEvent) -> Record {
+    match event {
+        RelayerEvent::SecretShared(e) => Record::SecretShared {
+            order_hash: hex(&e.order_hash),
+            part_idx: e.part_idx,
+        },
+    }
+}
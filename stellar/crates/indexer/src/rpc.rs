@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::Error;
+
+/// One event as returned by the `getEvents` RPC method, with the topics and
+/// value as base64 `ScVal` XDR.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcEvent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub ledger: u32,
+    pub ledger_closed_at: String,
+    pub contract_id: String,
+    /// Ordered by ledger, transaction and event, also usable as a cursor
+    pub id: String,
+    pub topic: Vec<String>,
+    pub value: String,
+    #[serde(default = "successful")]
+    pub in_successful_contract_call: bool,
+    pub tx_hash: String,
+}
+
+fn successful() -> bool {
+    true
+}
+
+/// Result of one `getEvents` call.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventPage {
+    pub events: Vec<RpcEvent>,
+    #[serde(default)]
+    pub latest_ledger: u32,
+    /// Where the next call continues, past the last ledger scanned
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response {
+    JsonRpc { result: EventPage },
+    Error { error: serde_json::Value },
+    Page(EventPage),
+}
+
+impl EventPage {
+    /// Parse a page from a recorded `getEvents` response, either the full
+    /// JSON-RPC response or only its `result`.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        match serde_json::from_str(json)? {
+            Response::JsonRpc { result } | Response::Page(result) => Ok(result),
+            Response::Error { error } => Err(Error::Rpc(error.to_string())),
+        }
+    }
+
+    /// Cursor to store once every event of the page is applied.
+    pub fn next_cursor(&self) -> Option<&str> {
+        self.cursor
+            .as_deref()
+            .or(self.events.last().map(|event| event.id.as_str()))
+    }
+}
+
+/// Where a `getEvents` call starts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Start {
+    Ledger(u32),
+    Cursor(String),
+}
+
+/// Minimal client for the `getEvents` method of a Stellar RPC server.
+pub struct RpcClient {
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    pub fn get_events(
+        &self,
+        start: &Start,
+        contract_ids: &[String],
+        limit: u32,
+    ) -> Result<EventPage, Error> {
+        let mut params = json!({
+            "filters": [{ "type": "contract", "contractIds": contract_ids }],
+            "pagination": { "limit": limit },
+        });
+        match start {
+            Start::Ledger(ledger) => params["startLedger"] = json!(ledger),
+            Start::Cursor(cursor) => params["pagination"]["cursor"] = json!(cursor),
+        }
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getEvents",
+            "params": params,
+        });
+
+        let response = ureq::post(&self.url)
+            .send_json(request)
+            .map_err(|e| Error::Rpc(e.to_string()))?
+            .into_string()
+            .map_err(|e| Error::Rpc(e.to_string()))?;
+        EventPage::from_json(&response)
+    }
+}
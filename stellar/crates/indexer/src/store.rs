@@ -0,0 +1,239 @@
+use escrow::{EscrowStatus, HashAlgorithm};
+use postgres::{Client, NoTls, Transaction};
+
+use crate::{Error, IndexedEvent, Record};
+
+/// Writes records to the tables of the `main/prisma` schema.
+///
+/// Escrows, claims and shared secrets go to the `stellar_*` tables. An escrow
+/// is `PARTIALLY_FILLED` until its claims add up to the amount, and a public
+/// cancellation is kept apart from the taker's. Orders the maker CLI stored
+/// in `orders` are kept in step: each claim is added to `order_fills` and the
+/// status follows the escrow once it is withdrawn or cancelled.
+pub struct PgStore {
+    client: Client,
+}
+
+impl PgStore {
+    pub fn connect(url: &str) -> Result<Self, Error> {
+        Ok(Self::new(Client::connect(url, NoTls)?))
+    }
+
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&mut self) -> &mut Client {
+        &mut self.client
+    }
+
+    /// Cursor stored by the last `apply` for the stream `name`.
+    pub fn cursor(&mut self, name: &str) -> Result<Option<String>, Error> {
+        let row = self.client.query_opt(
+            r#"SELECT "cursor" FROM "indexer_cursors" WHERE "name" = $1"#,
+            &[&name],
+        )?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    /// Apply the records of one page and store `cursor` in one transaction.
+    ///
+    /// Events at or before the stored cursor were applied already and are
+    /// skipped, so a page can be replayed after a crash.
+    pub fn apply(
+        &mut self,
+        name: &str,
+        events: &[IndexedEvent],
+        cursor: Option<&str>,
+    ) -> Result<(), Error> {
+        let applied = self.cursor(name)?;
+        let mut tx = self.client.transaction()?;
+        for event in events {
+            if applied
+                .as_deref()
+                .is_some_and(|applied| *event.id <= *applied)
+            {
+                continue;
+            }
+            apply_event(&mut tx, event)?;
+        }
+        if let Some(cursor) = cursor {
+            tx.execute(
+                r#"INSERT INTO "indexer_cursors" ("name", "cursor", "updatedAt")
+                   VALUES ($1, $2, CURRENT_TIMESTAMP)
+                   ON CONFLICT ("name") DO UPDATE
+                   SET "cursor" = EXCLUDED."cursor", "updatedAt" = EXCLUDED."updatedAt""#,
+                &[&name, &cursor],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn hash_algorithm(algorithm: HashAlgorithm) -> &'static str {
+    match algorithm {
+        HashAlgorithm::Keccak256 => "keccak256",
+        HashAlgorithm::Sha256 => "sha256",
+    }
+}
+
+/// Label of `status` in the `EscrowStatus` enum of the schema.
+fn escrow_status(status: EscrowStatus) -> &'static str {
+    match status {
+        EscrowStatus::Active => "ACTIVE",
+        EscrowStatus::PartiallyFilled => "PARTIALLY_FILLED",
+        EscrowStatus::Withdrawn => "WITHDRAWN",
+        EscrowStatus::Cancelled => "CANCELLED",
+        EscrowStatus::PublicCancelled => "PUBLIC_CANCELLED",
+    }
+}
+
+fn apply_event(tx: &mut Transaction, event: &IndexedEvent) -> Result<(), Error> {
+    let ledger = event.ledger as i32;
+    match &event.record {
+        Record::Created {
+            order_hash,
+            maker,
+            taker,
+            receiver,
+            token,
+            amount,
+            hashlock,
+            hash_algorithm: algorithm,
+            is_src,
+            parts,
+        } => {
+            tx.execute(
+                r#"INSERT INTO "stellar_escrows" ("orderHash", "contractId", "maker", "taker",
+                       "receiver", "token", "amount", "hashlock", "hashAlgorithm", "isSrc",
+                       "parts", "createdLedger", "updatedLedger", "updatedAt")
+                   VALUES ($1, $2, $3, $4, $5, $6, $7::text::numeric, $8, $9, $10, $11, $12,
+                       $12, CURRENT_TIMESTAMP)
+                   ON CONFLICT ("orderHash") DO NOTHING"#,
+                &[
+                    order_hash,
+                    &event.contract_id,
+                    maker,
+                    taker,
+                    receiver,
+                    token,
+                    &amount.to_string(),
+                    hashlock,
+                    &hash_algorithm(*algorithm),
+                    is_src,
+                    &(*parts as i32),
+                    &ledger,
+                ],
+            )?;
+        }
+        Record::Claimed {
+            order_hash,
+            preimage,
+            recipient,
+            amount,
+        } => {
+            let amount = amount.to_string();
+            let inserted = tx.execute(
+                r#"INSERT INTO "stellar_escrow_fills" ("eventId", "orderHash", "recipient",
+                       "amount", "preimage", "txHash", "ledger")
+                   VALUES ($1, $2, $3, $4::text::numeric, $5, $6, $7)
+                   ON CONFLICT ("eventId") DO NOTHING"#,
+                &[
+                    &event.id,
+                    order_hash,
+                    recipient,
+                    &amount,
+                    preimage,
+                    &event.tx_hash,
+                    &ledger,
+                ],
+            )?;
+            // Released amounts add up, so a claim must only count once
+            if inserted == 0 {
+                return Ok(());
+            }
+            tx.execute(
+                r#"UPDATE "stellar_escrows"
+                   SET "releasedAmount" = "releasedAmount" + $2::text::numeric,
+                       "status" = CASE WHEN "releasedAmount" + $2::text::numeric >= "amount"
+                           THEN 'WITHDRAWN'::"EscrowStatus"
+                           ELSE 'PARTIALLY_FILLED'::"EscrowStatus" END,
+                       "updatedLedger" = $3, "updatedAt" = CURRENT_TIMESTAMP
+                   WHERE "orderHash" = $1"#,
+                &[order_hash, &amount, &ledger],
+            )?;
+            tx.execute(
+                r#"INSERT INTO "order_fills" ("orderId", "filledBy", "filledAmount", "txHash",
+                       "blockNumber")
+                   SELECT "id", $2, $3::text::numeric, $4, $5 FROM "orders"
+                   WHERE "orderHash" = $1"#,
+                &[order_hash, recipient, &amount, &event.tx_hash, &ledger],
+            )?;
+            tx.execute(
+                r#"UPDATE "orders" SET "status" = 'FILLED', "updatedAt" = CURRENT_TIMESTAMP
+                   WHERE "orderHash" = $1 AND EXISTS (
+                       SELECT 1 FROM "stellar_escrows"
+                       WHERE "orderHash" = $1 AND "status" = 'WITHDRAWN')"#,
+                &[order_hash],
+            )?;
+        }
+        Record::Refunded {
+            order_hash,
+            amount,
+            status,
+            ..
+        } => {
+            tx.execute(
+                r#"UPDATE "stellar_escrows"
+                   SET "refundedAmount" = $2::text::numeric,
+                       "status" = $3::text::"EscrowStatus",
+                       "updatedLedger" = $4, "updatedAt" = CURRENT_TIMESTAMP
+                   WHERE "orderHash" = $1"#,
+                &[
+                    order_hash,
+                    &amount.to_string(),
+                    &escrow_status(*status),
+                    &ledger,
+                ],
+            )?;
+            tx.execute(
+                r#"UPDATE "orders" SET "status" = 'CANCELLED', "updatedAt" = CURRENT_TIMESTAMP
+                   WHERE "orderHash" = $1 AND "status" = 'OPEN'"#,
+                &[order_hash],
+            )?;
+        }
+        Record::SafetyDepositClaimed {
+            order_hash,
+            executor,
+            ..
+        } => {
+            tx.execute(
+                r#"UPDATE "stellar_escrows"
+                   SET "safetyDepositClaimedBy" = $2, "updatedLedger" = $3,
+                       "updatedAt" = CURRENT_TIMESTAMP
+                   WHERE "orderHash" = $1"#,
+                &[order_hash, executor, &ledger],
+            )?;
+        }
+        Record::SecretShared {
+            order_hash,
+            part_idx,
+        } => {
+            tx.execute(
+                r#"INSERT INTO "stellar_secrets" ("eventId", "orderHash", "partIdx", "txHash",
+                       "ledger")
+                   VALUES ($1, $2, $3, $4, $5)
+                   ON CONFLICT ("eventId") DO NOTHING"#,
+                &[
+                    &event.id,
+                    order_hash,
+                    &(*part_idx as i32),
+                    &event.tx_hash,
+                    &ledger,
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
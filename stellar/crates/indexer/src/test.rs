@@ -0,0 +1,568 @@
+#![cfg(test)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use escrow::{
+    AllowedResolvers, CreateEscrowArgs, EscrowContract, EscrowContractClient, EscrowStatus,
+    HashAlgorithm,
+};
+use postgres::{Client, NoTls};
+use relayer::SecretSharedEvent;
+use setu_client::to_sc_val;
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::token::StellarAssetClient;
+use soroban_sdk::xdr::{Limits, ScAddress, ScVal, WriteXdr};
+use soroban_sdk::{symbol_short, Address, BytesN, Env, TryFromVal};
+
+use crate::{
+    decode_page, Decoder, EventPage, IndexedEvent, PgStore, Record, RpcClient, RpcEvent, Start,
+};
+
+const RESCUE_DELAY: u32 = 3600;
+const FIXTURE: &str = include_str!("../fixtures/get_events.json");
+/// Database the Postgres tests run against, skipped when unset. The tests
+/// reset its `public` schema.
+const DATABASE_URL: &str = "SETU_INDEXER_TEST_DATABASE_URL";
+
+fn strkey(address: &Address) -> String {
+    ScAddress::from(address).to_string()
+}
+
+fn xdr(value: &ScVal) -> String {
+    value.to_xdr_base64(Limits::none()).unwrap()
+}
+
+/// Records events the way the RPC returns them, one transaction per call.
+struct Recorder {
+    env: Env,
+    events: Vec<RpcEvent>,
+    transactions: u64,
+}
+
+impl Recorder {
+    fn rpc_event(&mut self, contract: &Address, topics: Vec<ScVal>, value: ScVal) -> RpcEvent {
+        let ledger = self.env.ledger().sequence();
+        RpcEvent {
+            kind: "contract".to_string(),
+            ledger,
+            ledger_closed_at: "2026-10-18T00:00:00Z".to_string(),
+            contract_id: strkey(contract),
+            id: format!(
+                "{:019}-{:010}",
+                (ledger as u64) << 32 | self.transactions << 12,
+                self.events.len()
+            ),
+            topic: topics.iter().map(xdr).collect(),
+            value: xdr(&value),
+            in_successful_contract_call: true,
+            tx_hash: format!("{:064x}", self.transactions),
+        }
+    }
+
+    /// Record the events of the last invocation in a new ledger.
+    fn record(&mut self) {
+        self.transactions += 1;
+        for (contract, topics, data) in self.env.events().all().iter() {
+            let topics = topics
+                .iter()
+                .map(|topic| ScVal::try_from_val(&self.env, &topic).unwrap())
+                .collect();
+            let value = ScVal::try_from_val(&self.env, &data).unwrap();
+            let event = self.rpc_event(&contract, topics, value);
+            self.events.push(event);
+        }
+        let ledger = self.env.ledger().sequence();
+        self.env.ledger().set_sequence_number(ledger + 1);
+    }
+}
+
+/// Events of a withdrawn escrow, a cancelled one, a publicly cancelled one
+/// and a shared secret, with the contracts that published them.
+fn scenario() -> (EventPage, String, String) {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000_000);
+    env.ledger().set_sequence_number(100);
+
+    let maker = Address::generate(&env);
+    let taker = Address::generate(&env);
+    let relayer = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    StellarAssetClient::new(&env, &token).mint(&maker, &1_000_000);
+    let escrow_id = env.register(EscrowContract, (Address::generate(&env),));
+    let escrow = EscrowContractClient::new(&env, &escrow_id);
+
+    let args = |amount: i128| CreateEscrowArgs {
+        maker: maker.clone(),
+        taker: taker.clone(),
+        receiver: None,
+        token: token.clone(),
+        amount,
+        bundle: soroban_sdk::Vec::new(&env),
+        safety_deposit: 100,
+        hashlock: env
+            .crypto()
+            .keccak256(&BytesN::from_array(&env, &[7u8; 32]).into())
+            .to_bytes(),
+        hash_algorithm: HashAlgorithm::Keccak256,
+        rescue_delay: RESCUE_DELAY,
+        is_src: true,
+        merkle_root: BytesN::from_array(&env, &[0u8; 32]),
+        parts: 1,
+        resolvers: AllowedResolvers::Taker,
+        resolver_proof: soroban_sdk::Vec::new(&env),
+        src_cancellation: None,
+    };
+    let advance = |seconds: u32| {
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + seconds as u64)
+    };
+
+    let mut recorder = Recorder {
+        env: env.clone(),
+        events: Vec::new(),
+        transactions: 0,
+    };
+    let withdrawn = escrow.create_escrow(&args(1_000));
+    recorder.record();
+    let cancelled = escrow.create_escrow(&args(2_000));
+    recorder.record();
+    let public_cancelled = escrow.create_escrow(&args(3_000));
+    recorder.record();
+
+    advance(RESCUE_DELAY + 1);
+    escrow.withdraw(
+        &withdrawn,
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &soroban_sdk::Vec::new(&env),
+        &0,
+    );
+    recorder.record();
+
+    // The relayer contract publishes this when the maker shares the secret
+    recorder.transactions += 1;
+    let secret = recorder.rpc_event(
+        &relayer,
+        std::vec![ScVal::try_from_val(&env, &symbol_short!("secret").to_val()).unwrap()],
+        to_sc_val(
+            &env,
+            &SecretSharedEvent {
+                order_hash: withdrawn,
+                part_idx: 0,
+            },
+        )
+        .unwrap(),
+    );
+    recorder.events.push(secret);
+
+    advance(2 * RESCUE_DELAY);
+    escrow.cancel(&cancelled);
+    recorder.record();
+
+    advance(RESCUE_DELAY);
+    escrow.public_cancel(&public_cancelled, &Address::generate(&env));
+    recorder.record();
+
+    let page = EventPage {
+        latest_ledger: env.ledger().sequence(),
+        cursor: recorder.events.last().map(|event| event.id.clone()),
+        events: recorder.events,
+    };
+    (page, strkey(&escrow_id), strkey(&relayer))
+}
+
+fn fixture() -> (EventPage, Decoder) {
+    let (_, escrow, relayer) = scenario();
+    (
+        EventPage::from_json(FIXTURE).unwrap(),
+        Decoder::new(&[escrow], &[relayer]),
+    )
+}
+
+#[test]
+fn test_fixture_is_current() {
+    let (page, _, _) = scenario();
+    let json = serde_json::to_string_pretty(&serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "result": page,
+    }))
+    .unwrap();
+    if std::env::var_os("UPDATE_FIXTURES").is_some() {
+        std::fs::write(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/get_events.json"),
+            json + "\n",
+        )
+        .unwrap();
+        return;
+    }
+    assert_eq!(
+        EventPage::from_json(FIXTURE).unwrap(),
+        page,
+        "rerun with UPDATE_FIXTURES=1 after changing the contracts' events"
+    );
+}
+
+#[test]
+fn test_decode_fixture() {
+    let (page, decoder) = fixture();
+    let events = decode_page(&decoder, &page).unwrap();
+    let records: Vec<&Record> = events.iter().map(|event| &event.record).collect();
+
+    let [Record::Created {
+        order_hash,
+        amount: 1_000,
+        is_src: true,
+        parts: 1,
+        ..
+    }, Record::Created {
+        order_hash: cancelled,
+        amount: 2_000,
+        ..
+    }, Record::Created {
+        order_hash: public_cancelled,
+        amount: 3_000,
+        ..
+    }, Record::SafetyDepositClaimed { amount: 100, .. }, Record::Claimed {
+        preimage,
+        amount: 1_000,
+        ..
+    }, Record::SecretShared { part_idx: 0, .. }, Record::Refunded {
+        amount: 2_000,
+        status: EscrowStatus::Cancelled,
+        ..
+    }, Record::SafetyDepositClaimed { amount: 100, .. }, Record::Refunded {
+        amount: 3_000,
+        status: EscrowStatus::PublicCancelled,
+        ..
+    }] = records.as_slice()
+    else {
+        panic!("unexpected records {records:?}");
+    };
+    assert_eq!(*preimage, format!("0x{}", hex::encode([7u8; 32])));
+    assert!(order_hash.starts_with("0x") && order_hash.len() == 66);
+    for (record, hash) in records.iter().zip([
+        order_hash,
+        cancelled,
+        public_cancelled,
+        order_hash,
+        order_hash,
+        order_hash,
+        cancelled,
+        public_cancelled,
+        public_cancelled,
+    ]) {
+        assert_eq!(record.order_hash(), hash);
+    }
+
+    // Token transfers are published by the token contract and skipped
+    assert!(page.events.len() > events.len());
+}
+
+#[test]
+fn test_decode_skips_failed_calls_and_other_contracts() {
+    let (page, decoder) = fixture();
+    let created = page
+        .events
+        .iter()
+        .find(|event| decoder.decode(event).unwrap().is_some())
+        .unwrap()
+        .clone();
+
+    let failed = RpcEvent {
+        in_successful_contract_call: false,
+        ..created.clone()
+    };
+    assert_eq!(decoder.decode(&failed).unwrap(), None);
+
+    let other = Decoder::new(&[], &[]);
+    assert_eq!(other.decode(&created).unwrap(), None);
+
+    let garbled = RpcEvent {
+        value: created.topic[0].clone(),
+        ..created
+    };
+    assert!(decoder.decode(&garbled).is_err());
+}
+
+#[test]
+fn test_page_from_result_or_error() {
+    let (page, _) = fixture();
+    let result = serde_json::to_string(&page).unwrap();
+    assert_eq!(EventPage::from_json(&result).unwrap(), page);
+
+    let error = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32600,"message":"bad"}}"#;
+    assert!(EventPage::from_json(error)
+        .unwrap_err()
+        .to_string()
+        .contains("bad"));
+}
+
+#[test]
+fn test_rpc_client_requests_events() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{FIXTURE}",
+            FIXTURE.len()
+        )
+        .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    });
+
+    let page = RpcClient::new(url)
+        .get_events(&Start::Cursor("42".to_string()), &["C1".to_string()], 10)
+        .unwrap();
+    assert_eq!(page, EventPage::from_json(FIXTURE).unwrap());
+
+    let request = server.join().unwrap();
+    assert_eq!(request["method"], "getEvents");
+    assert_eq!(request["params"]["pagination"]["cursor"], "42");
+    assert_eq!(request["params"]["pagination"]["limit"], 10);
+    assert_eq!(request["params"]["filters"][0]["contractIds"][0], "C1");
+    assert!(request["params"].get("startLedger").is_none());
+}
+
+/// Connect to the test database with the `main/prisma` migrations applied.
+fn database() -> Option<PgStore> {
+    let url = std::env::var(DATABASE_URL).ok()?;
+    let mut client = Client::connect(&url, NoTls).unwrap();
+    client
+        .batch_execute("DROP SCHEMA public CASCADE; CREATE SCHEMA public;")
+        .unwrap();
+    let migrations = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../../main/prisma/migrations"
+    );
+    let mut dirs: Vec<_> = std::fs::read_dir(migrations)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    for dir in dirs {
+        let sql = std::fs::read_to_string(dir.join("migration.sql")).unwrap();
+        client.batch_execute(&sql).unwrap();
+    }
+    Some(PgStore::new(client))
+}
+
+fn insert_order(store: &mut PgStore, order_hash: &str) {
+    store
+        .client()
+        .execute(
+            r#"INSERT INTO "orders" ("salt", "maker", "r", "vs", "orderHash", "updatedAt")
+               VALUES ('1', '0xmaker', '0x', '0x', $1, CURRENT_TIMESTAMP)"#,
+            &[&order_hash],
+        )
+        .unwrap();
+}
+
+fn escrow_row(store: &mut PgStore, order_hash: &str) -> (String, i64, i64, Option<String>) {
+    let row = store
+        .client()
+        .query_one(
+            r#"SELECT "status"::text, "releasedAmount"::bigint, "refundedAmount"::bigint,
+                   "safetyDepositClaimedBy"
+               FROM "stellar_escrows" WHERE "orderHash" = $1"#,
+            &[&order_hash],
+        )
+        .unwrap();
+    (row.get(0), row.get(1), row.get(2), row.get(3))
+}
+
+fn escrow_status(store: &mut PgStore, order_hash: &str) -> String {
+    store
+        .client()
+        .query_one(
+            r#"SELECT "status"::text FROM "stellar_escrows" WHERE "orderHash" = $1"#,
+            &[&order_hash],
+        )
+        .unwrap()
+        .get(0)
+}
+
+fn order_status(store: &mut PgStore, order_hash: &str) -> String {
+    store
+        .client()
+        .query_one(
+            r#"SELECT "status"::text FROM "orders" WHERE "orderHash" = $1"#,
+            &[&order_hash],
+        )
+        .unwrap()
+        .get(0)
+}
+
+fn count(store: &mut PgStore, table: &str) -> i64 {
+    store
+        .client()
+        .query_one(&format!(r#"SELECT COUNT(*) FROM "{table}""#), &[])
+        .unwrap()
+        .get(0)
+}
+
+/// Runs every Postgres case in turn, as each resets the shared database.
+#[test]
+fn test_postgres_store() {
+    let Some(mut store) = database() else {
+        eprintln!("{DATABASE_URL} is not set, skipping");
+        return;
+    };
+    apply_fixture(&mut store);
+    partial_claims(&mut store);
+}
+
+fn apply_fixture(store: &mut PgStore) {
+    let (page, decoder) = fixture();
+    let events = decode_page(&decoder, &page).unwrap();
+    let withdrawn = events[0].record.order_hash().to_string();
+    let cancelled = events[1].record.order_hash().to_string();
+    let public_cancelled = events[2].record.order_hash().to_string();
+    insert_order(store, &withdrawn);
+    insert_order(store, &cancelled);
+    insert_order(store, &public_cancelled);
+
+    assert_eq!(store.cursor("stellar").unwrap(), None);
+    store.apply("stellar", &events, page.next_cursor()).unwrap();
+
+    let (status, released, _, executor) = escrow_row(store, &withdrawn);
+    assert_eq!((status.as_str(), released), ("WITHDRAWN", 1_000));
+    assert!(executor.is_some());
+    assert_eq!(order_status(store, &withdrawn), "FILLED");
+
+    let (status, released, refunded, executor) = escrow_row(store, &cancelled);
+    assert_eq!(
+        (status.as_str(), released, refunded, executor),
+        ("CANCELLED", 0, 2_000, None)
+    );
+    assert_eq!(order_status(store, &cancelled), "CANCELLED");
+
+    let (status, released, refunded, executor) = escrow_row(store, &public_cancelled);
+    assert_eq!(
+        (status.as_str(), released, refunded),
+        ("PUBLIC_CANCELLED", 0, 3_000)
+    );
+    assert!(executor.is_some());
+    assert_eq!(order_status(store, &public_cancelled), "CANCELLED");
+
+    let fill = store
+        .client()
+        .query_one(
+            r#"SELECT "filledBy", "filledAmount"::bigint, "blockNumber" FROM "order_fills""#,
+            &[],
+        )
+        .unwrap();
+    let Record::Claimed { recipient, .. } = &events[4].record else {
+        panic!("expected a claim");
+    };
+    assert_eq!(fill.get::<_, String>(0), *recipient);
+    assert_eq!(fill.get::<_, i64>(1), 1_000);
+    assert_eq!(fill.get::<_, i32>(2), events[4].ledger as i32);
+    assert_eq!(count(store, "stellar_secrets"), 1);
+    assert_eq!(
+        store.cursor("stellar").unwrap().as_deref(),
+        page.next_cursor()
+    );
+
+    // Replaying a page changes nothing, even under another cursor name
+    store.apply("stellar", &events, page.next_cursor()).unwrap();
+    store.apply("replay", &events, None).unwrap();
+    assert_eq!(escrow_row(store, &withdrawn).1, 1_000);
+    assert_eq!(count(store, "order_fills"), 1);
+    assert_eq!(count(store, "stellar_escrow_fills"), 1);
+    assert_eq!(count(store, "stellar_secrets"), 1);
+    assert_eq!(store.cursor("replay").unwrap(), None);
+}
+
+fn partial_claims(store: &mut PgStore) {
+    let order_hash = format!("0x{}", hex::encode([3u8; 32]));
+    let event = |id: &str, record: Record| IndexedEvent {
+        id: id.to_string(),
+        ledger: 500,
+        tx_hash: "ab".repeat(32),
+        contract_id: "C".repeat(56),
+        record,
+    };
+    let claim = |id: &str, amount: i128| {
+        event(
+            id,
+            Record::Claimed {
+                order_hash: order_hash.clone(),
+                preimage: order_hash.clone(),
+                recipient: "G".repeat(56),
+                amount,
+            },
+        )
+    };
+    let created = event(
+        "0000000000000000500-0000000000",
+        Record::Created {
+            order_hash: order_hash.clone(),
+            maker: "G".repeat(56),
+            taker: "G".repeat(56),
+            receiver: "G".repeat(56),
+            token: "C".repeat(56),
+            // Wider than i64, as token amounts can be
+            amount: 1 << 80,
+            hashlock: order_hash.clone(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            is_src: true,
+            parts: 2,
+        },
+    );
+    insert_order(store, &order_hash);
+
+    store
+        .apply(
+            "partial",
+            &[created, claim("0000000000000000500-0000000001", 1 << 79)],
+            None,
+        )
+        .unwrap();
+    assert_eq!(escrow_status(store, &order_hash), "PARTIALLY_FILLED");
+    assert_eq!(order_status(store, &order_hash), "OPEN");
+
+    store
+        .apply(
+            "partial",
+            &[claim("0000000000000000500-0000000002", 1 << 79)],
+            None,
+        )
+        .unwrap();
+    assert_eq!(escrow_status(store, &order_hash), "WITHDRAWN");
+    assert_eq!(order_status(store, &order_hash), "FILLED");
+    let row = store
+        .client()
+        .query_one(
+            r#"SELECT "releasedAmount"::text, "hashAlgorithm" FROM "stellar_escrows"
+               WHERE "orderHash" = $1"#,
+            &[&order_hash],
+        )
+        .unwrap();
+    assert_eq!(
+        row.get::<_, String>(0),
+        format!("{}.000000000000000000", 1i128 << 80)
+    );
+    assert_eq!(row.get::<_, String>(1), "sha256");
+}
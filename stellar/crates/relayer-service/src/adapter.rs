@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use secret_tree::{hex32, HashAlgorithm};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// Lifecycle of an escrow as far as the relayer cares.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EscrowPhase {
+    /// Funds are locked, possibly with some parts already withdrawn
+    Active,
+    Withdrawn,
+    Cancelled,
+}
+
+/// What the secrets of an escrow have to hash to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Hashlock {
+    /// Hash of the secret, or the Merkle root of the fill level secrets when
+    /// `parts` is more than one
+    #[serde(with = "hex32")]
+    pub value: [u8; 32],
+    pub algorithm: HashAlgorithm,
+    pub parts: u32,
+}
+
+impl Hashlock {
+    /// Secrets that open the escrow: as on EVM, one per fill level
+    /// `0..=parts` when it is split, else just the one.
+    pub fn secret_count(&self) -> u32 {
+        if self.parts <= 1 {
+            1
+        } else {
+            self.parts + 1
+        }
+    }
+
+    /// Whether the secret at `index` releases the whole escrow.
+    pub fn completes(&self, index: u32) -> bool {
+        index + 1 >= self.parts
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EscrowState {
+    pub phase: EscrowPhase,
+    /// The escrow's finality lock has passed, so it can no longer be reorged
+    /// away and the secret may be revealed
+    pub finalized: bool,
+    pub hashlock: Hashlock,
+}
+
+/// Read access to the escrows of one chain.
+pub trait ChainAdapter {
+    /// State of the escrow `escrow_id`, `None` until it is created.
+    fn escrow(&self, escrow_id: &[u8; 32]) -> Result<Option<EscrowState>, Error>;
+}
+
+/// Calls `SecretRelayerContract::emit_secret_shared`.
+pub trait SecretPublisher {
+    /// Adapter name of the chain whose escrows the relayer contract checks.
+    fn chain(&self) -> &str;
+
+    /// Announce that the secret for `part_idx` of the escrow `escrow_id` on
+    /// [`chain`](Self::chain) was shared and return the id of the
+    /// transaction that did it.
+    fn share_secret(&mut self, escrow_id: &[u8; 32], part_idx: u32) -> Result<String, Error>;
+}
+
+/// In-memory chain for tests. Clones share their escrows, so a test can
+/// keep one to change escrows while the relayer reads another.
+#[derive(Clone, Default)]
+pub struct MockChain {
+    escrows: Rc<RefCell<HashMap<[u8; 32], EscrowState>>>,
+    failing: Rc<RefCell<bool>>,
+}
+
+impl MockChain {
+    pub fn set_escrow(&self, escrow_id: [u8; 32], state: EscrowState) {
+        self.escrows.borrow_mut().insert(escrow_id, state);
+    }
+
+    /// Make every read fail, like an unreachable node.
+    pub fn set_failing(&self, failing: bool) {
+        *self.failing.borrow_mut() = failing;
+    }
+}
+
+impl ChainAdapter for MockChain {
+    fn escrow(&self, escrow_id: &[u8; 32]) -> Result<Option<EscrowState>, Error> {
+        if *self.failing.borrow() {
+            return Err(Error::Chain("mock chain is unreachable".to_string()));
+        }
+        Ok(self.escrows.borrow().get(escrow_id).copied())
+    }
+}
+
+/// Publisher for tests that records each call.
+#[derive(Clone)]
+pub struct MockPublisher {
+    chain: String,
+    shared: Vec<([u8; 32], u32)>,
+}
+
+impl MockPublisher {
+    /// Publisher for the escrows of the adapter named `chain`.
+    pub fn new(chain: &str) -> Self {
+        Self {
+            chain: chain.to_string(),
+            shared: Vec::new(),
+        }
+    }
+
+    pub fn shared(&self) -> Vec<([u8; 32], u32)> {
+        self.shared.clone()
+    }
+}
+
+impl SecretPublisher for MockPublisher {
+    fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    fn share_secret(&mut self, escrow_id: &[u8; 32], part_idx: u32) -> Result<String, Error> {
+        self.shared.push((*escrow_id, part_idx));
+        Ok(format!("mock-{}", self.shared.len()))
+    }
+}
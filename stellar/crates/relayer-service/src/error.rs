@@ -0,0 +1,72 @@
+use core::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A chain adapter could not read an escrow
+    Chain(String),
+    Rpc(String),
+    Client(setu_client::Error),
+    Json(serde_json::Error),
+    Io(std::io::Error),
+    /// No adapter is registered for `chain`; `available` lists the ones that are
+    UnknownChain {
+        chain: String,
+        available: Vec<String>,
+    },
+    UnknownOrder,
+    InvalidOrder(String),
+    /// The order is already registered with other parameters
+    OrderConflict,
+    /// Secrets are only accepted once both escrows are final
+    NotReady,
+    /// The secret does not match the order's hashlock or Merkle root
+    InvalidSecret,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Chain(e) => write!(f, "chain error: {e}"),
+            Error::Rpc(e) => write!(f, "rpc error: {e}"),
+            Error::Client(e) => write!(f, "{e}"),
+            Error::Json(e) => write!(f, "json error: {e}"),
+            Error::Io(e) => write!(f, "io error: {e}"),
+            Error::UnknownChain { chain, available } => write!(
+                f,
+                "no adapter for chain {chain}, this relayer supports: {}",
+                available.join(", ")
+            ),
+            Error::UnknownOrder => write!(f, "order not found"),
+            Error::InvalidOrder(e) => write!(f, "invalid order: {e}"),
+            Error::OrderConflict => write!(f, "order already registered with other parameters"),
+            Error::NotReady => write!(f, "both escrows must be created and final first"),
+            Error::InvalidSecret => write!(f, "secret does not match the order"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<setu_client::Error> for Error {
+    fn from(e: setu_client::Error) -> Self {
+        Error::Client(e)
+    }
+}
+
+impl From<soroban_sdk::xdr::Error> for Error {
+    fn from(e: soroban_sdk::xdr::Error) -> Self {
+        Error::Client(e.into())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Mutex;
+
+use secret_tree::{hex32, hex32_list};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{Error, Hashlock, Order, OrderRequest, Phase, Relayer};
+
+/// Body of `POST /orders/{order_hash}/secrets`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecretSubmission {
+    #[serde(default)]
+    pub index: u32,
+    #[serde(with = "hex32")]
+    pub secret: [u8; 32],
+    /// Merkle proof of the secret's hash, for orders with several parts
+    #[serde(default, with = "hex32_list")]
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// What the API shows of an order, without unshared secrets.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderView {
+    #[serde(flatten)]
+    pub request: OrderRequest,
+    pub phase: Phase,
+    /// Hashlock of the escrows once the order is ready
+    pub hashlock: Option<Hashlock>,
+    /// Parts whose secret was shared on chain
+    pub shared: Vec<u32>,
+}
+
+impl From<&Order> for OrderView {
+    fn from(order: &Order) -> Self {
+        Self {
+            request: order.request.clone(),
+            phase: order.phase,
+            hashlock: order.hashlock,
+            shared: order.shared_parts(),
+        }
+    }
+}
+
+/// Serve the relayer's API until the server is closed:
+///
+/// - `POST /orders` registers an [`OrderRequest`]
+/// - `GET /orders/{order_hash}` returns its [`OrderView`]
+/// - `POST /orders/{order_hash}/secrets` takes a [`SecretSubmission`] from
+///   the maker once the order is `ready`
+/// - `GET /orders/{order_hash}/secrets/{index}` returns a shared secret
+///
+/// Makers hand over their secrets here, so bind it to a local address.
+pub fn serve(server: &Server, relayer: &Mutex<Relayer>) {
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let (status, value) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => handle(relayer, &request, &body),
+            Err(e) => error(Error::Io(e)),
+        };
+        let response = Response::from_string(value.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        // The client may have gone away, which is not the relayer's problem
+        let _ = request.respond(response);
+    }
+}
+
+fn error(e: Error) -> (u16, Value) {
+    let status = match e {
+        Error::UnknownOrder => 404,
+        Error::OrderConflict | Error::NotReady => 409,
+        Error::UnknownChain { .. }
+        | Error::InvalidOrder(_)
+        | Error::InvalidSecret
+        | Error::Json(_) => 400,
+        _ => 500,
+    };
+    (status, json!({ "error": e.to_string() }))
+}
+
+fn parse_hash(s: &str) -> Result<[u8; 32], Error> {
+    hex32::parse(s).map_err(|_| Error::UnknownOrder)
+}
+
+fn handle(relayer: &Mutex<Relayer>, request: &Request, body: &str) -> (u16, Value) {
+    let path: Vec<&str> = request.url().trim_matches('/').split('/').collect();
+    let mut relayer = relayer.lock().unwrap();
+    let result = match (request.method(), path.as_slice()) {
+        (Method::Post, ["orders"]) => serde_json::from_str(body)
+            .map_err(Error::from)
+            .and_then(|order| relayer.register(order))
+            .map(|order| (201, json!(OrderView::from(order)))),
+        (Method::Get, ["orders", hash]) => parse_hash(hash).and_then(|hash| {
+            let order = relayer.order(&hash).ok_or(Error::UnknownOrder)?;
+            Ok((200, json!(OrderView::from(order))))
+        }),
+        (Method::Post, ["orders", hash, "secrets"]) => parse_hash(hash).and_then(|hash| {
+            let submission: SecretSubmission = serde_json::from_str(body)?;
+            relayer.submit_secret(
+                &hash,
+                submission.index,
+                submission.secret,
+                &submission.proof,
+            )?;
+            Ok((202, json!({ "index": submission.index })))
+        }),
+        (Method::Get, ["orders", hash, "secrets", index]) => parse_hash(hash).and_then(|hash| {
+            let index: u32 = index.parse().map_err(|_| Error::UnknownOrder)?;
+            relayer.order(&hash).ok_or(Error::UnknownOrder)?;
+            Ok(match relayer.shared_secret(&hash, index) {
+                Some(entry) => (
+                    200,
+                    json!({
+                        "index": index,
+                        "secret": format!("0x{}", hex::encode(entry.secret)),
+                        "shared_tx": entry.shared_tx,
+                    }),
+                ),
+                None => (404, json!({ "error": "secret not shared yet" })),
+            })
+        }),
+        _ => Ok((404, json!({ "error": "not found" }))),
+    };
+    result.unwrap_or_else(error)
+}
@@ -0,0 +1,29 @@
+//! Relayer service for the secret-reveal step of a swap.
+//!
+//! A maker registers an order with the two chains its escrows live on and
+//! the id of each escrow. [`tick`] polls both for the [`Relayer`] through
+//! [`ChainAdapter`]s until the escrows exist and are past their finality
+//! locks, then takes the maker's secret over the local HTTP API in
+//! [`http`], announces it with `SecretRelayerContract::emit_secret_shared`
+//! through a [`SecretPublisher`], and serves it to resolvers. Every step is
+//! persisted to a [`StateFile`].
+//!
+//! [`StellarChain`] and [`StellarPublisher`] talk to a Stellar RPC server;
+//! [`MockChain`] and [`MockPublisher`] keep everything in memory for tests.
+//! Adapters for other chains implement [`ChainAdapter`].
+
+mod adapter;
+mod error;
+pub mod http;
+mod relayer;
+mod state;
+mod stellar;
+mod test;
+
+pub use crate::adapter::{
+    ChainAdapter, EscrowPhase, EscrowState, Hashlock, MockChain, MockPublisher, SecretPublisher,
+};
+pub use crate::error::Error;
+pub use crate::relayer::{order_key, tick, Relayer};
+pub use crate::state::{Order, OrderRequest, Phase, SecretEntry, StateFile};
+pub use crate::stellar::{Simulation, StellarChain, StellarPublisher, StellarRpc};
@@ -0,0 +1,120 @@
+//! `setu-relayer` relays secrets for orders between Stellar escrows.
+//!
+//! Orders are registered and secrets submitted over HTTP on `--listen`;
+//! escrows are read through `--rpc-url`, and secrets are announced with the
+//! relayer account of `--secret-key`, which the relayer contract's admin
+//! must have authorized. Orders and secrets are kept in `--state`.
+//!
+//! Only the `stellar` adapter ships with this binary, so both legs of an
+//! order must be Stellar escrows. Orders naming another chain are rejected
+//! with an error that lists the supported adapters. Cross-chain swaps need an
+//! adapter for the other chain, built on `ChainAdapter`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use ed25519_dalek::SigningKey;
+use setu_relayer::{
+    http, tick, ChainAdapter, Relayer, StateFile, StellarChain, StellarPublisher, StellarRpc,
+};
+use tiny_http::Server;
+
+const TESTNET_PASSPHRASE: &str = "Test SDF Network ; September 2015";
+/// Adapter name orders use for escrows on Stellar
+const STELLAR: &str = "stellar";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "setu-relayer",
+    about = "Relay swap secrets once both escrows are final",
+    long_about = "Relay swap secrets once both escrows are final.\n\n\
+        Only the `stellar` adapter is available: both escrows of an order must be on Stellar."
+)]
+struct Cli {
+    /// File the orders and submitted secrets are kept in
+    #[arg(long, default_value = "relayer-state.json")]
+    state: PathBuf,
+    /// Address of the HTTP API; makers send secrets to it, so keep it local
+    #[arg(long, default_value = "127.0.0.1:8787")]
+    listen: String,
+    #[arg(long, env = "SETU_RPC_URL")]
+    rpc_url: String,
+    #[arg(long, env = "SETU_NETWORK_PASSPHRASE", default_value = TESTNET_PASSPHRASE)]
+    network_passphrase: String,
+    /// Secret key (S...) of the authorized relayer account
+    #[arg(long, env = "SETU_SECRET_KEY", hide_env_values = true)]
+    secret_key: String,
+    /// Address of the EscrowContract
+    #[arg(long, env = "SETU_ESCROW_CONTRACT")]
+    escrow: String,
+    /// Address of the SecretRelayerContract
+    #[arg(long, env = "SETU_RELAYER_CONTRACT")]
+    relayer_contract: String,
+    /// Account the relayer was authorized under with `add_relayer`
+    #[arg(long)]
+    admin: String,
+    /// Seconds between checks of the open orders
+    #[arg(long, default_value_t = 5)]
+    poll_interval: u64,
+}
+
+/// Reject malformed addresses up front, contract types panic on them.
+fn check_address(address: &str) -> Result<()> {
+    stellar_strkey::Strkey::from_string(address)
+        .map(|_| ())
+        .map_err(|_| anyhow!("invalid address {address}"))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    for address in [&cli.escrow, &cli.relayer_contract, &cli.admin] {
+        check_address(address)?;
+    }
+    let key = stellar_strkey::ed25519::PrivateKey::from_string(&cli.secret_key)
+        .map_err(|_| anyhow!("invalid secret key"))?;
+    let key = SigningKey::from_bytes(&key.0);
+
+    let relayer = Arc::new(Mutex::new(Relayer::open(
+        StateFile::new(&cli.state),
+        &[STELLAR.to_string()],
+    )?));
+    let server =
+        Server::http(&cli.listen).map_err(|e| anyhow!("listening on {}: {e}", cli.listen))?;
+    {
+        let relayer = relayer.clone();
+        thread::spawn(move || http::serve(&server, &relayer));
+    }
+
+    let rpc = Rc::new(StellarRpc::new(&cli.rpc_url, &cli.network_passphrase));
+    let mut chains: HashMap<String, Box<dyn ChainAdapter>> = HashMap::new();
+    chains.insert(
+        STELLAR.to_string(),
+        Box::new(StellarChain::new(
+            rpc.clone(),
+            &cli.escrow,
+            key.verifying_key().to_bytes(),
+        )),
+    );
+    let mut publisher = StellarPublisher::new(
+        rpc,
+        key,
+        STELLAR,
+        &cli.admin,
+        &cli.relayer_contract,
+        &cli.escrow,
+    );
+
+    println!("listening on {}", cli.listen);
+    loop {
+        for (order, e) in tick(&relayer, &chains, &mut publisher) {
+            eprintln!("{order}: {e}");
+        }
+        thread::sleep(Duration::from_secs(cli.poll_interval));
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use secret_tree::{hash_secret, verify};
+
+use crate::{
+    ChainAdapter, Error, EscrowPhase, EscrowState, Order, OrderRequest, Phase, SecretEntry,
+    SecretPublisher, StateFile,
+};
+
+/// Key of an order in the state file and the HTTP API.
+pub fn order_key(order_hash: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(order_hash))
+}
+
+/// Orders being relayed and the rules for moving them along.
+///
+/// Every change is written to the state file before it is acknowledged, so
+/// a restarted relayer continues where it stopped. A crash between a
+/// successful `emit_secret_shared` and the write only makes the event be
+/// published twice.
+pub struct Relayer {
+    orders: BTreeMap<String, Order>,
+    state: StateFile,
+    chains: Vec<String>,
+}
+
+impl Relayer {
+    /// Load the orders in `state`, accepting new ones on the `chains` with
+    /// an adapter.
+    pub fn open(state: StateFile, chains: &[String]) -> Result<Self, Error> {
+        Ok(Self {
+            orders: state.load()?,
+            state,
+            chains: chains.to_vec(),
+        })
+    }
+
+    pub fn order(&self, order_hash: &[u8; 32]) -> Option<&Order> {
+        self.orders.get(&order_key(order_hash))
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values()
+    }
+
+    /// Start watching an order. Registering the same order again is a no-op.
+    pub fn register(&mut self, request: OrderRequest) -> Result<&Order, Error> {
+        for chain in [&request.src_chain, &request.dst_chain] {
+            if !self.chains.contains(chain) {
+                return Err(Error::UnknownChain {
+                    chain: chain.clone(),
+                    available: self.chains.clone(),
+                });
+            }
+        }
+        let same_escrow = request.src_chain == request.dst_chain
+            && request.src_escrow_id == request.dst_escrow_id;
+        if same_escrow {
+            return Err(Error::InvalidOrder(
+                "the source and destination escrows must differ".to_string(),
+            ));
+        }
+        let key = order_key(&request.order_hash);
+        match self.orders.get(&key) {
+            Some(order) if order.request != request => return Err(Error::OrderConflict),
+            Some(_) => {}
+            None => {
+                self.orders.insert(key.clone(), Order::new(request));
+                self.save()?;
+            }
+        }
+        Ok(&self.orders[&key])
+    }
+
+    /// Accept the maker's secret for fill level `index`, shared on the next
+    /// [`tick`]. `proof` is only needed for partial fills.
+    pub fn submit_secret(
+        &mut self,
+        order_hash: &[u8; 32],
+        index: u32,
+        secret: [u8; 32],
+        proof: &[[u8; 32]],
+    ) -> Result<(), Error> {
+        let key = order_key(order_hash);
+        let order = self.orders.get_mut(&key).ok_or(Error::UnknownOrder)?;
+        if order.phase != Phase::Ready {
+            return Err(Error::NotReady);
+        }
+
+        let hashlock = order.hashlock.ok_or(Error::NotReady)?;
+        let leaf = hash_secret(hashlock.algorithm, &secret);
+        let valid = if hashlock.parts == 1 {
+            index == 0 && leaf == hashlock.value
+        } else {
+            index < hashlock.secret_count() && verify(&hashlock.value, &leaf, index, proof)
+        };
+        if !valid {
+            return Err(Error::InvalidSecret);
+        }
+
+        if let Entry::Vacant(entry) = order.secrets.entry(index) {
+            entry.insert(SecretEntry {
+                secret,
+                shared_tx: None,
+            });
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Secret of part `index` once it was shared on chain, for resolvers.
+    pub fn shared_secret(&self, order_hash: &[u8; 32], index: u32) -> Option<&SecretEntry> {
+        self.order(order_hash)?
+            .secrets
+            .get(&index)
+            .filter(|entry| entry.shared_tx.is_some())
+    }
+
+    /// Orders still being relayed.
+    fn open_orders(&self) -> Vec<(String, OrderRequest)> {
+        self.orders
+            .iter()
+            .filter(|(_, order)| !order.is_finished())
+            .map(|(key, order)| (key.clone(), order.request.clone()))
+            .collect()
+    }
+
+    /// Move the order `key` along with the states read for its escrows and
+    /// return the indexes of the secrets waiting to be shared.
+    fn update(
+        &mut self,
+        key: &str,
+        src: Option<EscrowState>,
+        dst: Option<EscrowState>,
+    ) -> Result<Vec<u32>, Error> {
+        let order = &self.orders[key];
+        let phases = [src, dst].map(|escrow| escrow.map(|escrow| escrow.phase));
+        let phase = if phases.contains(&Some(EscrowPhase::Cancelled)) {
+            Phase::Cancelled
+        } else if phases == [Some(EscrowPhase::Withdrawn); 2] {
+            Phase::Done
+        } else if order.phase == Phase::Watching {
+            match (src, dst) {
+                // A secret could not open both escrows
+                (Some(src), Some(dst)) if src.hashlock != dst.hashlock => Phase::Rejected,
+                (Some(src), Some(dst)) if src.finalized && dst.finalized => Phase::Ready,
+                _ => Phase::Watching,
+            }
+        } else {
+            order.phase
+        };
+        if let (Phase::Ready, Some(src)) = (phase, src) {
+            let order = self.orders.get_mut(key).unwrap();
+            order.hashlock.get_or_insert(src.hashlock);
+        }
+        self.set_phase(key, phase)?;
+        if phase != Phase::Ready {
+            return Ok(Vec::new());
+        }
+
+        Ok(self.orders[key]
+            .secrets
+            .iter()
+            .filter(|(_, entry)| entry.shared_tx.is_none())
+            .map(|(index, _)| *index)
+            .collect())
+    }
+
+    /// Record that the secret at `index` was shared in `tx`.
+    fn record_shared(&mut self, key: &str, index: u32, tx: String) -> Result<(), Error> {
+        let order = self.orders.get_mut(key).unwrap();
+        order.secrets.get_mut(&index).unwrap().shared_tx = Some(tx);
+        self.save()?;
+
+        // Nothing is left to share once a secret for the full fill is out
+        let order = &self.orders[key];
+        let completed = order.hashlock.is_some_and(|hashlock| {
+            order
+                .shared_parts()
+                .into_iter()
+                .any(|index| hashlock.completes(index))
+        });
+        if completed {
+            self.set_phase(key, Phase::Done)?;
+        }
+        Ok(())
+    }
+
+    fn set_phase(&mut self, key: &str, phase: Phase) -> Result<(), Error> {
+        let order = self.orders.get_mut(key).unwrap();
+        if order.phase != phase {
+            order.phase = phase;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        self.state.save(&self.orders)
+    }
+}
+
+/// Check the escrows of every open order and share pending secrets.
+///
+/// `relayer` is only locked to read and record orders, never while waiting
+/// on a chain, so the HTTP API stays responsive. Failures of one order do
+/// not hold up the others; they are returned with the order key and the
+/// order is retried on the next tick.
+pub fn tick(
+    relayer: &Mutex<Relayer>,
+    chains: &HashMap<String, Box<dyn ChainAdapter>>,
+    publisher: &mut dyn SecretPublisher,
+) -> Vec<(String, Error)> {
+    let orders = relayer.lock().unwrap().open_orders();
+    let mut failures = Vec::new();
+    for (key, request) in orders {
+        if let Err(e) = advance(relayer, &key, &request, chains, publisher) {
+            failures.push((key, e));
+        }
+    }
+    failures
+}
+
+fn advance(
+    relayer: &Mutex<Relayer>,
+    key: &str,
+    request: &OrderRequest,
+    chains: &HashMap<String, Box<dyn ChainAdapter>>,
+    publisher: &mut dyn SecretPublisher,
+) -> Result<(), Error> {
+    let unknown = |chain: &str| {
+        let mut available: Vec<String> = chains.keys().cloned().collect();
+        available.sort();
+        Error::UnknownChain {
+            chain: chain.to_string(),
+            available,
+        }
+    };
+    let escrow = |chain: &String, escrow_id: &[u8; 32]| {
+        chains
+            .get(chain)
+            .ok_or_else(|| unknown(chain))?
+            .escrow(escrow_id)
+    };
+    let src = escrow(&request.src_chain, &request.src_escrow_id)?;
+    let dst = escrow(&request.dst_chain, &request.dst_escrow_id)?;
+    let pending = relayer.lock().unwrap().update(key, src, dst)?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    // The relayer contract checks the escrow on the publisher's chain
+    let escrow_id = if publisher.chain() == request.src_chain {
+        request.src_escrow_id
+    } else if publisher.chain() == request.dst_chain {
+        request.dst_escrow_id
+    } else {
+        return Err(unknown(publisher.chain()));
+    };
+    for index in pending {
+        let tx = publisher.share_secret(&escrow_id, index)?;
+        relayer.lock().unwrap().record_shared(key, index, tx)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use secret_tree::hex32;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Hashlock};
+
+/// Order a maker asks the relayer to watch.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OrderRequest {
+    /// Key the order is registered and looked up under
+    #[serde(with = "hex32")]
+    pub order_hash: [u8; 32],
+    /// Adapter name of the chain with the source escrow
+    pub src_chain: String,
+    pub dst_chain: String,
+    /// Id of the source escrow on `src_chain`, its order hash on Stellar
+    #[serde(with = "hex32")]
+    pub src_escrow_id: [u8; 32],
+    #[serde(with = "hex32")]
+    pub dst_escrow_id: [u8; 32],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Waiting for both escrows to be created and final
+    Watching,
+    /// Secrets are accepted and shared
+    Ready,
+    /// The secret for a full fill was shared or both escrows were withdrawn
+    Done,
+    /// An escrow was cancelled
+    Cancelled,
+    /// The two escrows are locked with different secrets
+    Rejected,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SecretEntry {
+    #[serde(with = "hex32")]
+    pub secret: [u8; 32],
+    /// Transaction that called `emit_secret_shared`, once it succeeded
+    pub shared_tx: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    #[serde(flatten)]
+    pub request: OrderRequest,
+    pub phase: Phase,
+    /// Hashlock of the escrows, read from them once the order is ready
+    pub hashlock: Option<Hashlock>,
+    /// Secrets the maker submitted, by part index
+    pub secrets: BTreeMap<u32, SecretEntry>,
+}
+
+impl Order {
+    pub fn new(request: OrderRequest) -> Self {
+        Self {
+            request,
+            phase: Phase::Watching,
+            hashlock: None,
+            secrets: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.phase, Phase::Done | Phase::Cancelled | Phase::Rejected)
+    }
+
+    /// Parts whose secret was shared on chain.
+    pub fn shared_parts(&self) -> Vec<u32> {
+        self.secrets
+            .iter()
+            .filter(|(_, entry)| entry.shared_tx.is_some())
+            .map(|(index, _)| *index)
+            .collect()
+    }
+}
+
+/// JSON file the orders are written to after every change.
+///
+/// It holds the makers' secrets, so it is only readable by its owner.
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<BTreeMap<String, Order>, Error> {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replace the file, going through a temporary file so a crash never
+    /// leaves it half written.
+    pub fn save(&self, orders: &BTreeMap<String, Order>) -> Result<(), Error> {
+        let tmp = self.path.with_extension("tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&tmp)?;
+        file.write_all(serde_json::to_string_pretty(orders)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
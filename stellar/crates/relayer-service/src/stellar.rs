@@ -0,0 +1,292 @@
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use ed25519_dalek::SigningKey;
+use escrow::{Escrow, EscrowStatus, Stage};
+use secret_tree::HashAlgorithm;
+use serde_json::{json, Value};
+use setu_client::{
+    from_sc_val, network_id, sign_transaction, EscrowCalls, Invocation, RelayerCalls,
+};
+use soroban_sdk::xdr::{
+    AccountId, LedgerEntryData, LedgerKey, LedgerKeyAccount, Limits, PublicKey, ReadXdr, ScVal,
+    SorobanAuthorizationEntry, SorobanTransactionData, TransactionEnvelope, TransactionV1Envelope,
+    Uint256, WriteXdr,
+};
+use soroban_sdk::{Address, BytesN, Env};
+
+use crate::{ChainAdapter, Error, EscrowPhase, EscrowState, Hashlock, SecretPublisher};
+
+/// Inclusion fee of the relayer's transactions, in stroops
+const FEE: u32 = 100;
+/// How often and how long a submitted transaction is polled for
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const POLL_ATTEMPTS: u32 = 30;
+
+fn rpc_error(e: impl std::fmt::Display) -> Error {
+    Error::Rpc(e.to_string())
+}
+
+/// Outcome of `simulateTransaction` for a single call.
+pub struct Simulation {
+    pub result: ScVal,
+    pub auth: Vec<SorobanAuthorizationEntry>,
+    pub transaction_data: SorobanTransactionData,
+}
+
+/// JSON-RPC client for the Stellar RPC methods the relayer needs.
+pub struct StellarRpc {
+    url: String,
+    network_id: [u8; 32],
+}
+
+impl StellarRpc {
+    pub fn new(url: impl Into<String>, network_passphrase: &str) -> Self {
+        Self {
+            url: url.into(),
+            network_id: network_id(network_passphrase),
+        }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response: Value = ureq::post(&self.url)
+            .send_json(request)
+            .map_err(rpc_error)?
+            .into_json()
+            .map_err(rpc_error)?;
+        if let Some(error) = response.get("error") {
+            return Err(Error::Rpc(error.to_string()));
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(Error::Rpc(format!("{method} returned no result"))),
+        }
+    }
+
+    /// Simulate `invocation` sent from the account of `source`.
+    pub fn simulate(
+        &self,
+        source: &[u8; 32],
+        invocation: &Invocation,
+    ) -> Result<Simulation, Error> {
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: invocation.transaction(source, 0, FEE, Vec::new(), None)?,
+            signatures: Default::default(),
+        });
+        let result = self.call(
+            "simulateTransaction",
+            json!({ "transaction": envelope.to_xdr_base64(Limits::none())? }),
+        )?;
+        if let Some(error) = result.get("error") {
+            return Err(Error::Rpc(format!("simulation failed: {error}")));
+        }
+
+        let field = |value: &Value, name: &str| -> Result<String, Error> {
+            value[name]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| Error::Rpc(format!("simulation is missing {name}")))
+        };
+        let call = &result["results"][0];
+        let auth = match call["auth"].as_array() {
+            Some(entries) => entries
+                .iter()
+                .map(|entry| {
+                    let entry = entry.as_str().unwrap_or_default();
+                    Ok(SorobanAuthorizationEntry::from_xdr_base64(
+                        entry,
+                        Limits::none(),
+                    )?)
+                })
+                .collect::<Result<_, Error>>()?,
+            None => Vec::new(),
+        };
+        Ok(Simulation {
+            result: ScVal::from_xdr_base64(field(call, "xdr")?, Limits::none())?,
+            auth,
+            transaction_data: SorobanTransactionData::from_xdr_base64(
+                field(&result, "transactionData")?,
+                Limits::none(),
+            )?,
+        })
+    }
+
+    /// Current sequence number of `account`.
+    pub fn sequence(&self, account: &[u8; 32]) -> Result<i64, Error> {
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(*account))),
+        });
+        let result = self.call(
+            "getLedgerEntries",
+            json!({ "keys": [key.to_xdr_base64(Limits::none())?] }),
+        )?;
+        let data = result["entries"][0]["xdr"]
+            .as_str()
+            .ok_or_else(|| Error::Rpc("account not found".to_string()))?;
+        match LedgerEntryData::from_xdr_base64(data, Limits::none())? {
+            LedgerEntryData::Account(account) => Ok(account.seq_num.0),
+            _ => Err(Error::Rpc("expected an account entry".to_string())),
+        }
+    }
+
+    /// Send `envelope` and wait until it is applied, returning its hash.
+    pub fn submit(&self, envelope: &TransactionEnvelope) -> Result<String, Error> {
+        let result = self.call(
+            "sendTransaction",
+            json!({ "transaction": envelope.to_xdr_base64(Limits::none())? }),
+        )?;
+        let hash = result["hash"].as_str().unwrap_or_default().to_string();
+        match result["status"].as_str() {
+            Some("PENDING") | Some("DUPLICATE") => {}
+            status => {
+                return Err(Error::Rpc(format!(
+                    "transaction {hash} was not accepted: {}",
+                    status.unwrap_or("no status")
+                )))
+            }
+        }
+
+        for _ in 0..POLL_ATTEMPTS {
+            let result = self.call("getTransaction", json!({ "hash": hash }))?;
+            match result["status"].as_str() {
+                Some("SUCCESS") => return Ok(hash),
+                Some("NOT_FOUND") => thread::sleep(POLL_INTERVAL),
+                status => {
+                    return Err(Error::Rpc(format!(
+                        "transaction {hash} failed: {}",
+                        status.unwrap_or("no status")
+                    )))
+                }
+            }
+        }
+        Err(Error::Rpc(format!(
+            "transaction {hash} was not applied in time"
+        )))
+    }
+}
+
+/// Reads escrows of an `EscrowContract` by simulating its getters.
+///
+/// Each call converts its values in a fresh `Env`, so the host's budget and
+/// objects don't build up over the life of the service.
+pub struct StellarChain {
+    rpc: Rc<StellarRpc>,
+    escrow: String,
+    /// Account the simulations are sent from
+    source: [u8; 32],
+}
+
+impl StellarChain {
+    pub fn new(rpc: Rc<StellarRpc>, escrow: &str, source: [u8; 32]) -> Self {
+        Self {
+            rpc,
+            escrow: escrow.to_string(),
+            source,
+        }
+    }
+}
+
+impl ChainAdapter for StellarChain {
+    fn escrow(&self, escrow_id: &[u8; 32]) -> Result<Option<EscrowState>, Error> {
+        let env = Env::default();
+        let calls = EscrowCalls::new(&env, &Address::from_str(&env, &self.escrow));
+        let order_hash = BytesN::from_array(&env, escrow_id);
+
+        let escrow = self
+            .rpc
+            .simulate(&self.source, &calls.get_escrow_info(&order_hash)?)?;
+        let Some(escrow) = from_sc_val::<Option<Escrow>>(&env, &escrow.result)? else {
+            return Ok(None);
+        };
+        let phase = match escrow.status {
+            EscrowStatus::Active | EscrowStatus::PartiallyFilled => EscrowPhase::Active,
+            EscrowStatus::Withdrawn => EscrowPhase::Withdrawn,
+            EscrowStatus::Cancelled | EscrowStatus::PublicCancelled => EscrowPhase::Cancelled,
+        };
+
+        let stage = self
+            .rpc
+            .simulate(&self.source, &calls.get_current_stage(&order_hash)?)?;
+        let stage: Stage = from_sc_val(&env, &stage.result)?;
+        Ok(Some(EscrowState {
+            phase,
+            finalized: !matches!(stage, Stage::BeforeFinality | Stage::Finality),
+            hashlock: Hashlock {
+                value: escrow.hashlock.value.to_array(),
+                algorithm: match escrow.hashlock.algorithm {
+                    escrow::HashAlgorithm::Keccak256 => HashAlgorithm::Keccak256,
+                    escrow::HashAlgorithm::Sha256 => HashAlgorithm::Sha256,
+                },
+                parts: escrow.parts,
+            },
+        }))
+    }
+}
+
+/// Calls `emit_secret_shared` on a `SecretRelayerContract`, signed by an
+/// authorized relayer account that also pays for the transaction.
+pub struct StellarPublisher {
+    rpc: Rc<StellarRpc>,
+    key: SigningKey,
+    chain: String,
+    admin: String,
+    contract: String,
+    escrow: String,
+}
+
+impl StellarPublisher {
+    /// `chain` is the adapter name orders use for the escrows of `escrow`,
+    /// and `admin` the account the relayer was authorized under.
+    pub fn new(
+        rpc: Rc<StellarRpc>,
+        key: SigningKey,
+        chain: &str,
+        admin: &str,
+        contract: &str,
+        escrow: &str,
+    ) -> Self {
+        Self {
+            rpc,
+            key,
+            chain: chain.to_string(),
+            admin: admin.to_string(),
+            contract: contract.to_string(),
+            escrow: escrow.to_string(),
+        }
+    }
+}
+
+impl SecretPublisher for StellarPublisher {
+    fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    fn share_secret(&mut self, escrow_id: &[u8; 32], part_idx: u32) -> Result<String, Error> {
+        let env = Env::default();
+        let source = self.key.verifying_key().to_bytes();
+        let relayer = stellar_strkey::ed25519::PublicKey(source).to_string();
+        let escrow = Address::from_str(&env, &self.escrow);
+        let invocation = RelayerCalls::new(&env, &Address::from_str(&env, &self.contract))
+            .emit_secret_shared(
+                &Address::from_str(&env, &relayer),
+                &Address::from_str(&env, &self.admin),
+                &escrow,
+                &BytesN::from_array(&env, escrow_id),
+                part_idx,
+                &escrow,
+            )?;
+        let simulation = self.rpc.simulate(&source, &invocation)?;
+        let sequence = self.rpc.sequence(&source)? + 1;
+        let tx = invocation.transaction(
+            &source,
+            sequence,
+            FEE,
+            simulation.auth,
+            Some(simulation.transaction_data),
+        )?;
+        let envelope = sign_transaction(tx, &self.rpc.network_id, &self.key)?;
+        self.rpc.submit(&envelope)
+    }
+}
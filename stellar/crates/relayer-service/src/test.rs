@@ -0,0 +1,825 @@
+#![cfg(test)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use ed25519_dalek::SigningKey;
+use escrow::{AllowedResolvers, CreateEscrowArgs, EscrowContract, EscrowContractClient};
+use relayer::{SecretRelayerContract, SecretRelayerContractClient, SecretSharedEvent};
+use secret_tree::{hash_secret, HashAlgorithm, SecretBundle};
+use serde_json::{json, Value};
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::token::StellarAssetClient;
+use soroban_sdk::xdr::{
+    AccountEntry, AccountEntryExt, AccountId, ExtensionPoint, HostFunction, LedgerEntryData,
+    LedgerFootprint, Limits, OperationBody, PublicKey, ReadXdr, ScAddress, ScVal, SequenceNumber,
+    SorobanResources, SorobanTransactionData, Thresholds, TransactionEnvelope,
+    TransactionV1Envelope, Uint256, WriteXdr,
+};
+use soroban_sdk::{Address, BytesN, Env, Symbol, TryFromVal, Val};
+use tiny_http::{Response, Server};
+
+use crate::{
+    http, order_key, ChainAdapter, Error, EscrowPhase, EscrowState, Hashlock, MockChain,
+    MockPublisher, OrderRequest, Phase, Relayer, SecretPublisher, StateFile, StellarChain,
+    StellarPublisher, StellarRpc,
+};
+
+const RESCUE_DELAY: u32 = 3600;
+const SECRET: [u8; 32] = [7u8; 32];
+const ORDER_HASH: [u8; 32] = [1u8; 32];
+
+/// State of an escrow locked with the hash of `SECRET`
+fn escrow_state(phase: EscrowPhase, finalized: bool) -> EscrowState {
+    EscrowState {
+        phase,
+        finalized,
+        hashlock: Hashlock {
+            value: hash_secret(HashAlgorithm::Keccak256, &SECRET),
+            algorithm: HashAlgorithm::Keccak256,
+            parts: 1,
+        },
+    }
+}
+
+fn created() -> EscrowState {
+    escrow_state(EscrowPhase::Active, false)
+}
+
+fn finalized() -> EscrowState {
+    escrow_state(EscrowPhase::Active, true)
+}
+
+fn cancelled() -> EscrowState {
+    escrow_state(EscrowPhase::Cancelled, true)
+}
+
+fn state_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("setu-relayer-{}-{name}", std::process::id()))
+}
+
+/// State file in a fresh temporary directory.
+fn state_file(name: &str) -> PathBuf {
+    let dir = state_dir(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("state.json")
+}
+
+struct Setup {
+    relayer: Mutex<Relayer>,
+    src: MockChain,
+    dst: MockChain,
+    chains: HashMap<String, Box<dyn ChainAdapter>>,
+    publisher: MockPublisher,
+}
+
+fn setup(name: &str) -> Setup {
+    let src = MockChain::default();
+    let dst = MockChain::default();
+    let mut chains: HashMap<String, Box<dyn ChainAdapter>> = HashMap::new();
+    chains.insert("stellar".to_string(), Box::new(src.clone()));
+    chains.insert("evm".to_string(), Box::new(dst.clone()));
+    let relayer = Relayer::open(
+        StateFile::new(state_file(name)),
+        &["stellar".to_string(), "evm".to_string()],
+    )
+    .unwrap();
+    Setup {
+        relayer: Mutex::new(relayer),
+        src,
+        dst,
+        chains,
+        publisher: MockPublisher::new("stellar"),
+    }
+}
+
+/// Escrow ids of the order registered as `order_hash`
+fn src_id(order_hash: [u8; 32]) -> [u8; 32] {
+    let mut id = order_hash;
+    id[31] = 0xaa;
+    id
+}
+
+fn dst_id(order_hash: [u8; 32]) -> [u8; 32] {
+    let mut id = order_hash;
+    id[31] = 0xbb;
+    id
+}
+
+fn request(order_hash: [u8; 32]) -> OrderRequest {
+    OrderRequest {
+        order_hash,
+        src_chain: "stellar".to_string(),
+        dst_chain: "evm".to_string(),
+        src_escrow_id: src_id(order_hash),
+        dst_escrow_id: dst_id(order_hash),
+    }
+}
+
+fn tick(s: &mut Setup) -> Vec<(String, Error)> {
+    crate::tick(&s.relayer, &s.chains, &mut s.publisher)
+}
+
+fn phase(s: &Setup, order_hash: &[u8; 32]) -> Phase {
+    s.relayer.lock().unwrap().order(order_hash).unwrap().phase
+}
+
+#[test]
+fn test_secret_shared_once_both_escrows_are_final() {
+    let mut s = setup("final");
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    tick(&mut s);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Watching);
+
+    s.src.set_escrow(src_id(ORDER_HASH), finalized());
+    s.dst.set_escrow(dst_id(ORDER_HASH), created());
+    tick(&mut s);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Watching);
+    assert!(matches!(
+        s.relayer
+            .lock()
+            .unwrap()
+            .submit_secret(&ORDER_HASH, 0, SECRET, &[]),
+        Err(Error::NotReady)
+    ));
+
+    s.dst.set_escrow(dst_id(ORDER_HASH), finalized());
+    tick(&mut s);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Ready);
+    assert!(matches!(
+        s.relayer
+            .lock()
+            .unwrap()
+            .submit_secret(&ORDER_HASH, 0, [8u8; 32], &[]),
+        Err(Error::InvalidSecret)
+    ));
+    s.relayer
+        .lock()
+        .unwrap()
+        .submit_secret(&ORDER_HASH, 0, SECRET, &[])
+        .unwrap();
+    assert!(s
+        .relayer
+        .lock()
+        .unwrap()
+        .shared_secret(&ORDER_HASH, 0)
+        .is_none());
+
+    tick(&mut s);
+    assert_eq!(s.publisher.shared(), vec![(src_id(ORDER_HASH), 0)]);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Done);
+    let relayer = s.relayer.lock().unwrap();
+    let shared = relayer.shared_secret(&ORDER_HASH, 0).unwrap();
+    assert_eq!(shared.secret, SECRET);
+    assert_eq!(shared.shared_tx.as_deref(), Some("mock-1"));
+    drop(relayer);
+
+    // Finished orders are left alone
+    tick(&mut s);
+    assert_eq!(s.publisher.shared().len(), 1);
+}
+
+#[test]
+fn test_secret_shared_for_escrow_on_publisher_chain() {
+    let mut s = setup("publisher-chain");
+    s.publisher = MockPublisher::new("evm");
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.src.set_escrow(src_id(ORDER_HASH), finalized());
+    s.dst.set_escrow(dst_id(ORDER_HASH), finalized());
+    tick(&mut s);
+    s.relayer
+        .lock()
+        .unwrap()
+        .submit_secret(&ORDER_HASH, 0, SECRET, &[])
+        .unwrap();
+    tick(&mut s);
+    assert_eq!(s.publisher.shared(), vec![(dst_id(ORDER_HASH), 0)]);
+}
+
+/// Publisher that checks the relayer is not locked while it submits.
+struct UnlockedPublisher {
+    relayer: Arc<Mutex<Relayer>>,
+    inner: MockPublisher,
+}
+
+impl SecretPublisher for UnlockedPublisher {
+    fn chain(&self) -> &str {
+        self.inner.chain()
+    }
+
+    fn share_secret(&mut self, escrow_id: &[u8; 32], part_idx: u32) -> Result<String, Error> {
+        assert!(self.relayer.try_lock().is_ok());
+        self.inner.share_secret(escrow_id, part_idx)
+    }
+}
+
+#[test]
+fn test_relayer_unlocked_while_sharing() {
+    let s = setup("unlocked");
+    let relayer = Arc::new(s.relayer);
+    let mut publisher = UnlockedPublisher {
+        relayer: relayer.clone(),
+        inner: MockPublisher::new("stellar"),
+    };
+    relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.src.set_escrow(src_id(ORDER_HASH), finalized());
+    s.dst.set_escrow(dst_id(ORDER_HASH), finalized());
+    crate::tick(&relayer, &s.chains, &mut publisher);
+    relayer
+        .lock()
+        .unwrap()
+        .submit_secret(&ORDER_HASH, 0, SECRET, &[])
+        .unwrap();
+
+    assert!(crate::tick(&relayer, &s.chains, &mut publisher).is_empty());
+    assert_eq!(publisher.inner.shared(), vec![(src_id(ORDER_HASH), 0)]);
+    assert!(relayer
+        .lock()
+        .unwrap()
+        .shared_secret(&ORDER_HASH, 0)
+        .is_some());
+}
+
+#[test]
+fn test_state_survives_restart() {
+    let mut s = setup("restart");
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.src.set_escrow(src_id(ORDER_HASH), finalized());
+    s.dst.set_escrow(dst_id(ORDER_HASH), finalized());
+    tick(&mut s);
+    s.relayer
+        .lock()
+        .unwrap()
+        .submit_secret(&ORDER_HASH, 0, SECRET, &[])
+        .unwrap();
+
+    let path = state_dir("restart").join("state.json");
+    s.relayer = Mutex::new(
+        Relayer::open(
+            StateFile::new(&path),
+            &["stellar".to_string(), "evm".to_string()],
+        )
+        .unwrap(),
+    );
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Ready);
+
+    tick(&mut s);
+    assert_eq!(s.publisher.shared(), vec![(src_id(ORDER_HASH), 0)]);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Done);
+
+    let reopened = Relayer::open(StateFile::new(&path), &[]).unwrap();
+    assert_eq!(reopened.order(&ORDER_HASH).unwrap().phase, Phase::Done);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn test_partial_fill_secrets() {
+    let mut s = setup("parts");
+    let bundle = SecretBundle::generate(3, HashAlgorithm::Keccak256).unwrap();
+    let state = |phase| EscrowState {
+        phase,
+        finalized: true,
+        hashlock: Hashlock {
+            value: bundle.merkle_root,
+            algorithm: HashAlgorithm::Keccak256,
+            parts: bundle.parts(),
+        },
+    };
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.src
+        .set_escrow(src_id(ORDER_HASH), state(EscrowPhase::Active));
+    s.dst
+        .set_escrow(dst_id(ORDER_HASH), state(EscrowPhase::Active));
+    tick(&mut s);
+
+    let [first, second, ..] = bundle.secrets.as_slice() else {
+        unreachable!()
+    };
+    assert!(matches!(
+        s.relayer
+            .lock()
+            .unwrap()
+            .submit_secret(&ORDER_HASH, 1, first.secret, &first.proof),
+        Err(Error::InvalidSecret)
+    ));
+    for part in [first, second] {
+        s.relayer
+            .lock()
+            .unwrap()
+            .submit_secret(&ORDER_HASH, part.index, part.secret, &part.proof)
+            .unwrap();
+    }
+    tick(&mut s);
+    assert_eq!(
+        s.publisher.shared(),
+        vec![(src_id(ORDER_HASH), 0), (src_id(ORDER_HASH), 1)]
+    );
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Ready);
+
+    // The order can be filled without using every secret
+    s.src
+        .set_escrow(src_id(ORDER_HASH), state(EscrowPhase::Withdrawn));
+    s.dst
+        .set_escrow(dst_id(ORDER_HASH), state(EscrowPhase::Withdrawn));
+    tick(&mut s);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Done);
+}
+
+#[test]
+fn test_final_fill_secret_completes_order() {
+    let mut s = setup("final_fill");
+    let bundle = SecretBundle::generate(3, HashAlgorithm::Keccak256).unwrap();
+    let state = EscrowState {
+        phase: EscrowPhase::Active,
+        finalized: true,
+        hashlock: Hashlock {
+            value: bundle.merkle_root,
+            algorithm: HashAlgorithm::Keccak256,
+            parts: bundle.parts(),
+        },
+    };
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.src.set_escrow(src_id(ORDER_HASH), state);
+    s.dst.set_escrow(dst_id(ORDER_HASH), state);
+    tick(&mut s);
+
+    let last = bundle.last();
+    assert!(matches!(
+        s.relayer.lock().unwrap().submit_secret(
+            &ORDER_HASH,
+            last.index + 1,
+            last.secret,
+            &last.proof
+        ),
+        Err(Error::InvalidSecret)
+    ));
+    s.relayer
+        .lock()
+        .unwrap()
+        .submit_secret(&ORDER_HASH, last.index, last.secret, &last.proof)
+        .unwrap();
+    tick(&mut s);
+    assert_eq!(s.publisher.shared(), vec![(src_id(ORDER_HASH), 3)]);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Done);
+}
+
+#[test]
+fn test_escrows_with_other_hashlocks_reject_order() {
+    let mut s = setup("rejected");
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    let mut other = finalized();
+    other.hashlock.value = hash_secret(HashAlgorithm::Keccak256, &[8u8; 32]);
+    s.src.set_escrow(src_id(ORDER_HASH), finalized());
+    s.dst.set_escrow(dst_id(ORDER_HASH), other);
+    tick(&mut s);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Rejected);
+    assert!(matches!(
+        s.relayer
+            .lock()
+            .unwrap()
+            .submit_secret(&ORDER_HASH, 0, SECRET, &[]),
+        Err(Error::NotReady)
+    ));
+}
+
+#[test]
+fn test_cancelled_escrow_stops_order() {
+    let mut s = setup("cancelled");
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.src.set_escrow(src_id(ORDER_HASH), finalized());
+    s.dst.set_escrow(dst_id(ORDER_HASH), cancelled());
+    tick(&mut s);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Cancelled);
+    assert!(matches!(
+        s.relayer
+            .lock()
+            .unwrap()
+            .submit_secret(&ORDER_HASH, 0, SECRET, &[]),
+        Err(Error::NotReady)
+    ));
+}
+
+#[test]
+fn test_failing_chain_does_not_block_other_orders() {
+    let mut s = setup("failing");
+    let other = [2u8; 32];
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(OrderRequest {
+            src_chain: "evm".to_string(),
+            dst_chain: "evm".to_string(),
+            ..request(other)
+        })
+        .unwrap();
+    s.dst.set_escrow(src_id(other), finalized());
+    s.dst.set_escrow(dst_id(other), finalized());
+    s.src.set_failing(true);
+
+    let failures = tick(&mut s);
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, order_key(&ORDER_HASH));
+    assert_eq!(phase(&s, &other), Phase::Ready);
+    assert_eq!(phase(&s, &ORDER_HASH), Phase::Watching);
+}
+
+#[test]
+fn test_register_checks_order() {
+    let s = setup("register");
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    s.relayer
+        .lock()
+        .unwrap()
+        .register(request(ORDER_HASH))
+        .unwrap();
+    assert!(matches!(
+        s.relayer.lock().unwrap().register(OrderRequest {
+            dst_escrow_id: [9u8; 32],
+            ..request(ORDER_HASH)
+        }),
+        Err(Error::OrderConflict)
+    ));
+    assert!(matches!(
+        s.relayer.lock().unwrap().register(OrderRequest {
+            dst_chain: "stellar".to_string(),
+            dst_escrow_id: src_id([2u8; 32]),
+            ..request([2u8; 32])
+        }),
+        Err(Error::InvalidOrder(_))
+    ));
+    let unknown = s
+        .relayer
+        .lock()
+        .unwrap()
+        .register(OrderRequest {
+            dst_chain: "solana".to_string(),
+            ..request([2u8; 32])
+        })
+        .err();
+    match unknown {
+        Some(e @ Error::UnknownChain { .. }) => assert_eq!(
+            e.to_string(),
+            "no adapter for chain solana, this relayer supports: stellar, evm"
+        ),
+        other => panic!("unexpected {other:?}"),
+    }
+    assert_eq!(s.relayer.lock().unwrap().orders().count(), 1);
+}
+
+#[test]
+fn test_http_api() {
+    let mut s = setup("http");
+    let relayer = Arc::new(Mutex::new(
+        Relayer::open(
+            StateFile::new(state_file("http-api")),
+            &["stellar".to_string(), "evm".to_string()],
+        )
+        .unwrap(),
+    ));
+    let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+    {
+        let relayer = relayer.clone();
+        let server = server.clone();
+        thread::spawn(move || http::serve(&server, &relayer));
+    }
+
+    let post = |path: &str, body: Value| match ureq::post(&format!("{url}{path}")).send_json(body) {
+        Ok(response) => (response.status(), response.into_json::<Value>().unwrap()),
+        Err(ureq::Error::Status(status, response)) => (status, response.into_json().unwrap()),
+        Err(e) => panic!("{e}"),
+    };
+    let get = |path: &str| match ureq::get(&format!("{url}{path}")).call() {
+        Ok(response) => (response.status(), response.into_json::<Value>().unwrap()),
+        Err(ureq::Error::Status(status, response)) => (status, response.into_json().unwrap()),
+        Err(e) => panic!("{e}"),
+    };
+
+    let order = order_key(&ORDER_HASH);
+    let (status, body) = post(
+        "/orders",
+        json!({
+            "order_hash": order,
+            "src_chain": "stellar",
+            "dst_chain": "evm",
+            "src_escrow_id": format!("0x{}", hex::encode(src_id(ORDER_HASH))),
+            "dst_escrow_id": format!("0x{}", hex::encode(dst_id(ORDER_HASH))),
+        }),
+    );
+    assert_eq!((status, body["phase"].as_str()), (201, Some("watching")));
+    assert!(body["hashlock"].is_null());
+
+    let secret = json!({ "secret": format!("0x{}", hex::encode(SECRET)) });
+    assert_eq!(
+        post(&format!("/orders/{order}/secrets"), secret.clone()).0,
+        409
+    );
+    assert_eq!(get(&format!("/orders/0x{}", hex::encode([9u8; 32]))).0, 404);
+    assert_eq!(post("/orders", json!({ "order_hash": order })).0, 400);
+
+    s.src.set_escrow(src_id(ORDER_HASH), finalized());
+    s.dst.set_escrow(dst_id(ORDER_HASH), finalized());
+    let tick = |s: &mut Setup| crate::tick(&relayer, &s.chains, &mut s.publisher);
+    tick(&mut s);
+    let body = get(&format!("/orders/{order}")).1;
+    assert_eq!(body["phase"], "ready");
+    assert_eq!(
+        body["hashlock"],
+        json!({
+            "value": format!("0x{}", hex::encode(hash_secret(HashAlgorithm::Keccak256, &SECRET))),
+            "algorithm": "keccak256",
+            "parts": 1,
+        })
+    );
+
+    let wrong = json!({ "secret": format!("0x{}", hex::encode([8u8; 32])) });
+    assert_eq!(post(&format!("/orders/{order}/secrets"), wrong).0, 400);
+    assert_eq!(post(&format!("/orders/{order}/secrets"), secret).0, 202);
+    let (status, body) = get(&format!("/orders/{order}/secrets/0"));
+    assert_eq!(
+        (status, body["error"].as_str()),
+        (404, Some("secret not shared yet"))
+    );
+
+    tick(&mut s);
+    let (status, body) = get(&format!("/orders/{order}/secrets/0"));
+    assert_eq!(status, 200);
+    assert_eq!(body["secret"], format!("0x{}", hex::encode(SECRET)));
+    assert_eq!(body["shared_tx"], "mock-1");
+    let body = get(&format!("/orders/{order}")).1;
+    assert_eq!(
+        (body["phase"].as_str(), &body["shared"]),
+        (Some("done"), &json!([0]))
+    );
+
+    server.unblock();
+}
+
+/// Order hash and part of a shared secret
+type Shared = ([u8; 32], u32);
+
+/// Serves the Stellar RPC methods the relayer uses from a test `Env` holding
+/// the escrow and relayer contracts.
+struct MockRpc {
+    env: Env,
+    relayer_key: SigningKey,
+    relayer_contract: Address,
+    /// Sequence number and shared secrets of each transaction sent
+    sent: Vec<(i64, Vec<Shared>)>,
+}
+
+const ACCOUNT_SEQUENCE: i64 = 41;
+
+impl MockRpc {
+    fn invoke(&self, envelope: &str) -> (TransactionV1Envelope, Val) {
+        let TransactionEnvelope::Tx(envelope) =
+            TransactionEnvelope::from_xdr_base64(envelope, Limits::none()).unwrap()
+        else {
+            panic!("expected a v1 envelope");
+        };
+        let OperationBody::InvokeHostFunction(op) = &envelope.tx.operations[0].body else {
+            panic!("expected an invoke host function operation");
+        };
+        let HostFunction::InvokeContract(call) = &op.host_function else {
+            panic!("expected a contract call");
+        };
+        let mut args = soroban_sdk::Vec::new(&self.env);
+        for arg in call.args.iter() {
+            args.push_back(Val::try_from_val(&self.env, arg).unwrap());
+        }
+        let result = self.env.invoke_contract::<Val>(
+            &Address::from_str(&self.env, &call.contract_address.to_string()),
+            &Symbol::new(&self.env, &call.function_name.to_utf8_string().unwrap()),
+            args,
+        );
+        (envelope, result)
+    }
+
+    fn handle(&mut self, request: &Value) -> Value {
+        let transaction = request["params"]["transaction"]
+            .as_str()
+            .unwrap_or_default();
+        match request["method"].as_str().unwrap() {
+            "simulateTransaction" => {
+                let (_, result) = self.invoke(transaction);
+                let result = ScVal::try_from_val(&self.env, &result).unwrap();
+                let data = SorobanTransactionData {
+                    ext: ExtensionPoint::V0,
+                    resources: SorobanResources {
+                        footprint: LedgerFootprint {
+                            read_only: Default::default(),
+                            read_write: Default::default(),
+                        },
+                        instructions: 0,
+                        read_bytes: 0,
+                        write_bytes: 0,
+                    },
+                    resource_fee: 500,
+                };
+                json!({
+                    "transactionData": data.to_xdr_base64(Limits::none()).unwrap(),
+                    "results": [{ "auth": [], "xdr": result.to_xdr_base64(Limits::none()).unwrap() }],
+                })
+            }
+            "getLedgerEntries" => {
+                let data = LedgerEntryData::Account(AccountEntry {
+                    account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+                        self.relayer_key.verifying_key().to_bytes(),
+                    ))),
+                    balance: 1_000_000_000,
+                    seq_num: SequenceNumber(ACCOUNT_SEQUENCE),
+                    num_sub_entries: 0,
+                    inflation_dest: None,
+                    flags: 0,
+                    home_domain: Default::default(),
+                    thresholds: Thresholds([1, 0, 0, 0]),
+                    signers: Default::default(),
+                    ext: AccountEntryExt::V0,
+                });
+                json!({ "entries": [{ "xdr": data.to_xdr_base64(Limits::none()).unwrap() }] })
+            }
+            "sendTransaction" => {
+                let (envelope, _) = self.invoke(transaction);
+                assert_eq!(envelope.signatures.len(), 1);
+                assert_eq!(envelope.tx.fee, 600);
+                let shared = self
+                    .env
+                    .events()
+                    .all()
+                    .iter()
+                    .filter(|(address, _, _)| *address == self.relayer_contract)
+                    .map(|(_, _, data)| {
+                        let event = SecretSharedEvent::try_from_val(&self.env, &data).unwrap();
+                        (event.order_hash.to_array(), event.part_idx)
+                    })
+                    .collect();
+                self.sent.push((envelope.tx.seq_num.0, shared));
+                json!({ "status": "PENDING", "hash": "ab".repeat(32) })
+            }
+            "getTransaction" => json!({ "status": "SUCCESS" }),
+            method => panic!("unexpected method {method}"),
+        }
+    }
+}
+
+fn strkey(address: &Address) -> String {
+    ScAddress::from(address).to_string()
+}
+
+#[test]
+fn test_stellar_adapters() {
+    let relayer_key = SigningKey::from_bytes(&[3u8; 32]);
+    let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+    let (addresses, received) = mpsc::channel();
+
+    let rpc_server = server.clone();
+    let rpc_key = relayer_key.clone();
+    let rpc = thread::spawn(move || {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().set_timestamp(1_000_000);
+
+        let maker = Address::generate(&env);
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        StellarAssetClient::new(&env, &token).mint(&maker, &1_000_000);
+        let escrow = EscrowContractClient::new(
+            &env,
+            &env.register(EscrowContract, (Address::generate(&env),)),
+        );
+        let order_hash = escrow.create_escrow(&CreateEscrowArgs {
+            maker,
+            taker: Address::generate(&env),
+            receiver: None,
+            token,
+            amount: 1_000,
+            bundle: soroban_sdk::Vec::new(&env),
+            safety_deposit: 100,
+            hashlock: BytesN::from_array(&env, &hash_secret(HashAlgorithm::Keccak256, &SECRET)),
+            hash_algorithm: escrow::HashAlgorithm::Keccak256,
+            rescue_delay: RESCUE_DELAY,
+            is_src: true,
+            merkle_root: BytesN::from_array(&env, &[0u8; 32]),
+            parts: 1,
+            resolvers: AllowedResolvers::Taker,
+            resolver_proof: soroban_sdk::Vec::new(&env),
+            src_cancellation: None,
+        });
+
+        let admin = Address::generate(&env);
+        let contract =
+            SecretRelayerContractClient::new(&env, &env.register(SecretRelayerContract, ()));
+        contract.initialize(&admin);
+        let relayer =
+            stellar_strkey::ed25519::PublicKey(rpc_key.verifying_key().to_bytes()).to_string();
+        contract.add_relayer(&admin, &Address::from_str(&env, &relayer));
+        addresses
+            .send((
+                order_hash.to_array(),
+                strkey(&escrow.address),
+                strkey(&contract.address),
+                strkey(&admin),
+            ))
+            .unwrap();
+
+        let mut mock = MockRpc {
+            env: env.clone(),
+            relayer_key: rpc_key,
+            relayer_contract: contract.address.clone(),
+            sent: Vec::new(),
+        };
+        for (served, mut request) in rpc_server.incoming_requests().enumerate() {
+            let body: Value = serde_json::from_reader(request.as_reader()).unwrap();
+            let result = mock.handle(&body);
+            // Let the escrow reach finality after the first status check
+            if served == 1 {
+                env.ledger().set_timestamp(1_000_000 + RESCUE_DELAY as u64);
+            }
+            let response = json!({ "jsonrpc": "2.0", "id": 1, "result": result });
+            request
+                .respond(Response::from_string(response.to_string()))
+                .unwrap();
+        }
+        mock.sent
+    });
+
+    let (order_hash, escrow, contract, admin) = received.recv().unwrap();
+    let rpc_client = Rc::new(StellarRpc::new(url, "Test SDF Network ; September 2015"));
+    let source = relayer_key.verifying_key().to_bytes();
+    let chain = StellarChain::new(rpc_client.clone(), &escrow, source);
+    assert_eq!(chain.escrow(&order_hash).unwrap(), Some(created()));
+    assert_eq!(chain.escrow(&order_hash).unwrap(), Some(finalized()));
+    assert_eq!(chain.escrow(&[9u8; 32]).unwrap(), None);
+
+    let mut publisher = StellarPublisher::new(
+        rpc_client,
+        relayer_key,
+        "stellar",
+        &admin,
+        &contract,
+        &escrow,
+    );
+    assert_eq!(
+        publisher.share_secret(&order_hash, 0).unwrap(),
+        "ab".repeat(32)
+    );
+
+    server.unblock();
+    assert_eq!(
+        rpc.join().unwrap(),
+        vec![(ACCOUNT_SEQUENCE + 1, vec![(order_hash, 0)])]
+    );
+}
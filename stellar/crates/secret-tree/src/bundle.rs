@@ -0,0 +1,236 @@
+use core::fmt;
+
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{hash_secret, verify, HashAlgorithm, MerkleTree};
+
+/// Most parts an escrow can be split into, the escrow's `MAX_PARTS`.
+pub const MAX_PARTS: u32 = 255;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A bundle needs at least one part
+    NoParts,
+    TooManyParts,
+    /// Bundles hold one secret, or `parts + 1` for two parts or more
+    SecretCount(usize),
+    /// A proof in a deserialized bundle does not lead to its root
+    InvalidProof(u32),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoParts => write!(f, "at least one part is required"),
+            Error::TooManyParts => write!(f, "at most {MAX_PARTS} parts are supported"),
+            Error::SecretCount(count) => {
+                write!(f, "{count} secrets do not match any number of parts")
+            }
+            Error::InvalidProof(index) => {
+                write!(f, "proof for part {index} does not match the root")
+            }
+            Error::Json(e) => write!(f, "json error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+/// Secret for one fill level with what a resolver needs to reveal it.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PartSecret {
+    /// `fill_index` passed to `withdraw`
+    pub index: u32,
+    #[serde(with = "hex32")]
+    pub secret: [u8; 32],
+    #[serde(with = "hex32")]
+    pub hash: [u8; 32],
+    #[serde(with = "hex32_list")]
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Secrets are left out so bundles can be logged.
+impl fmt::Debug for PartSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartSecret")
+            .field("index", &self.index)
+            .field("hash", &hex::encode(self.hash))
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SecretBundle {
+    pub hash_algorithm: HashAlgorithm,
+    #[serde(with = "hex32")]
+    pub merkle_root: [u8; 32],
+    pub secrets: Vec<PartSecret>,
+}
+
+impl SecretBundle {
+    /// Generate the secrets of an order filled in `parts` parts from the
+    /// operating system's random source.
+    pub fn generate(parts: u32, hash_algorithm: HashAlgorithm) -> Result<Self, Error> {
+        Self::generate_with_rng(&mut OsRng, parts, hash_algorithm)
+    }
+
+    pub fn generate_with_rng<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        parts: u32,
+        hash_algorithm: HashAlgorithm,
+    ) -> Result<Self, Error> {
+        check_parts(parts)?;
+        let secrets = (0..secret_count(parts))
+            .map(|_| {
+                let mut secret = [0u8; 32];
+                rng.fill_bytes(&mut secret);
+                secret
+            })
+            .collect();
+        Self::from_secrets(secrets, hash_algorithm)
+    }
+
+    /// Bundle for existing secrets, in fill index order.
+    pub fn from_secrets(
+        secrets: Vec<[u8; 32]>,
+        hash_algorithm: HashAlgorithm,
+    ) -> Result<Self, Error> {
+        parts_for(secrets.len())?;
+        let hashes: Vec<_> = secrets
+            .iter()
+            .map(|secret| hash_secret(hash_algorithm, secret))
+            .collect();
+        let tree = MerkleTree::new(&hashes);
+
+        Ok(Self {
+            hash_algorithm,
+            merkle_root: tree.root(),
+            secrets: secrets
+                .into_iter()
+                .zip(hashes)
+                .enumerate()
+                .map(|(index, (secret, hash))| PartSecret {
+                    index: index as u32,
+                    secret,
+                    hash,
+                    proof: tree.proof(index),
+                })
+                .collect(),
+        })
+    }
+
+    /// Value for the escrow's `parts`, one less than the number of secrets
+    /// when the order is split.
+    pub fn parts(&self) -> u32 {
+        parts_for(self.secrets.len()).unwrap()
+    }
+
+    /// Secret that fills the escrow completely in one go.
+    pub fn last(&self) -> &PartSecret {
+        self.secrets.last().unwrap()
+    }
+
+    pub fn part(&self, index: u32) -> Option<&PartSecret> {
+        self.secrets.get(index as usize)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a bundle and check that every part hashes and proves to its root.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let bundle: Self = serde_json::from_str(json)?;
+        parts_for(bundle.secrets.len())?;
+        for (position, part) in bundle.secrets.iter().enumerate() {
+            let valid = part.index as usize == position
+                && part.hash == hash_secret(bundle.hash_algorithm, &part.secret)
+                && verify(&bundle.merkle_root, &part.hash, part.index, &part.proof);
+            if !valid {
+                return Err(Error::InvalidProof(part.index));
+            }
+        }
+        Ok(bundle)
+    }
+}
+
+fn check_parts(parts: u32) -> Result<(), Error> {
+    match parts {
+        0 => Err(Error::NoParts),
+        parts if parts > MAX_PARTS => Err(Error::TooManyParts),
+        _ => Ok(()),
+    }
+}
+
+fn secret_count(parts: u32) -> u32 {
+    if parts == 1 {
+        1
+    } else {
+        parts + 1
+    }
+}
+
+/// Number of parts opened by `count` secrets.
+fn parts_for(count: usize) -> Result<u32, Error> {
+    let parts = match count {
+        0 => return Err(Error::NoParts),
+        1 => 1,
+        2 => return Err(Error::SecretCount(count)),
+        count => u32::try_from(count - 1).map_err(|_| Error::TooManyParts)?,
+    };
+    check_parts(parts)?;
+    Ok(parts)
+}
+
+/// Serde format of 32-byte values as `0x`-prefixed hex.
+pub mod hex32 {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse(&s).map_err(D::Error::custom)
+    }
+
+    pub fn parse(s: &str) -> Result<[u8; 32], String> {
+        let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+        bytes
+            .try_into()
+            .map_err(|_| "expected 32 bytes".to_string())
+    }
+}
+
+/// Serde format of lists of 32-byte values, see [`hex32`].
+pub mod hex32_list {
+    use serde::ser::SerializeSeq;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(list: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(list.len()))?;
+        for bytes in list {
+            seq.serialize_element(&format!("0x{}", hex::encode(bytes)))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; 32]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| super::hex32::parse(s).map_err(D::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,41 @@
+//! Secrets for escrows filled in parts.
+//!
+//! As on EVM, an order split into `n` parts uses `n + 1` secrets, one per
+//! fill level: index `i` fills the escrow up to `(i + 1) / n` of its amount
+//! and index `n` fills it completely. The escrow is created with `parts`
+//! set to `n` and commits to every secret through `merkle_root`, the root of
+//! a Keccak256 tree whose leaves are the secret hashes, which is also passed
+//! as its `hashlock`. A revealed secret is checked with
+//! `Verifier::<Keccak256>::verify_with_index`. [`SecretBundle`] generates
+//! the secrets, builds that tree and keeps the proof for every index, and
+//! serializes to JSON so the maker can hand it to a relayer.
+//!
+//! An order that is not split has a single secret, whose hash is the root.
+
+mod bundle;
+mod test;
+mod tree;
+
+pub use crate::bundle::{hex32, hex32_list, Error, PartSecret, SecretBundle, MAX_PARTS};
+pub use crate::tree::{hash_pair, verify, MerkleTree};
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+/// Hash applied to a secret, matching the escrow's `HashAlgorithm`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Keccak256,
+    Sha256,
+}
+
+/// Hash of a secret as the escrow computes it.
+pub fn hash_secret(algorithm: HashAlgorithm, secret: &[u8; 32]) -> [u8; 32] {
+    match algorithm {
+        HashAlgorithm::Keccak256 => Keccak256::digest(secret).into(),
+        HashAlgorithm::Sha256 => Sha256::digest(secret).into(),
+    }
+}
@@ -0,0 +1,226 @@
+#![cfg(test)]
+
+use escrow::{
+    AllowedResolvers, CreateEscrowArgs, EscrowContract, EscrowContractClient, EscrowError,
+    EscrowStatus,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::token::{StellarAssetClient, TokenClient};
+use soroban_sdk::{Address, BytesN, Env};
+
+use crate::{hash_secret, verify, Error, HashAlgorithm, MerkleTree, SecretBundle, MAX_PARTS};
+
+const RESCUE_DELAY: u32 = 3600;
+
+fn bundle(parts: u32, hash_algorithm: HashAlgorithm) -> SecretBundle {
+    SecretBundle::generate_with_rng(&mut StdRng::seed_from_u64(7), parts, hash_algorithm).unwrap()
+}
+
+/// Create an escrow for `bundle` and fill it at each index in `fills`,
+/// checking what each one pays out.
+fn fill_escrow(bundle: &SecretBundle, fills: &[(u32, i128)]) {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000_000);
+
+    let maker = Address::generate(&env);
+    let taker = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    StellarAssetClient::new(&env, &token).mint(&maker, &1_000_000);
+    let escrow = EscrowContractClient::new(
+        &env,
+        &env.register(EscrowContract, (Address::generate(&env),)),
+    );
+
+    let hash_algorithm = match bundle.hash_algorithm {
+        HashAlgorithm::Keccak256 => escrow::HashAlgorithm::Keccak256,
+        HashAlgorithm::Sha256 => escrow::HashAlgorithm::Sha256,
+    };
+    let order_hash = escrow.create_escrow(&CreateEscrowArgs {
+        maker: maker.clone(),
+        taker: taker.clone(),
+        receiver: None,
+        token: token.clone(),
+        amount: 1_000,
+        bundle: soroban_sdk::Vec::new(&env),
+        safety_deposit: 100,
+        hashlock: BytesN::from_array(&env, &bundle.merkle_root),
+        hash_algorithm,
+        rescue_delay: RESCUE_DELAY,
+        is_src: true,
+        merkle_root: BytesN::from_array(&env, &bundle.merkle_root),
+        parts: bundle.parts(),
+        resolvers: AllowedResolvers::Taker,
+        resolver_proof: soroban_sdk::Vec::new(&env),
+        src_cancellation: None,
+    });
+    env.ledger()
+        .set_timestamp(env.ledger().timestamp() + RESCUE_DELAY as u64);
+
+    let proof = |proof: &[[u8; 32]]| {
+        let mut nodes = soroban_sdk::Vec::new(&env);
+        for node in proof {
+            nodes.push_back(BytesN::from_array(&env, node));
+        }
+        nodes
+    };
+
+    // A secret does not open any other fill level
+    if let [first, second, ..] = bundle.secrets.as_slice() {
+        assert_eq!(
+            escrow.try_withdraw(
+                &order_hash,
+                &BytesN::from_array(&env, &first.secret),
+                &proof(&first.proof),
+                &second.index,
+            ),
+            Err(Ok(EscrowError::InvalidMerkleProof.into()))
+        );
+    }
+
+    let token = TokenClient::new(&env, &token);
+    for &(index, amount) in fills {
+        let part = bundle.part(index).unwrap();
+        let before = token.balance(&taker);
+        escrow.withdraw(
+            &order_hash,
+            &BytesN::from_array(&env, &part.secret),
+            &proof(&part.proof),
+            &part.index,
+        );
+        assert_eq!(token.balance(&taker) - before, amount);
+    }
+    assert_eq!(
+        escrow.get_status(&order_hash),
+        Some(EscrowStatus::Withdrawn)
+    );
+    assert_eq!(token.balance(&taker), 1_000);
+}
+
+#[test]
+fn test_proofs_for_every_size() {
+    for len in 1..=9u8 {
+        let leaves: Vec<[u8; 32]> = (0..len).map(|i| [i + 1; 32]).collect();
+        let tree = MerkleTree::new(&leaves);
+        assert_eq!(tree.len(), len as usize);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = tree.proof(index);
+            assert_eq!(1 << proof.len(), (len as usize).next_power_of_two());
+            assert!(verify(&tree.root(), leaf, index as u32, &proof));
+            assert!(!verify(&tree.root(), leaf, index as u32 + 1, &proof));
+        }
+    }
+}
+
+#[test]
+fn test_single_part_root_is_secret_hash() {
+    let bundle = bundle(1, HashAlgorithm::Keccak256);
+    let part = &bundle.secrets[0];
+    assert_eq!(
+        part.hash,
+        hash_secret(HashAlgorithm::Keccak256, &part.secret)
+    );
+    assert_eq!(bundle.merkle_root, part.hash);
+    assert!(part.proof.is_empty());
+}
+
+#[test]
+fn test_generated_secrets_are_distinct() {
+    let bundle = SecretBundle::generate(5, HashAlgorithm::Keccak256).unwrap();
+    assert_eq!(bundle.parts(), 5);
+    assert_eq!(bundle.secrets.len(), 6);
+    assert_eq!(bundle.last().index, 5);
+    for (i, a) in bundle.secrets.iter().enumerate() {
+        for b in &bundle.secrets[i + 1..] {
+            assert_ne!(a.secret, b.secret);
+        }
+    }
+}
+
+#[test]
+fn test_no_parts() {
+    assert!(matches!(
+        SecretBundle::generate(0, HashAlgorithm::Keccak256),
+        Err(Error::NoParts)
+    ));
+}
+
+#[test]
+fn test_parts_limited_like_escrow() {
+    assert_eq!(MAX_PARTS, escrow::MAX_PARTS);
+    assert!(matches!(
+        SecretBundle::generate(MAX_PARTS + 1, HashAlgorithm::Keccak256),
+        Err(Error::TooManyParts)
+    ));
+    let bundle = SecretBundle::generate(MAX_PARTS, HashAlgorithm::Keccak256).unwrap();
+    assert_eq!(bundle.parts(), MAX_PARTS);
+    assert_eq!(bundle.last().proof.len(), 8);
+}
+
+#[test]
+fn test_two_secrets_are_not_a_bundle() {
+    assert!(matches!(
+        SecretBundle::from_secrets(vec![[1u8; 32], [2u8; 32]], HashAlgorithm::Keccak256),
+        Err(Error::SecretCount(2))
+    ));
+}
+
+#[test]
+fn test_escrow_accepts_keccak_bundle() {
+    // Three parts take four secrets, the third one completes the fill
+    fill_escrow(
+        &bundle(3, HashAlgorithm::Keccak256),
+        &[(0, 333), (1, 333), (2, 334)],
+    );
+}
+
+#[test]
+fn test_escrow_accepts_sha256_bundle() {
+    fill_escrow(
+        &bundle(4, HashAlgorithm::Sha256),
+        &[(0, 250), (2, 500), (4, 250)],
+    );
+}
+
+#[test]
+fn test_escrow_filled_at_once_with_last_secret() {
+    // As on EVM, a fill of the remaining amount jumps to the final index
+    let bundle = bundle(4, HashAlgorithm::Keccak256);
+    fill_escrow(&bundle, &[(1, 500), (bundle.last().index, 500)]);
+}
+
+#[test]
+fn test_escrow_accepts_single_secret() {
+    fill_escrow(&bundle(1, HashAlgorithm::Keccak256), &[(0, 1_000)]);
+}
+
+#[test]
+fn test_json_round_trip() {
+    let bundle = bundle(3, HashAlgorithm::Sha256);
+    let json = bundle.to_json().unwrap();
+    assert!(json.contains("\"hash_algorithm\": \"sha256\""));
+    assert!(json.contains(&format!("\"0x{}\"", hex::encode(bundle.merkle_root))));
+    assert_eq!(SecretBundle::from_json(&json).unwrap(), bundle);
+}
+
+#[test]
+fn test_json_with_wrong_proof() {
+    let mut bundle = bundle(3, HashAlgorithm::Keccak256);
+    bundle.secrets[2].proof[1] = [0u8; 32];
+    assert!(matches!(
+        SecretBundle::from_json(&bundle.to_json().unwrap()),
+        Err(Error::InvalidProof(2))
+    ));
+}
+
+#[test]
+fn test_debug_hides_secrets() {
+    let bundle = bundle(1, HashAlgorithm::Keccak256);
+    let debug = format!("{bundle:?}");
+    assert!(!debug.contains(&hex::encode(bundle.secrets[0].secret)));
+    assert!(debug.contains(&hex::encode(bundle.secrets[0].hash)));
+}
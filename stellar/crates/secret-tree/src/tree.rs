@@ -0,0 +1,95 @@
+use sha3::{Digest, Keccak256};
+
+/// Parent of two nodes. Pairs are hashed in position order, not sorted,
+/// as `verify_with_index` expects.
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Check `proof` for `leaf` at `index` the same way the escrow does.
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], mut index: u32, proof: &[[u8; 32]]) -> bool {
+    if proof.len() >= 32 || index >= 1 << proof.len() {
+        return false;
+    }
+    let mut node = *leaf;
+    for sibling in proof {
+        node = if index.is_multiple_of(2) {
+            hash_pair(&node, sibling)
+        } else {
+            hash_pair(sibling, &node)
+        };
+        index /= 2;
+    }
+    node == *root
+}
+
+/// Keccak256 Merkle tree over a list of leaves.
+///
+/// Index-based verification needs every proof to have the same length, so
+/// the leaves are padded with zero hashes up to a power of two. No secret
+/// hashes to zero, so padding can never be opened.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MerkleTree {
+    /// Padded leaves first, the root last
+    layers: Vec<Vec<[u8; 32]>>,
+    len: usize,
+}
+
+impl MerkleTree {
+    /// # Panics
+    ///
+    /// If `leaves` is empty.
+    pub fn new(leaves: &[[u8; 32]]) -> Self {
+        assert!(!leaves.is_empty(), "a tree needs at least one leaf");
+
+        let mut layer = leaves.to_vec();
+        layer.resize(leaves.len().next_power_of_two(), [0u8; 32]);
+        let mut layers = vec![layer];
+        while layers.last().unwrap().len() > 1 {
+            let parents = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], &pair[1]))
+                .collect();
+            layers.push(parents);
+        }
+
+        Self {
+            layers,
+            len: leaves.len(),
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    /// Number of leaves, without padding.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Sibling of each node on the path from leaf `index` up to the root.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        assert!(index < self.len, "leaf index out of range");
+        let mut index = index;
+        let mut proof = Vec::with_capacity(self.layers.len() - 1);
+        for layer in &self.layers[..self.layers.len() - 1] {
+            proof.push(layer[index ^ 1]);
+            index /= 2;
+        }
+        proof
+    }
+}
@@ -0,0 +1,137 @@
+//! Safety checks for a pair of escrow schedules on two chains.
+//!
+//! The maker holds the secret and may reveal it on the destination chain at
+//! any point before the destination escrow can be cancelled. The resolver then
+//! needs enough time to see that withdrawal finalize and to land its own
+//! withdrawal on the source chain before the source escrow becomes
+//! cancellable. Otherwise the maker could be refunded on the source chain and
+//! still withdraw on the destination chain.
+//!
+//! All times are unix timestamps or durations in seconds. The crate is
+//! `no_std` and allocation free so contracts can use it directly.
+#![no_std]
+
+mod test;
+
+/// Source escrow deadlines, each the start of a stage.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SrcSchedule {
+    pub withdrawal: u64,
+    pub public_withdrawal: u64,
+    pub cancellation: u64,
+    pub public_cancellation: u64,
+}
+
+/// Destination escrow deadlines, each the start of a stage.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DstSchedule {
+    pub withdrawal: u64,
+    pub public_withdrawal: u64,
+    pub cancellation: u64,
+}
+
+/// How long each chain takes to make a transaction irreversible, plus the
+/// worst disagreement expected between the two chains' clocks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FinalityAssumptions {
+    pub src_finality: u64,
+    pub dst_finality: u64,
+    pub clock_drift: u64,
+}
+
+impl FinalityAssumptions {
+    /// Least time needed between the destination and source cancellations.
+    pub const fn required_margin(&self) -> u64 {
+        self.src_finality
+            .saturating_add(self.dst_finality)
+            .saturating_add(self.clock_drift)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum Violation {
+    /// Source stages do not start in order
+    SrcOutOfOrder = 1 << 0,
+    /// Destination stages do not start in order
+    DstOutOfOrder = 1 << 1,
+    /// The destination escrow opens for withdrawal before the source escrow
+    /// does, so the secret could be revealed before the source side is final
+    DstWithdrawalTooEarly = 1 << 2,
+    /// The destination escrow stays withdrawable too close to, or after, the
+    /// source cancellation
+    CancellationMarginTooSmall = 1 << 3,
+}
+
+impl Violation {
+    const ALL: [Violation; 4] = [
+        Violation::SrcOutOfOrder,
+        Violation::DstOutOfOrder,
+        Violation::DstWithdrawalTooEarly,
+        Violation::CancellationMarginTooSmall,
+    ];
+}
+
+/// Set of violations found by `validate`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Violations(u32);
+
+impl Violations {
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, violation: Violation) -> bool {
+        self.0 & violation as u32 != 0
+    }
+
+    pub fn insert(&mut self, violation: Violation) {
+        self.0 |= violation as u32;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Violation> + '_ {
+        Violation::ALL.into_iter().filter(|v| self.contains(*v))
+    }
+}
+
+/// Check that `dst_cancellation` leaves the required margin before
+/// `src_cancellation`.
+pub fn check_cancellation_margin(
+    src_cancellation: u64,
+    dst_cancellation: u64,
+    assumptions: &FinalityAssumptions,
+) -> Result<(), Violation> {
+    if dst_cancellation.saturating_add(assumptions.required_margin()) > src_cancellation {
+        return Err(Violation::CancellationMarginTooSmall);
+    }
+    Ok(())
+}
+
+/// Check a source and destination schedule against each other.
+pub fn validate(
+    src: &SrcSchedule,
+    dst: &DstSchedule,
+    assumptions: &FinalityAssumptions,
+) -> Violations {
+    let mut violations = Violations::default();
+
+    if !(src.withdrawal <= src.public_withdrawal
+        && src.public_withdrawal <= src.cancellation
+        && src.cancellation <= src.public_cancellation)
+    {
+        violations.insert(Violation::SrcOutOfOrder);
+    }
+    if !(dst.withdrawal <= dst.public_withdrawal && dst.public_withdrawal <= dst.cancellation) {
+        violations.insert(Violation::DstOutOfOrder);
+    }
+    if dst.withdrawal < src.withdrawal {
+        violations.insert(Violation::DstWithdrawalTooEarly);
+    }
+    if let Err(violation) =
+        check_cancellation_margin(src.cancellation, dst.cancellation, assumptions)
+    {
+        violations.insert(violation);
+    }
+
+    violations
+}
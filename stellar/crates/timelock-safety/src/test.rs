@@ -0,0 +1,95 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use std::vec::Vec;
+
+const ASSUMPTIONS: FinalityAssumptions = FinalityAssumptions {
+    src_finality: 900,
+    dst_finality: 60,
+    clock_drift: 60,
+};
+
+fn src() -> SrcSchedule {
+    SrcSchedule {
+        withdrawal: 1_000,
+        public_withdrawal: 5_000,
+        cancellation: 10_000,
+        public_cancellation: 12_000,
+    }
+}
+
+fn dst() -> DstSchedule {
+    DstSchedule {
+        withdrawal: 1_500,
+        public_withdrawal: 4_000,
+        cancellation: 8_000,
+    }
+}
+
+#[test]
+fn test_safe_schedules() {
+    assert!(validate(&src(), &dst(), &ASSUMPTIONS).is_empty());
+}
+
+#[test]
+fn test_required_margin() {
+    assert_eq!(ASSUMPTIONS.required_margin(), 1_020);
+    let src_cancellation = src().cancellation;
+
+    // Exactly the margin is enough, one second less is not
+    assert_eq!(
+        check_cancellation_margin(src_cancellation, src_cancellation - 1_020, &ASSUMPTIONS),
+        Ok(())
+    );
+    assert_eq!(
+        check_cancellation_margin(src_cancellation, src_cancellation - 1_019, &ASSUMPTIONS),
+        Err(Violation::CancellationMarginTooSmall)
+    );
+}
+
+#[test]
+fn test_dst_cancellation_after_src_cancellation() {
+    let dst = DstSchedule {
+        cancellation: 11_000,
+        ..dst()
+    };
+    let violations = validate(&src(), &dst, &ASSUMPTIONS);
+    assert_eq!(
+        violations.iter().collect::<Vec<_>>(),
+        [Violation::CancellationMarginTooSmall]
+    );
+}
+
+#[test]
+fn test_out_of_order_schedules() {
+    let src = SrcSchedule {
+        public_withdrawal: 11_000,
+        ..src()
+    };
+    let dst = DstSchedule {
+        withdrawal: 500,
+        public_withdrawal: 9_000,
+        ..dst()
+    };
+    let violations = validate(&src, &dst, &ASSUMPTIONS);
+    assert_eq!(
+        violations.iter().collect::<Vec<_>>(),
+        [
+            Violation::SrcOutOfOrder,
+            Violation::DstOutOfOrder,
+            Violation::DstWithdrawalTooEarly,
+        ]
+    );
+}
+
+#[test]
+fn test_margin_saturates() {
+    let assumptions = FinalityAssumptions {
+        src_finality: u64::MAX,
+        ..ASSUMPTIONS
+    };
+    assert_eq!(assumptions.required_margin(), u64::MAX);
+    assert!(validate(&src(), &dst(), &assumptions)
+        .contains(Violation::CancellationMarginTooSmall));
+}